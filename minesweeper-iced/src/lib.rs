@@ -3,19 +3,32 @@ use iced::{
     Animation, Color, Element, Length, Shadow, Subscription, Task, Theme,
     animation::Easing,
     border, padding, time,
-    widget::{Column, Row, button, column, container, pick_list, row, text},
+    widget::{Column, Row, button, checkbox, column, container, pick_list, row, text, text_input},
     window,
 };
 use minesweeper::{
-    history::{WinHistory, load_wins, save_win},
-    model::{Board, CellState, GameState, Pos},
+    history::{
+        DataDir, Format, Leaderboard, Preferences, SavedGame, Settings, Statistics, WinHistory,
+        create_profile, delete_profile, export_wins, import_wins, load_game, load_preferences,
+        load_settings, load_statistics, load_wins, profiles, record_game, remove_saved_game,
+        rename_profile, save_game, save_preferences, save_replay, save_win, update_settings,
+    },
+    model::{Board, BoardConfig, BoardEvent, Difficulty, GameState, GenerationMode, Pos},
+    replay::Replay,
+    solver::{Deduction, probabilities},
 };
 use modal::modal;
 use std::{
-    num::NonZeroU8,
+    env::temp_dir,
+    fmt::{self, Display, Formatter},
+    path::Path,
+    sync::mpsc::Receiver,
     time::{Duration, Instant},
 };
-use views::{CellView, Header, ScoreBoard, cell_view, mk_button_shadow};
+use views::{
+    CellView, Header, PLAYBACK_TICK, Playback, ScoreBoard, Speed, StatisticsView, cell_view,
+    format_time, mk_button_shadow, update_cell_views,
+};
 
 mod modal;
 mod views;
@@ -24,12 +37,44 @@ mod views;
 pub struct AppState {
     /// Game board.
     pub board: Board,
-    /// Active play timer.
-    elapsed_seconds: u64,
+    /// Events of the game board.
+    events: Receiver<BoardEvent>,
+    /// Selected difficulty level.
+    difficulty: Difficulty,
+    /// Selected mine generation mode.
+    generation: GenerationMode,
+    /// Play practice games that allow undo.
+    practice: bool,
+    /// Mark a flagged cell with a question mark when it is flagged again.
+    question_marks: bool,
+    /// Seed entered by the player.
+    seed_input: String,
+    /// Folder for the history files.
+    data_dir: DataDir,
+    /// Data folder entered by the player, empty for the default location.
+    data_dir_input: String,
+    /// Profiles in the data folder.
+    profiles: Vec<Profile>,
+    /// Profile name entered by the player.
+    profile_input: String,
+    /// Last hint given, cleared by the next move.
+    hint: Option<Deduction>,
+    /// Show mine probabilities over closed cells.
+    heatmap: bool,
     /// Win outcome.
     outcome: Option<String>,
     /// Scoreboard when viewing historic wins.
     scoreboard: Option<WinHistory>,
+    /// Leaderboard shown on the scoreboard.
+    leaderboard: Option<Leaderboard>,
+    /// File the wins are exported to or imported from.
+    wins_file: String,
+    /// Lifetime statistics when viewing them.
+    statistics: Option<Statistics>,
+    /// Game saved on the last exit that can be continued.
+    saved: Option<SavedGame>,
+    /// Replay being played back instead of the game.
+    playback: Option<Playback>,
     /// Game cells.
     cells: Vec<CellView>,
     /// Current instant.
@@ -40,6 +85,16 @@ pub struct AppState {
     pub theme: Theme,
 }
 
+/// Player profile, the anonymous profile when it has no name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile(Option<String>);
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_deref().unwrap_or("Anonymous"))
+    }
+}
+
 /// Application messages.
 #[derive(Debug, Clone)]
 pub enum AppMsg {
@@ -47,6 +102,8 @@ pub enum AppMsg {
     Open(Pos),
     /// Flag a cell via its position.
     Flag(Pos),
+    /// Open the neighbours of a cell via its position.
+    Chord(Pos),
     /// Timer tick.
     Tick,
     /// Restart the game.
@@ -57,28 +114,130 @@ pub enum AppMsg {
     ViewScoreBoard,
     /// Dismiss the scoreboard.
     DismissScoreBoard,
+    /// Select the leaderboard shown on the scoreboard.
+    Leaderboard(Leaderboard),
+    /// Wins file input changed.
+    WinsFileInput(String),
+    /// Export the wins to the entered file.
+    ExportWins,
+    /// Import the wins from the entered file.
+    ImportWins,
+    /// View the lifetime statistics.
+    ViewStatistics,
+    /// Dismiss the lifetime statistics.
+    DismissStatistics,
     /// No-op.
     None,
     /// Render for animation. No-op.
     Animate,
     /// Select theme
     Theme(Theme),
+    /// Select difficulty and restart the game.
+    Difficulty(Difficulty),
+    /// Seed input changed.
+    SeedInput(String),
+    /// Restart the game with the entered seed.
+    PlaySeed,
+    /// Data folder input changed.
+    DataDirInput(String),
+    /// Use the entered data folder and save it in the settings.
+    SetDataDir,
+    /// Switch to the profile and save it in the settings.
+    Profile(Profile),
+    /// Profile name input changed.
+    ProfileInput(String),
+    /// Create a profile with the entered name and switch to it.
+    CreateProfile,
+    /// Rename the active profile to the entered name.
+    RenameProfile,
+    /// Delete the active profile and switch to the anonymous profile.
+    DeleteProfile,
+    /// Toggle no guess generation and restart the game.
+    NoGuess(bool),
+    /// Toggle practice mode and restart the game.
+    Practice(bool),
+    /// Toggle question marks and restart the game.
+    QuestionMarks(bool),
+    /// Take back the last move.
+    Undo,
+    /// Play the last undone move again.
+    Redo,
+    /// Show a cell that can be deduced safe or mined.
+    Hint,
+    /// Toggle the mine probability heatmap.
+    Heatmap(bool),
+    /// Continue the saved game.
+    Continue,
+    /// Discard the saved game.
+    DiscardSaved,
+    /// Save the game and exit.
+    CloseRequested(window::Id),
+    /// Play back the finished game.
+    ReplayGame,
+    /// Play back the win at the rank index of the shown leaderboard.
+    ReplayWin(usize),
+    /// Pause or resume playback.
+    PlaybackToggle,
+    /// Play the next move of the replay.
+    PlaybackStep,
+    /// Select the playback speed.
+    PlaybackSpeed(Speed),
+    /// Playback timer tick.
+    PlaybackTick,
+    /// Stop playback and return to the game.
+    PlaybackClose,
 }
 
 impl AppState {
     /// Create a new application state.
     fn new() -> Self {
-        let board = mk_board();
+        let settings = load_settings().unwrap_or_else(|err| {
+            eprintln!("Failed to load settings: {err}");
+            Settings::default()
+        });
+        let data_dir = mk_data_dir(&settings);
+        let preferences = mk_preferences(&data_dir).unwrap_or(Preferences {
+            difficulty: Difficulty::Expert,
+            generation: GenerationMode::Random,
+            question_marks: false,
+        });
+        let difficulty = preferences.difficulty;
+        let generation = preferences.generation;
+        let question_marks = preferences.question_marks;
+        let mut board = mk_board(difficulty, generation, false, question_marks, None);
+        let events = board.subscribe();
         let now = Instant::now();
         Self {
+            difficulty,
+            generation,
+            practice: false,
+            question_marks,
+            seed_input: String::new(),
+            saved: load_game(&data_dir).unwrap_or_else(|err| {
+                eprintln!("Failed to load saved game: {err}");
+                None
+            }),
+            profiles: mk_profiles(&data_dir),
+            profile_input: String::new(),
+            data_dir,
+            data_dir_input: settings
+                .data_dir
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            hint: None,
+            heatmap: false,
             cells: board
                 .positions()
-                .map(|(pos, cell)| cell_view(*cell, *pos, *board.state(), now))
+                .map(|(pos, cell)| cell_view(*cell, pos, *board.state(), now))
                 .collect(),
             board,
-            elapsed_seconds: 0,
+            events,
             outcome: None,
             scoreboard: None,
+            statistics: None,
+            leaderboard: None,
+            wins_file: String::new(),
+            playback: None,
             now,
             modal_animation: mk_modal_animation(),
             theme: Theme::TokyoNight,
@@ -89,6 +248,12 @@ impl AppState {
     pub fn subscription(&self) -> Subscription<AppMsg> {
         // Check if any of our animations are active.
         let is_animating = self.cells.iter().any(|cell| cell.is_animating(self.now))
+            || self.playback.as_ref().is_some_and(|playback| {
+                playback
+                    .cells
+                    .iter()
+                    .any(|cell| cell.is_animating(self.now))
+            })
             || self.modal_animation.is_animating(self.now);
 
         fn maybe_subscription<T>(b: bool, f: impl FnOnce() -> Subscription<T>) -> Subscription<T> {
@@ -99,108 +264,469 @@ impl AppState {
             maybe_subscription(matches!(self.board.state(), GameState::Active), || {
                 time::every(Duration::from_secs(1)).map(|_| AppMsg::Tick)
             }),
+            maybe_subscription(
+                self.playback
+                    .as_ref()
+                    .is_some_and(|playback| playback.playing),
+                || time::every(PLAYBACK_TICK).map(|_| AppMsg::PlaybackTick),
+            ),
             maybe_subscription(is_animating, || window::frames().map(|_| AppMsg::Animate)),
+            window::close_requests().map(AppMsg::CloseRequested),
         ])
     }
 
     /// Update game application view state.
     pub fn update(&mut self, message: AppMsg, instant: Instant) -> Task<AppMsg> {
         self.now = instant;
-        self.cells.iter_mut().for_each(|cell_view| {
-            cell_view.now = instant;
-        });
+        self.cells
+            .iter_mut()
+            .chain(self.playback.iter_mut().flat_map(|p| p.cells.iter_mut()))
+            .for_each(|cell_view| {
+                cell_view.now = instant;
+            });
+
+        // The game can't be played while a replay is shown.
+        if self.playback.is_some()
+            && matches!(
+                message,
+                AppMsg::Open(_) | AppMsg::Flag(_) | AppMsg::Chord(_) | AppMsg::Hint
+            )
+        {
+            return Task::none();
+        }
+
+        // A hint only applies to the board it was given for.
+        if matches!(
+            message,
+            AppMsg::Open(_) | AppMsg::Flag(_) | AppMsg::Chord(_) | AppMsg::Undo | AppMsg::Redo
+        ) {
+            self.clear_hint();
+        }
 
         match message {
             AppMsg::Open(pos)
                 if matches!(self.board.state(), GameState::Active | GameState::New) =>
             {
-                self.board.open_cell(pos);
-
-                let game_state = self.board.state();
-
-                // Update cell state.
-                for (cell_view, (_pos, cell)) in self.cells.iter_mut().zip(self.board.positions()) {
-                    // Enable open animation for all opened cells.
-                    if let (CellState::Closed { .. }, CellState::Open) =
-                        (cell_view.cell.state, cell.state)
-                    {
-                        cell_view.open();
-                    }
-
-                    if let (CellState::Closed { .. }, CellState::ExposedMine) =
-                        (cell_view.cell.state, cell.state)
-                    {
-                        cell_view.detonate();
-                    }
-                    cell_view.game_state = *game_state;
-                    cell_view.cell = *cell;
-                }
-
-                if matches!(self.board.state(), GameState::Win) {
-                    self.outcome = Some("You won!".into());
-                    self.modal_animation.go_mut(true, self.now);
-                    if let Err(err) = save_win(self.elapsed_seconds) {
-                        eprintln!("Failed to save win: {err}");
+                match self.board.open_cell(pos) {
+                    Ok(_) => self.sync_opened_cells(),
+                    Err(err) => {
+                        self.outcome = Some(err.to_string());
+                        self.modal_animation.go_mut(true, self.now);
                     }
                 }
             }
+            AppMsg::Chord(pos) if matches!(self.board.state(), GameState::Active) => {
+                self.board.chord(pos);
+                self.sync_opened_cells();
+            }
             AppMsg::Flag(pos) if matches!(self.board.state(), GameState::Active) => {
                 self.board.flag_cell(pos);
-
-                // Update cell state.
-                for (cell_view, (_pos, cell)) in self.cells.iter_mut().zip(self.board.positions()) {
-                    // Enabled flagged animation for flagged cell.
-                    if let (
-                        CellState::Closed { flagged: false, .. },
-                        CellState::Closed { flagged: true, .. },
-                    ) = (cell_view.cell.state, cell.state)
-                    {
-                        cell_view.flag();
-                    }
-                    cell_view.cell = *cell;
-                }
-            }
-            AppMsg::Tick => {
-                // Pause timer when viewing scoreboard.
-                if self.scoreboard.is_none() {
-                    self.elapsed_seconds += 1;
-                }
+                self.update_cells();
+                self.update_heatmap();
             }
             AppMsg::Restart => {
-                self.elapsed_seconds = 0;
-                self.board = mk_board();
-                self.cells = self
-                    .board
-                    .positions()
-                    .map(|(pos, cell)| cell_view(*cell, *pos, *self.board.state(), self.now))
-                    .collect();
-                self.outcome = None;
-                self.modal_animation = mk_modal_animation();
+                self.restart(None);
             }
             AppMsg::DismissModal => {
                 self.outcome = None;
             }
             AppMsg::ViewScoreBoard => {
-                self.scoreboard = load_wins();
+                self.scoreboard = load_wins(&self.data_dir)
+                    .inspect_err(|err| eprintln!("Failed to load wins: {err}"))
+                    .ok();
+                self.leaderboard = self
+                    .scoreboard
+                    .as_ref()
+                    .and_then(|history| history.default_leaderboard(self.board.config()));
+            }
+            AppMsg::Leaderboard(leaderboard) => {
+                self.leaderboard = Some(leaderboard);
             }
             AppMsg::DismissScoreBoard => {
                 self.scoreboard = None;
             }
+            AppMsg::WinsFileInput(input) => {
+                self.wins_file = input;
+            }
+            AppMsg::ExportWins => {
+                if let Some(history) = &self.scoreboard {
+                    let path = Path::new(self.wins_file.trim());
+                    if let Err(err) =
+                        Format::of(path).and_then(|format| export_wins(history, path, format))
+                    {
+                        eprintln!("Failed to export wins: {err}");
+                    }
+                }
+            }
+            AppMsg::ImportWins => {
+                let path = Path::new(self.wins_file.trim());
+                match Format::of(path).and_then(|format| import_wins(&self.data_dir, path, format))
+                {
+                    Ok(_) => match load_wins(&self.data_dir) {
+                        Ok(history) => self.scoreboard = Some(history),
+                        Err(err) => eprintln!("Failed to load wins: {err}"),
+                    },
+                    Err(err) => eprintln!("Failed to import wins: {err}"),
+                }
+            }
+            AppMsg::ViewStatistics => match load_statistics(&self.data_dir) {
+                Ok(statistics) => self.statistics = Some(statistics),
+                Err(err) => eprintln!("Failed to load statistics: {err}"),
+            },
+            AppMsg::DismissStatistics => {
+                self.statistics = None;
+            }
             AppMsg::Theme(theme) => {
                 self.theme = theme;
             }
+            AppMsg::Difficulty(difficulty) => {
+                self.difficulty = difficulty;
+                self.save_board_preferences();
+                self.restart(None);
+            }
+            AppMsg::SeedInput(input) => {
+                self.seed_input = input;
+            }
+            AppMsg::DataDirInput(input) => {
+                self.data_dir_input = input;
+            }
+            AppMsg::SetDataDir => {
+                let path = self.data_dir_input.trim();
+                let settings = Settings {
+                    data_dir: (!path.is_empty()).then(|| path.into()),
+                    profile: self.data_dir.profile().map(ToOwned::to_owned),
+                };
+                self.switch_data_dir(mk_data_dir(&settings));
+                if let Err(err) = update_settings(|saved| saved.data_dir = settings.data_dir) {
+                    eprintln!("Failed to save settings: {err}");
+                }
+            }
+            AppMsg::Profile(Profile(profile)) => {
+                match self.data_dir.clone().with_profile(profile.as_deref()) {
+                    Ok(data_dir) => {
+                        self.switch_data_dir(data_dir);
+                        self.save_profile();
+                    }
+                    Err(err) => eprintln!("Failed to switch profile: {err}"),
+                }
+            }
+            AppMsg::ProfileInput(input) => {
+                self.profile_input = input;
+            }
+            AppMsg::CreateProfile => {
+                let name = self.profile_input.trim().to_owned();
+                match create_profile(&self.data_dir, &name) {
+                    Ok(()) => {
+                        self.profile_input.clear();
+                        return self.update(AppMsg::Profile(Profile(Some(name))), instant);
+                    }
+                    Err(err) => eprintln!("Failed to create profile: {err}"),
+                }
+            }
+            AppMsg::RenameProfile => {
+                let name = self.profile_input.trim();
+                if let Some(profile) = self.data_dir.profile() {
+                    let renamed = rename_profile(&self.data_dir, profile, name)
+                        .and_then(|()| self.data_dir.clone().with_profile(Some(name)));
+                    match renamed {
+                        Ok(data_dir) => {
+                            self.data_dir = data_dir;
+                            self.profiles = mk_profiles(&self.data_dir);
+                            self.profile_input.clear();
+                            self.save_profile();
+                        }
+                        Err(err) => eprintln!("Failed to rename profile: {err}"),
+                    }
+                }
+            }
+            AppMsg::DeleteProfile => {
+                if let Some(profile) = self.data_dir.profile().map(ToOwned::to_owned) {
+                    let anonymous = self.data_dir.clone().with_profile(None);
+                    match anonymous
+                        .and_then(|data_dir| delete_profile(&data_dir, &profile).map(|()| data_dir))
+                    {
+                        Ok(data_dir) => {
+                            // The game in progress belonged to the deleted
+                            // profile and is dropped with it.
+                            self.board = mk_board(
+                                self.difficulty,
+                                self.generation,
+                                self.practice,
+                                self.question_marks,
+                                None,
+                            );
+                            self.switch_data_dir(data_dir);
+                            self.save_profile();
+                        }
+                        Err(err) => eprintln!("Failed to delete profile: {err}"),
+                    }
+                }
+            }
+            AppMsg::NoGuess(no_guess) => {
+                self.generation = if no_guess {
+                    GenerationMode::NoGuess
+                } else {
+                    GenerationMode::Random
+                };
+                self.save_board_preferences();
+                self.restart(None);
+            }
+            AppMsg::Practice(practice) => {
+                self.practice = practice;
+                self.restart(None);
+            }
+            AppMsg::QuestionMarks(question_marks) => {
+                self.question_marks = question_marks;
+                self.save_board_preferences();
+                self.restart(None);
+            }
+            AppMsg::Undo => {
+                if let Err(err) = self.board.undo() {
+                    eprintln!("Failed to undo: {err}");
+                }
+                self.sync_cells();
+            }
+            AppMsg::Redo => {
+                if let Err(err) = self.board.redo() {
+                    eprintln!("Failed to redo: {err}");
+                }
+                self.sync_cells();
+            }
+            AppMsg::Hint if matches!(self.board.state(), GameState::Active) => {
+                self.clear_hint();
+                self.hint = self.board.hint();
+                if let Some(hint) = self.hint
+                    && let Some(cell_view) = self.cells.iter_mut().find(|c| c.pos == hint.pos)
+                {
+                    cell_view.hint = Some(hint.verdict);
+                }
+            }
+            AppMsg::Heatmap(heatmap) => {
+                self.heatmap = heatmap;
+                self.update_heatmap();
+            }
+            AppMsg::Continue => {
+                if let Some(saved) = self.saved.take() {
+                    self.difficulty = saved.board.config().difficulty();
+                    self.generation = saved.board.config().generation();
+                    self.practice = saved.board.config().practice();
+                    self.question_marks = saved.board.question_marks();
+                    self.set_board(saved.board);
+                }
+            }
+            AppMsg::DiscardSaved => {
+                if let Some(saved) = self.saved.take()
+                    && let Err(err) = record_game(&self.data_dir, &saved.board)
+                {
+                    eprintln!("Failed to save statistics: {err}");
+                }
+                if let Err(err) = remove_saved_game(&self.data_dir) {
+                    eprintln!("Failed to remove saved game: {err}");
+                }
+            }
+            AppMsg::CloseRequested(_) => {
+                if let Err(err) = save_game(&self.data_dir, &self.board) {
+                    eprintln!("Failed to save game: {err}");
+                }
+                return iced::exit();
+            }
+            AppMsg::ReplayGame => {
+                let replay = Replay::new(&self.board);
+                self.playback = Some(Playback::new(replay, self.now));
+            }
+            AppMsg::ReplayWin(index) => {
+                if let Some(replay) = self.scoreboard.take().and_then(|history| {
+                    let wins = history.wins_on(self.leaderboard?);
+                    wins.get(index)?.replay.clone()
+                }) {
+                    self.playback = Some(Playback::new(replay, self.now));
+                }
+            }
+            AppMsg::PlaybackToggle => {
+                if let Some(playback) = &mut self.playback {
+                    playback.playing = !playback.playing;
+                }
+            }
+            AppMsg::PlaybackStep => {
+                if let Some(playback) = &mut self.playback {
+                    playback.step();
+                }
+            }
+            AppMsg::PlaybackSpeed(speed) => {
+                if let Some(playback) = &mut self.playback {
+                    playback.speed = speed;
+                }
+            }
+            AppMsg::PlaybackTick => {
+                if let Some(playback) = &mut self.playback {
+                    playback.tick();
+                }
+            }
+            AppMsg::PlaybackClose => {
+                self.playback = None;
+            }
+            AppMsg::PlaySeed => {
+                if let Ok(seed) = self.seed_input.trim().parse() {
+                    self.restart(Some(seed));
+                }
+            }
             _ => (),
         }
+        self.sync_clock();
         Task::none()
     }
 
+    /// Count time only while the board is shown, pausing the clock for the
+    /// scoreboard, statistics and replays.
+    fn sync_clock(&mut self) {
+        if self.scoreboard.is_none() && self.statistics.is_none() && self.playback.is_none() {
+            self.board.resume_clock();
+        } else {
+            self.board.pause_clock();
+        }
+    }
+
+    /// Update cell views after cells have been opened and check for a win.
+    /// The replay of a finished game is saved and the game is counted in the
+    /// lifetime statistics.
+    fn sync_opened_cells(&mut self) {
+        self.update_cells();
+        self.update_heatmap();
+
+        if matches!(self.board.state(), GameState::Win | GameState::Loss) {
+            let replay = Replay::new(&self.board);
+            if let Err(err) = save_replay(&self.data_dir, &replay) {
+                eprintln!("Failed to save replay: {err}");
+            }
+            if let Err(err) = record_game(&self.data_dir, &self.board) {
+                eprintln!("Failed to save statistics: {err}");
+            }
+        }
+
+        if matches!(self.board.state(), GameState::Win) {
+            self.outcome = Some(format!("You won in {}!", format_time(self.board.elapsed())));
+            self.modal_animation.go_mut(true, self.now);
+            if let Err(err) = save_win(&self.data_dir, &self.board) {
+                eprintln!("Failed to save win: {err}");
+            }
+        }
+    }
+
+    /// Update cell views after a move was undone or redone.
+    fn sync_cells(&mut self) {
+        self.update_cells();
+        self.update_heatmap();
+        self.outcome = None;
+    }
+
+    /// Update cell views from the events of the last move.
+    fn update_cells(&mut self) {
+        update_cell_views(&mut self.cells, &self.board, self.events.try_iter());
+    }
+
+    /// Recompute the mine probabilities shown on closed cells.
+    fn update_heatmap(&mut self) {
+        let probabilities = if self.heatmap {
+            probabilities(&self.board)
+        } else {
+            Default::default()
+        };
+        for cell_view in &mut self.cells {
+            cell_view.probability = probabilities.get(&cell_view.pos).copied();
+        }
+    }
+
+    /// Remove the highlight of the last hint.
+    fn clear_hint(&mut self) {
+        if let Some(hint) = self.hint.take()
+            && let Some(cell_view) = self.cells.iter_mut().find(|c| c.pos == hint.pos)
+        {
+            cell_view.hint = None;
+        }
+    }
+
+    /// Start a new game. A random seed is used when none is given.
+    fn restart(&mut self, seed: Option<u64>) {
+        self.set_board(mk_board(
+            self.difficulty,
+            self.generation,
+            self.practice,
+            self.question_marks,
+            seed,
+        ));
+    }
+
+    /// Switch to the files of another data folder or profile. The game in
+    /// progress is counted as abandoned where it was played and a game on
+    /// the board last chosen in the profile is started.
+    fn switch_data_dir(&mut self, data_dir: DataDir) {
+        if let Some(preferences) = mk_preferences(&data_dir) {
+            self.difficulty = preferences.difficulty;
+            self.generation = preferences.generation;
+            self.question_marks = preferences.question_marks;
+        }
+        self.restart(None);
+        self.saved = load_game(&data_dir).unwrap_or_else(|err| {
+            eprintln!("Failed to load saved game: {err}");
+            None
+        });
+        self.profiles = mk_profiles(&data_dir);
+        self.data_dir = data_dir;
+    }
+
+    /// Keep the chosen board for the next start of the profile.
+    fn save_board_preferences(&self) {
+        let preferences = Preferences {
+            difficulty: self.difficulty,
+            generation: self.generation,
+            question_marks: self.question_marks,
+        };
+        if let Err(err) = save_preferences(&self.data_dir, &preferences) {
+            eprintln!("Failed to save preferences: {err}");
+        }
+    }
+
+    /// Keep the active profile in the settings.
+    fn save_profile(&self) {
+        let profile = self.data_dir.profile().map(ToOwned::to_owned);
+        if let Err(err) = update_settings(|settings| settings.profile = profile) {
+            eprintln!("Failed to save settings: {err}");
+        }
+    }
+
+    /// Play on the given board. A started game that is replaced is counted as
+    /// abandoned.
+    fn set_board(&mut self, board: Board) {
+        if *self.board.state() == GameState::Active
+            && let Err(err) = record_game(&self.data_dir, &self.board)
+        {
+            eprintln!("Failed to save statistics: {err}");
+        }
+        self.playback = None;
+        self.hint = None;
+        self.board = board;
+        self.events = self.board.subscribe();
+        self.cells = self
+            .board
+            .positions()
+            .map(|(pos, cell)| cell_view(*cell, pos, *self.board.state(), self.now))
+            .collect();
+        self.update_heatmap();
+        self.outcome = None;
+        self.modal_animation = mk_modal_animation();
+    }
+
     /// Render the game view.
     pub fn view(&self) -> iced::Element<'_, AppMsg> {
         let mut y = 1;
         let mut rows = Vec::new();
         let mut row: Vec<Element<'_, AppMsg>> = Vec::new();
 
-        for cell_view in &self.cells {
+        let cells = self
+            .playback
+            .as_ref()
+            .map_or(&self.cells, |playback| &playback.cells);
+        for cell_view in cells {
             if cell_view.pos.y.get() != y {
                 rows.push(Element::from(
                     Row::with_children(row).spacing(BOARD_SPACING),
@@ -241,7 +767,76 @@ impl AppState {
                     .on_press(AppMsg::ViewScoreBoard),
             )
             .padding(padding::left(10).right(10)),
+            container(
+                button("Statistics")
+                    .style(|theme: &Theme, status| {
+                        button::Style {
+                            border: border::rounded(10),
+                            shadow: mk_button_shadow(theme, status),
+                            ..button::primary(theme, status)
+                        }
+                    })
+                    .on_press(AppMsg::ViewStatistics),
+            )
+            .padding(padding::left(10).right(10)),
+            container(
+                button("Hint")
+                    .style(|theme: &Theme, status| {
+                        button::Style {
+                            border: border::rounded(10),
+                            shadow: mk_button_shadow(theme, status),
+                            ..button::primary(theme, status)
+                        }
+                    })
+                    .on_press_maybe(
+                        matches!(self.board.state(), GameState::Active).then_some(AppMsg::Hint)
+                    ),
+            )
+            .padding(padding::left(10).right(10)),
+            container(
+                button("Replay")
+                    .style(|theme: &Theme, status| {
+                        button::Style {
+                            border: border::rounded(10),
+                            shadow: mk_button_shadow(theme, status),
+                            ..button::primary(theme, status)
+                        }
+                    })
+                    .on_press_maybe(
+                        matches!(self.board.state(), GameState::Win | GameState::Loss)
+                            .then_some(AppMsg::ReplayGame)
+                    ),
+            )
+            .padding(padding::left(10).right(10)),
         ];
+        let button_row = if self.practice {
+            button_row.extend([
+                container(
+                    button("Undo")
+                        .style(|theme: &Theme, status| button::Style {
+                            border: border::rounded(10),
+                            shadow: mk_button_shadow(theme, status),
+                            ..button::primary(theme, status)
+                        })
+                        .on_press_maybe(self.board.can_undo().then_some(AppMsg::Undo)),
+                )
+                .padding(padding::left(10).right(10))
+                .into(),
+                container(
+                    button("Redo")
+                        .style(|theme: &Theme, status| button::Style {
+                            border: border::rounded(10),
+                            shadow: mk_button_shadow(theme, status),
+                            ..button::primary(theme, status)
+                        })
+                        .on_press_maybe(self.board.can_redo().then_some(AppMsg::Redo)),
+                )
+                .padding(padding::left(10).right(10))
+                .into(),
+            ])
+        } else {
+            button_row
+        };
 
         let board = container(Column::with_children(rows).spacing(BOARD_SPACING))
             .center(Length::Fill)
@@ -250,41 +845,189 @@ impl AppState {
                 container::primary(theme).background(palette.secondary.base.color)
             });
 
-        let theme_picker = container(pick_list(
-            Theme::ALL,
-            Some(self.theme.clone()),
-            AppMsg::Theme,
-        ))
+        let difficulty_picker = pick_list(
+            Difficulty::PRESETS,
+            Some(self.difficulty),
+            AppMsg::Difficulty,
+        );
+
+        let seed_input = text_input("Seed", &self.seed_input)
+            .on_input(AppMsg::SeedInput)
+            .on_submit(AppMsg::PlaySeed)
+            .width(200);
+
+        let data_dir_input = text_input("Data folder", &self.data_dir_input)
+            .on_input(AppMsg::DataDirInput)
+            .on_submit(AppMsg::SetDataDir)
+            .width(200);
+
+        let no_guess = checkbox(self.generation == GenerationMode::NoGuess)
+            .label("No guess")
+            .on_toggle(AppMsg::NoGuess);
+
+        let practice = checkbox(self.practice)
+            .label("Practice")
+            .on_toggle(AppMsg::Practice);
+
+        let question_marks = checkbox(self.question_marks)
+            .label("Question marks")
+            .on_toggle(AppMsg::QuestionMarks);
+
+        let heatmap = checkbox(self.heatmap)
+            .label("Heatmap")
+            .on_toggle(AppMsg::Heatmap);
+
+        let profile_button = |label, msg: Option<AppMsg>| {
+            button(label)
+                .style(|theme: &Theme, status| button::Style {
+                    border: border::rounded(10),
+                    shadow: mk_button_shadow(theme, status),
+                    ..button::primary(theme, status)
+                })
+                .on_press_maybe(msg)
+        };
+        let named = self.data_dir.profile().is_some();
+        let has_name = !self.profile_input.trim().is_empty();
+        let profile_bar = container(
+            row![
+                pick_list(
+                    self.profiles.as_slice(),
+                    Some(Profile(self.data_dir.profile().map(ToOwned::to_owned))),
+                    AppMsg::Profile
+                ),
+                text_input("Profile name", &self.profile_input)
+                    .on_input(AppMsg::ProfileInput)
+                    .on_submit(AppMsg::CreateProfile)
+                    .width(200),
+                profile_button("Create", has_name.then_some(AppMsg::CreateProfile)),
+                profile_button(
+                    "Rename",
+                    (named && has_name).then_some(AppMsg::RenameProfile)
+                ),
+                profile_button("Delete", named.then_some(AppMsg::DeleteProfile)),
+            ]
+            .spacing(10),
+        )
         .align_right(Length::Fill);
 
-        let bottom = row![button_row, theme_picker];
+        let theme_picker = container(
+            row![
+                heatmap,
+                practice,
+                question_marks,
+                no_guess,
+                seed_input,
+                data_dir_input,
+                difficulty_picker,
+                pick_list(Theme::ALL, Some(self.theme.clone()), AppMsg::Theme)
+            ]
+            .spacing(10),
+        )
+        .align_right(Length::Fill);
+
+        let bottom: Element<'_, AppMsg> = match &self.playback {
+            Some(playback) => container(playback.controls())
+                .padding(padding::left(10))
+                .into(),
+            None => column![row![button_row, theme_picker], profile_bar]
+                .spacing(10)
+                .into(),
+        };
 
         let button_container = container(bottom).width(Length::Fill).padding(10);
 
-        let content = column![
-            Header::new(&self.board, self.elapsed_seconds).view(),
-            board,
-            button_container,
-        ];
+        let hint = container(text(match self.hint {
+            Some(hint) => format!("Hint: {hint}"),
+            None => String::new(),
+        }))
+        .padding(padding::left(10));
+
+        let header = match &self.playback {
+            Some(playback) => {
+                Header::new(playback.player.board(), playback.player.position() / 1000)
+            }
+            None => Header::new(&self.board, self.board.elapsed().as_secs()),
+        };
+
+        let content = column![header.view(), board, hint, button_container];
 
-        if let Some(outcome) = self.outcome.as_ref() {
+        if let Some(saved) = self.saved.as_ref() {
+            let continue_button = |label, msg| {
+                button(label)
+                    .style(|theme: &Theme, status| button::Style {
+                        border: border::rounded(10),
+                        shadow: mk_button_shadow(theme, status),
+                        ..button::primary(theme, status)
+                    })
+                    .on_press(msg)
+            };
             modal(
                 content,
-                container(text(outcome).size(30))
-                    .center_x(Length::Fill)
-                    .padding(20)
-                    .width(200)
-                    .style(|theme| modal_content_style(theme, &self.modal_animation, self.now)),
+                container(
+                    column![
+                        text("Continue the saved game?").size(24),
+                        text!(
+                            "{} saved {}",
+                            saved.board.config(),
+                            saved.date.format("%b %d %Y %I:%M%P")
+                        ),
+                        row![
+                            continue_button("Continue", AppMsg::Continue),
+                            continue_button("New game", AppMsg::DiscardSaved),
+                        ]
+                        .spacing(10),
+                    ]
+                    .spacing(10),
+                )
+                .padding(20)
+                .style(|theme| modal_content_style(theme, &self.modal_animation, self.now)),
+                AppMsg::DiscardSaved,
+            )
+            .into()
+        } else if let Some(outcome) = self.outcome.as_ref() {
+            // A won game shows its speed and efficiency under the outcome.
+            let metrics = self
+                .board
+                .metrics()
+                .filter(|_| *self.board.state() == GameState::Win)
+                .map(|metrics| text(metrics.to_string()).size(16));
+            modal(
+                content,
+                container(
+                    column![text(outcome).size(30)]
+                        .extend(metrics.map(Into::into))
+                        .spacing(10),
+                )
+                .center_x(Length::Fill)
+                .padding(20)
+                .width(300)
+                .style(|theme| modal_content_style(theme, &self.modal_animation, self.now)),
                 AppMsg::DismissModal,
             )
             .into()
         } else if let Some(wins) = self.scoreboard.as_ref() {
             modal(
                 content,
-                container(ScoreBoard::new(&wins.wins).view())
+                container(ScoreBoard::new(wins, self.leaderboard).view(
+                    AppMsg::Leaderboard,
+                    AppMsg::ReplayWin,
+                    &self.wins_file,
+                    AppMsg::WinsFileInput,
+                    AppMsg::ExportWins,
+                    AppMsg::ImportWins,
+                ))
+                .padding(10)
+                .style(|theme| modal_content_style(theme, &self.modal_animation, self.now)),
+                AppMsg::DismissScoreBoard,
+            )
+            .into()
+        } else if let Some(statistics) = self.statistics.as_ref() {
+            modal(
+                content,
+                container(StatisticsView::new(statistics).view())
                     .padding(10)
                     .style(|theme| modal_content_style(theme, &self.modal_animation, self.now)),
-                AppMsg::DismissScoreBoard,
+                AppMsg::DismissStatistics,
             )
             .into()
         } else {
@@ -337,11 +1080,51 @@ fn mk_modal_animation() -> Animation<bool> {
         .repeat(2)
 }
 
-fn mk_board() -> Board {
-    Board::new(
-        NonZeroU8::try_from(40).unwrap(),
-        NonZeroU8::try_from(20).unwrap(),
-    )
+/// Data folder chosen in the settings, a temporary folder when there is no
+/// default location.
+fn mk_data_dir(settings: &Settings) -> DataDir {
+    settings.data_dir().unwrap_or_else(|err| {
+        eprintln!("Failed to find the data folder: {err}");
+        DataDir::new(temp_dir().join("minesweeper"))
+    })
+}
+
+/// Preferences saved in the profile, logging failures to load them.
+fn mk_preferences(data_dir: &DataDir) -> Option<Preferences> {
+    load_preferences(data_dir).unwrap_or_else(|err| {
+        eprintln!("Failed to load preferences: {err}");
+        None
+    })
+}
+
+/// The anonymous profile followed by the named profiles in the data folder.
+fn mk_profiles(data_dir: &DataDir) -> Vec<Profile> {
+    let names = profiles(data_dir).unwrap_or_else(|err| {
+        eprintln!("Failed to list profiles: {err}");
+        vec![]
+    });
+    std::iter::once(Profile(None))
+        .chain(names.into_iter().map(|name| Profile(Some(name))))
+        .collect()
+}
+
+fn mk_board(
+    difficulty: Difficulty,
+    generation: GenerationMode,
+    practice: bool,
+    question_marks: bool,
+    seed: Option<u64>,
+) -> Board {
+    let config = BoardConfig::preset(difficulty)
+        .unwrap_or_default()
+        .with_generation(generation)
+        .with_practice(practice);
+    let mut board = match seed {
+        Some(seed) => Board::with_seed(config, seed),
+        None => Board::new(config),
+    };
+    board.set_question_marks(question_marks);
+    board
 }
 
 const BOARD_SPACING: u32 = 5;
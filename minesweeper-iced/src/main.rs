@@ -12,6 +12,8 @@ fn main() -> iced::Result {
     .title("Minesweeper")
     .window(window::Settings {
         size: (1644., 924.).into(),
+        // The game is saved before closing.
+        exit_on_close_request: false,
         #[cfg(target_os = "linux")]
         platform_specific: window::settings::PlatformSpecific {
             application_id: "io.github.darrellroberts.minesweeper".into(),
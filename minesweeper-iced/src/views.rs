@@ -1,12 +1,17 @@
 //! Application views.
 mod cell;
 mod header;
+mod playback;
 mod scoreboard;
+mod statistics;
 
-pub use cell::{CellView, cell_view};
+pub use cell::{CellView, cell_view, update_cell_views};
 pub use header::Header;
 use iced::{Shadow, Theme, widget::button};
+pub use playback::{PLAYBACK_TICK, Playback, Speed};
 pub use scoreboard::ScoreBoard;
+pub use statistics::StatisticsView;
+use std::time::Duration;
 
 /// Displayable elapsed time.
 pub fn format_elapsed(seconds: u64) -> String {
@@ -21,6 +26,20 @@ pub fn format_elapsed(seconds: u64) -> String {
     }
 }
 
+/// Displayable recorded time with milliseconds.
+pub fn format_time(time: Duration) -> String {
+    let (seconds, millis) = (time.as_secs(), time.subsec_millis());
+    match seconds {
+        0..=59 => format!("{seconds}.{millis:03} seconds"),
+        60..=3599 => format!(
+            "{} minute(s) {}.{millis:03} seconds",
+            seconds.div_euclid(60),
+            seconds.rem_euclid(60)
+        ),
+        3600.. => format_elapsed(seconds),
+    }
+}
+
 /// Create a button shadow. Active when not pressed.
 pub fn mk_button_shadow(theme: &Theme, status: button::Status) -> Shadow {
     let palette = theme.extended_palette();
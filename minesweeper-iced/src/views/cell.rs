@@ -5,17 +5,24 @@ use crate::{AppMsg, views::mk_button_shadow};
 use iced::{
     Animation, Background, Color, Element, Gradient, Length, Theme,
     animation::Easing,
-    color,
+    border, color,
     gradient::Linear,
     widget::{Button, button, container, mouse_area, text},
 };
-use minesweeper::model::{Cell, CellState, GameState, Pos};
+use minesweeper::{
+    model::{Board, BoardEvent, Cell, CellState, GameState, Pos},
+    solver::{Probability, Verdict},
+};
 
 /// Cell view.
 pub struct CellView {
     pub cell: Cell,
     pub pos: Pos,
     pub game_state: GameState,
+    /// Verdict of a hint given for this cell.
+    pub hint: Option<Verdict>,
+    /// Mine probability shown by the heatmap.
+    pub probability: Option<Probability>,
     cell_animation: Animation<bool>,
     exposed_animation: Animation<bool>,
     pub now: Instant,
@@ -28,6 +35,8 @@ impl CellView {
             cell,
             pos,
             game_state,
+            hint: None,
+            probability: None,
             cell_animation: mk_cell_animation(),
             exposed_animation: Animation::new(false)
                 .repeat(3)
@@ -48,6 +57,35 @@ pub fn cell_view(cell: Cell, pos: Pos, game_state: GameState, now: Instant) -> C
     CellView::new(cell, pos, game_state, now)
 }
 
+/// Update the cell views from the events of the board, animating opened,
+/// flagged and exposed cells.
+pub fn update_cell_views(
+    cells: &mut [CellView],
+    board: &Board,
+    events: impl IntoIterator<Item = BoardEvent>,
+) {
+    let columns = usize::from(board.total_columns().get());
+    for event in events {
+        if let Some((pos, cell)) = event.cell() {
+            let index = (usize::from(pos.y.get()) - 1) * columns + usize::from(pos.x.get()) - 1;
+            let Some(cell_view) = cells.get_mut(index) else {
+                continue;
+            };
+            match event {
+                BoardEvent::CellOpened(..) => cell_view.open(),
+                BoardEvent::CellFlagged(..) | BoardEvent::CellQuestioned(..) => cell_view.flag(),
+                BoardEvent::MineExposed(..) => cell_view.detonate(),
+                _ => (),
+            }
+            cell_view.cell = cell;
+        } else if !matches!(event, BoardEvent::CountersChanged { .. }) {
+            for cell_view in cells.iter_mut() {
+                cell_view.game_state = *board.state();
+            }
+        }
+    }
+}
+
 fn mk_cell_animation() -> Animation<bool> {
     Animation::new(false).easing(Easing::EaseIn).quick()
 }
@@ -76,40 +114,51 @@ impl CellView {
         let adjacent_mines = self.cell.adjacent_mines;
 
         let content: Element<'_, AppMsg> = match self.cell.state {
-            CellState::Open => container(if self.cell.adjacent_mines > 0 {
-                text!("{adjacent_mines}")
-                    .center()
-                    .style(move |theme| {
-                        if self.cell_animation.is_animating(self.now) {
-                            select_color(
-                                theme,
-                                adjacent_mines,
-                                self.cell_animation.interpolate(0.0, 1.0, self.now),
-                            )
-                        } else {
-                            select_color(theme, adjacent_mines, 1.0)
-                        }
-                    })
-                    .center()
-            } else {
-                text("")
-            })
-            .center(Length::Fill)
-            // Animate the button fading from closed to open color.
-            .style(|theme: &Theme| {
-                let palette = theme.extended_palette();
-                if self.cell_animation.is_animating(self.now) {
-                    container::primary(theme).background(
-                        mk_cell_background(theme, button::Status::Active)
-                            .scale_alpha(self.cell_animation.interpolate(1.0, 0.0, self.now)),
-                    )
+            CellState::Open => mouse_area(
+                container(if self.cell.adjacent_mines > 0 {
+                    text!("{adjacent_mines}")
+                        .center()
+                        .style(move |theme| {
+                            if self.cell_animation.is_animating(self.now) {
+                                select_color(
+                                    theme,
+                                    adjacent_mines,
+                                    self.cell_animation.interpolate(0.0, 1.0, self.now),
+                                )
+                            } else {
+                                select_color(theme, adjacent_mines, 1.0)
+                            }
+                        })
+                        .center()
                 } else {
-                    container::primary(theme).background(palette.background.weak.color)
-                }
+                    text("")
+                })
+                .center(Length::Fill)
+                // Animate the button fading from closed to open color.
+                .style(|theme: &Theme| {
+                    let palette = theme.extended_palette();
+                    if self.cell_animation.is_animating(self.now) {
+                        container::primary(theme).background(
+                            mk_cell_background(theme, button::Status::Active)
+                                .scale_alpha(self.cell_animation.interpolate(1.0, 0.0, self.now)),
+                        )
+                    } else {
+                        container::primary(theme).background(palette.background.weak.color)
+                    }
+                }),
+            )
+            .on_middle_press(if matches!(self.game_state, GameState::Active) {
+                AppMsg::Chord(self.pos)
+            } else {
+                AppMsg::None
             })
             .into(),
 
-            CellState::Closed { flagged, .. } => {
+            CellState::Closed {
+                flagged,
+                questioned,
+                ..
+            } => {
                 let game_active = matches!(self.game_state, GameState::Active | GameState::New);
                 if flagged {
                     mouse_area(
@@ -141,12 +190,24 @@ impl CellView {
                     })
                     .into()
                 } else {
+                    let label = if questioned {
+                        "?".to_string()
+                    } else {
+                        self.probability.map(probability_label).unwrap_or_default()
+                    };
                     mouse_area(
-                        cell_button("")
+                        cell_button(text(label).size(12).center())
                             .style(|theme, status| {
-                                let mut style = button::primary(theme, status)
-                                    .with_background(mk_cell_background(theme, status));
+                                let mut style = button::primary(theme, status).with_background(
+                                    match self.probability {
+                                        Some(p) => heat_background(p.value),
+                                        None => mk_cell_background(theme, status),
+                                    },
+                                );
                                 style.shadow = mk_button_shadow(theme, status);
+                                if let Some(verdict) = self.hint {
+                                    style.border = hint_border(theme, verdict);
+                                }
                                 style
                             })
                             .on_press_maybe(game_active.then_some(AppMsg::Open(self.pos))),
@@ -201,6 +262,27 @@ fn select_color(theme: &Theme, adjacent_mines: u8, opacity: f32) -> text::Style
     }
 }
 
+/// Probability in percent, estimates are marked with `~`.
+fn probability_label(probability: Probability) -> String {
+    let mark = if probability.exact { "" } else { "~" };
+    format!("{mark}{:.0}", probability.value * 100.)
+}
+
+/// Heatmap color from green for safe to red for mined.
+fn heat_background(probability: f64) -> Background {
+    let p = probability as f32;
+    Background::Color(Color::from_rgb(p, 1.0 - p, 0.2).scale_alpha(0.8))
+}
+
+/// Highlight a hinted cell as safe or mined.
+fn hint_border(theme: &Theme, verdict: Verdict) -> border::Border {
+    let palette = theme.extended_palette();
+    border::width(3).color(match verdict {
+        Verdict::Safe => palette.success.strong.color,
+        Verdict::Mine => palette.danger.strong.color,
+    })
+}
+
 fn cell_button<'a, Message>(content: impl Into<Element<'a, Message>>) -> Button<'a, Message>
 where
     Message: Clone + 'a,
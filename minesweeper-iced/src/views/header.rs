@@ -12,6 +12,8 @@ pub struct Header {
     opened: usize,
     flagged: usize,
     mined: usize,
+    seed: u64,
+    hints: usize,
 }
 
 impl Header {
@@ -22,6 +24,8 @@ impl Header {
             opened: board.opened(),
             flagged: board.flagged(),
             mined: board.mined(),
+            seed: board.seed(),
+            hints: board.hints(),
         }
     }
 }
@@ -39,7 +43,9 @@ impl Header {
                     text!("🚩 {}", self.flagged).shaping(text::Shaping::Advanced),
                     text!("💣 {}", self.mined).shaping(text::Shaping::Advanced),
                     text!("⏰ {}", format_elapsed(self.elapsed_seconds))
-                        .shaping(text::Shaping::Advanced)
+                        .shaping(text::Shaping::Advanced),
+                    text!("🎲 {}", self.seed).shaping(text::Shaping::Advanced),
+                    text!("💡 {}", self.hints).shaping(text::Shaping::Advanced)
                 ]
                 .spacing(20),
             )
@@ -0,0 +1,140 @@
+//! Replay playback and its controls.
+use super::{CellView, cell_view, format_elapsed, mk_button_shadow, update_cell_views};
+use crate::AppMsg;
+use iced::{
+    Element, Theme, border,
+    widget::{button, pick_list, row, text},
+};
+use minesweeper::{
+    model::BoardEvent,
+    replay::{Player, Replay},
+};
+use std::{
+    fmt::{Display, Formatter},
+    sync::mpsc::Receiver,
+    time::{Duration, Instant},
+};
+
+/// Interval between playback ticks.
+pub const PLAYBACK_TICK: Duration = Duration::from_millis(100);
+
+/// Playback speed.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Speed {
+    Half,
+    #[default]
+    Normal,
+    Double,
+    Quadruple,
+}
+
+impl Speed {
+    pub const ALL: [Speed; 4] = [Speed::Half, Speed::Normal, Speed::Double, Speed::Quadruple];
+
+    /// Replay milliseconds played for the given real milliseconds.
+    fn scale(self, millis: u64) -> u64 {
+        match self {
+            Speed::Half => millis / 2,
+            Speed::Normal => millis,
+            Speed::Double => millis * 2,
+            Speed::Quadruple => millis * 4,
+        }
+    }
+}
+
+impl Display for Speed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Speed::Half => "0.5x",
+            Speed::Normal => "1x",
+            Speed::Double => "2x",
+            Speed::Quadruple => "4x",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Playback of a replay on its own board.
+pub struct Playback {
+    pub player: Player,
+    /// Events of the replayed board.
+    events: Receiver<BoardEvent>,
+    /// Cells of the replayed board.
+    pub cells: Vec<CellView>,
+    pub playing: bool,
+    pub speed: Speed,
+}
+
+impl Playback {
+    /// Start playing the replay.
+    pub fn new(replay: Replay, now: Instant) -> Self {
+        let mut player = Player::new(replay);
+        let events = player.subscribe();
+        let board = player.board();
+        let cells = board
+            .positions()
+            .map(|(pos, cell)| cell_view(*cell, pos, *board.state(), now))
+            .collect();
+        Self {
+            player,
+            events,
+            cells,
+            playing: true,
+            speed: Speed::default(),
+        }
+    }
+
+    /// Play the actions reached in one tick at the selected speed.
+    pub fn tick(&mut self) {
+        self.player
+            .advance(self.speed.scale(PLAYBACK_TICK.as_millis() as u64));
+        self.sync();
+    }
+
+    /// Play the next action.
+    pub fn step(&mut self) {
+        self.playing = false;
+        self.player.step();
+        self.sync();
+    }
+
+    fn sync(&mut self) {
+        update_cell_views(&mut self.cells, self.player.board(), self.events.try_iter());
+        if self.player.is_finished() {
+            self.playing = false;
+        }
+    }
+
+    /// Render the playback controls.
+    pub fn controls(&self) -> Element<'_, AppMsg> {
+        let control = |label, msg: Option<AppMsg>| {
+            button(label)
+                .style(|theme: &Theme, status| button::Style {
+                    border: border::rounded(10),
+                    shadow: mk_button_shadow(theme, status),
+                    ..button::primary(theme, status)
+                })
+                .on_press_maybe(msg)
+        };
+        let finished = self.player.is_finished();
+
+        row![
+            control(
+                if self.playing { "Pause" } else { "Play" },
+                (!finished).then_some(AppMsg::PlaybackToggle)
+            ),
+            control("Step", (!finished).then_some(AppMsg::PlaybackStep)),
+            pick_list(Speed::ALL, Some(self.speed), AppMsg::PlaybackSpeed),
+            text!(
+                "Move {} of {} at {}",
+                self.player.played(),
+                self.player.replay().actions.len(),
+                format_elapsed(self.player.position() / 1000)
+            ),
+            control("Close", Some(AppMsg::PlaybackClose)),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+    }
+}
@@ -1,40 +1,101 @@
 //! Scoreboard modal.
-use super::format_elapsed;
+use super::{format_time, mk_button_shadow};
 use iced::{
-    Element,
-    widget::{Column, container, row, text},
+    Element, Theme, border,
+    widget::{Column, button, container, pick_list, row, text, text_input},
+};
+use minesweeper::{
+    history::{Leaderboard, Win, WinHistory},
+    model::GenerationMode,
 };
-use minesweeper::history::Win;
 
-/// Scoreboard view.
+/// Scoreboard view of one leaderboard.
 pub struct ScoreBoard<'a> {
-    win_history: &'a [Win],
+    leaderboards: Vec<Leaderboard>,
+    selected: Option<Leaderboard>,
+    wins: Vec<&'a Win>,
 }
 
 impl<'a> ScoreBoard<'a> {
-    /// New scoreboard view.
-    pub fn new(win_history: &'a [Win]) -> Self {
-        Self { win_history }
+    /// New scoreboard view of the selected leaderboard.
+    pub fn new(win_history: &'a WinHistory, selected: Option<Leaderboard>) -> Self {
+        Self {
+            leaderboards: win_history.leaderboards(),
+            selected,
+            wins: selected
+                .map(|leaderboard| win_history.wins_on(leaderboard))
+                .unwrap_or_default(),
+        }
     }
 
-    /// Render scoreboard.
-    pub fn view<Message>(&self) -> impl Into<Element<'a, Message>>
+    /// Render scoreboard with a picker for the leaderboard. Wins with a
+    /// replay can be played back. The wins are exported to or imported from
+    /// the entered JSON or CSV file.
+    pub fn view<Message>(
+        &self,
+        on_select: impl Fn(Leaderboard) -> Message + 'a,
+        on_replay: impl Fn(usize) -> Message,
+        file: &'a str,
+        on_file: impl Fn(String) -> Message + 'a,
+        on_export: Message,
+        on_import: Message,
+    ) -> impl Into<Element<'a, Message>>
     where
-        Message: 'a,
+        Message: Clone + 'a,
     {
+        let file_button = |label| {
+            button(label).style(|theme: &Theme, status| button::Style {
+                border: border::rounded(10),
+                shadow: mk_button_shadow(theme, status),
+                ..button::primary(theme, status)
+            })
+        };
+        let has_file = !file.trim().is_empty();
+        let transfer = row![
+            text_input("Wins file (.json or .csv)", file)
+                .on_input(on_file)
+                .width(300),
+            file_button("Export").on_press_maybe(has_file.then_some(on_export)),
+            file_button("Import").on_press_maybe(has_file.then_some(on_import)),
+        ]
+        .spacing(10);
+        let picker = pick_list(self.leaderboards.clone(), self.selected, on_select);
         let col = self
-            .win_history
+            .wins
             .iter()
             .zip(1..)
-            .fold(Column::new(), |col, (win, rank)| {
+            .fold(Column::new().push(picker), |col, (win, rank)| {
                 let row = row![
                     container(text!("{rank:<5}").size(20)).width(25),
-                    container(text(format_elapsed(win.duration)).size(20)).width(250),
-                    text!("{}", win.date.format("%b %d %Y %I:%M%P")).size(20)
+                    container(text(format_time(win.score())).size(20)).width(250),
+                    container(text!("{}", win.date.format("%b %d %Y %I:%M%P")).size(20)).width(250),
+                    container(
+                        text(match win.generation() {
+                            GenerationMode::NoGuess => "No guess",
+                            GenerationMode::Random => "",
+                        })
+                        .size(20)
+                    )
+                    .width(100),
+                    text(if win.hints > 0 {
+                        format!("💡 {}", win.hints)
+                    } else {
+                        String::new()
+                    })
+                    .shaping(text::Shaping::Advanced)
+                    .size(20)
+                    .width(80),
+                    button("Replay")
+                        .style(|theme: &Theme, status| button::Style {
+                            border: border::rounded(10),
+                            shadow: mk_button_shadow(theme, status),
+                            ..button::primary(theme, status)
+                        })
+                        .on_press_maybe(win.replay.is_some().then(|| on_replay(rank - 1)))
                 ]
                 .spacing(10);
                 col.push(row).spacing(10)
             });
-        container(col).padding(20)
+        container(col.push(transfer)).padding(20)
     }
 }
@@ -0,0 +1,69 @@
+//! Lifetime statistics modal.
+use super::format_time;
+use iced::{
+    Element,
+    widget::{Column, container, row, text},
+};
+use minesweeper::history::Statistics;
+use std::time::Duration;
+
+/// Statistics view with a row for every board configuration played.
+pub struct StatisticsView<'a> {
+    statistics: &'a Statistics,
+}
+
+impl<'a> StatisticsView<'a> {
+    pub fn new(statistics: &'a Statistics) -> Self {
+        Self { statistics }
+    }
+
+    /// Render the statistics table.
+    pub fn view<Message: 'a>(&self) -> impl Into<Element<'a, Message>> {
+        let cell = |label: String, width| container(text(label).size(20)).width(width);
+        let time = |time: Option<Duration>| time.map_or("-".into(), format_time);
+        let header = row![
+            cell("Board".into(), 300),
+            cell("Played".into(), 80),
+            cell("Won".into(), 80),
+            cell("Lost".into(), 80),
+            cell("Abandoned".into(), 110),
+            cell("Streak".into(), 80),
+            cell("Best streak".into(), 120),
+            cell("Best time".into(), 250),
+            cell("Average time".into(), 250),
+            cell("Efficiency".into(), 110),
+        ]
+        .spacing(10);
+
+        let col = if self.statistics.boards.is_empty() {
+            Column::new().push(text("No games played").size(20))
+        } else {
+            self.statistics.boards.iter().fold(
+                Column::new().push(header),
+                |col, (config, stats)| {
+                    col.push(
+                        row![
+                            cell(config.to_string(), 300),
+                            cell(stats.played.to_string(), 80),
+                            cell(format!("{:.0}%", stats.win_rate()), 80),
+                            cell(stats.losses.to_string(), 80),
+                            cell(stats.abandoned.to_string(), 110),
+                            cell(stats.current_streak.to_string(), 80),
+                            cell(stats.best_streak.to_string(), 120),
+                            cell(time(stats.best_time), 250),
+                            cell(time(stats.average_time()), 250),
+                            cell(
+                                stats
+                                    .efficiency()
+                                    .map_or("-".into(), |e| format!("{e:.0}%")),
+                                110
+                            ),
+                        ]
+                        .spacing(10),
+                    )
+                },
+            )
+        };
+        container(col.spacing(10)).padding(20)
+    }
+}
@@ -1,5 +1,5 @@
 pub mod app;
 pub mod history;
 pub mod positions;
+pub mod statistics;
 pub mod status_dialog;
-pub mod timer;
@@ -1,49 +1,330 @@
 use super::{
     history::{HistoryMsg, HistoryOut, WinHistoryView},
+    statistics::{StatisticsMsg, StatisticsOut, StatisticsView},
     status_dialog::{StatusDialogModel, StatusMsg},
-    timer::{GameTimer, GameTimerInput, GameTimerOutput},
 };
 use crate::{
-    board, components::positions::PositionOutput, format_elapsed, types::Position, BOMB, FLAG,
+    board, components::positions::PositionOutput, data_dir, format_elapsed, format_time,
+    types::Position, BOMB, FLAG,
 };
 use minesweeper::{
-    history::save_win,
-    model::{Board, GameState, Pos},
+    history::{
+        create_profile, delete_profile, load_game, load_preferences, load_settings, profiles,
+        record_game, rename_profile, save_game, save_preferences, save_replay, save_win,
+        update_settings, DataDir, Preferences, SavedGame, Settings,
+    },
+    model::{Board, BoardEvent, Difficulty, GameState, GenerationMode, Pos},
+    replay::{Player, Replay},
+    solver::{probabilities, Deduction},
 };
 use relm4::{
-    factory::FactoryVecDeque, gtk, gtk::prelude::*, Component, ComponentController, ComponentParts,
-    ComponentSender, Controller, SimpleComponent, WorkerController,
+    factory::FactoryVecDeque,
+    gtk,
+    gtk::{glib, prelude::*},
+    Component, ComponentController, ComponentParts, ComponentSender, Controller, SimpleComponent,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::mpsc::Receiver, time::Duration};
+
+/// Interval between playback ticks.
+const PLAYBACK_TICK: Duration = Duration::from_millis(100);
+
+/// Labels and factors for the playback speed selector.
+const SPEED_LABELS: [&str; 4] = ["0.5x", "1x", "2x", "4x"];
+const SPEEDS: [f64; 4] = [0.5, 1., 2., 4.];
+
+/// Replay shown on the board instead of the game.
+struct Playback {
+    player: Player,
+    /// Events of the replayed board.
+    events: Receiver<BoardEvent>,
+    playing: bool,
+    /// Index into [SPEEDS].
+    speed: usize,
+    /// Timer sending playback ticks.
+    timer: glib::SourceId,
+}
 
 /// Application state.
 pub struct AppModel {
     /// Game board and API
     board: Board,
+    /// Events of the game board.
+    events: Receiver<BoardEvent>,
+    /// Folder for the history files.
+    data_dir: DataDir,
+    /// Named profiles in the data folder.
+    profiles: Vec<String>,
+    /// Names for the profile selector, the anonymous profile first.
+    profile_labels: gtk::StringList,
+    /// Profile name entered by the player.
+    profile_name: String,
     /// View model for board.
     positions: FactoryVecDeque<Position>,
     /// Map Pos items to index in [FactoryVec].
     pos_map: HashMap<Pos, usize>,
     /// Status dialog.
     dialog: Controller<StatusDialogModel>,
-    /// Background working tracking game time.
-    timer_worker: WorkerController<GameTimer>,
-    /// The elapsed time from game start to end.
-    time_elapsed: u64,
-    /// Elapsed time copied when paused.
-    time_paused: u64,
     /// If the game is paused.
     paused: bool,
     /// History view window.
     history_window: Controller<WinHistoryView>,
+    /// Lifetime statistics window.
+    statistics_window: Controller<StatisticsView>,
+    /// Last hint given, cleared by the next move.
+    hint: Option<Deduction>,
+    /// Show mine probabilities over closed positions.
+    heatmap: bool,
+    /// Game saved on the last exit that can be continued.
+    saved: Option<SavedGame>,
+    /// Replay of the finished game being played back.
+    playback: Option<Playback>,
 }
 
 impl AppModel {
-    /// Sync up the view model with the game board.
+    /// Board shown, the replayed board during playback.
+    fn shown_board(&self) -> &Board {
+        self.playback
+            .as_ref()
+            .map_or(&self.board, |playback| playback.player.board())
+    }
+
+    /// Sync up the view model with the shown board.
     fn update_all_positions(&mut self) {
-        self.positions.guard().clear();
-        for (&pos, &cell) in self.board.positions() {
-            self.positions.guard().push_back((pos, cell));
+        let mut positions = self.positions.guard();
+        positions.clear();
+        let board = self
+            .playback
+            .as_ref()
+            .map_or(&self.board, |playback| playback.player.board());
+        for (pos, &cell) in board.positions() {
+            positions.push_back((pos, cell));
+        }
+        drop(positions);
+        // Board dimensions change with the difficulty level.
+        self.pos_map = self
+            .positions
+            .iter()
+            .map(|Position { index, pos, .. }| (*pos, *index))
+            .collect();
+    }
+
+    /// Update the view after cells have been opened. Handles the outcome of
+    /// a game that ended with the move, a game that was already over is not
+    /// saved again.
+    fn handle_opened(&mut self, before: GameState) {
+        self.apply_events();
+        match *self.board.state() {
+            s @ GameState::Loss | s @ GameState::Win if !before.is_over() => {
+                if s == GameState::Win {
+                    save_win(&self.data_dir, &self.board)
+                        .unwrap_or_else(|e| eprintln!("Failed to save game win {e}"));
+                    self.history_window.emit(HistoryMsg::Reload);
+                }
+                save_replay(&self.data_dir, &Replay::new(&self.board))
+                    .unwrap_or_else(|e| eprintln!("Failed to save replay {e}"));
+                record_game(&self.data_dir, &self.board)
+                    .unwrap_or_else(|e| eprintln!("Failed to save statistics {e}"));
+                let message = if s == GameState::Win {
+                    let won = format!("You win in {}!", format_time(self.board.elapsed()));
+                    match self.board.metrics() {
+                        Some(metrics) => format!("{won}\n{metrics}"),
+                        None => won,
+                    }
+                } else {
+                    "You lose!".into()
+                };
+                self.dialog
+                    .sender()
+                    .send(StatusMsg::Open(message))
+                    .unwrap_or_else(|_| eprintln!("Failed to send message"));
+            }
+            _ => (),
+        }
+    }
+
+    /// Recompute the mine probabilities shown on closed positions.
+    fn update_heatmap(&mut self) {
+        let probabilities = if self.heatmap {
+            probabilities(&self.board)
+        } else {
+            HashMap::new()
+        };
+        if probabilities.is_empty() && self.positions.iter().all(|p| p.probability.is_none()) {
+            return;
+        }
+        let mut positions = self.positions.guard();
+        for index in 0..positions.len() {
+            if let Some(position) = positions.get_mut(index) {
+                position.probability = probabilities.get(&position.pos).copied();
+            }
+        }
+    }
+
+    /// Highlight the position of a hint, or remove the highlight of the last
+    /// hint when none is given.
+    fn show_hint(&mut self, hint: Option<Deduction>) {
+        if let Some(&index) = self.hint.and_then(|h| self.pos_map.get(&h.pos)) {
+            if let Some(position) = self.positions.guard().get_mut(index) {
+                position.hint = None;
+            }
+        }
+        if let Some(&index) = hint.and_then(|h| self.pos_map.get(&h.pos)) {
+            if let Some(position) = self.positions.guard().get_mut(index) {
+                position.hint = hint.map(|h| h.verdict);
+            }
+        }
+        self.hint = hint;
+    }
+
+    /// Index of the active profile in the profile selector.
+    fn profile_index(&self) -> usize {
+        self.data_dir
+            .profile()
+            .and_then(|profile| self.profiles.iter().position(|name| name == profile))
+            .map_or(0, |index| index + 1)
+    }
+
+    /// List the profiles in the data folder. The selector gets a new list
+    /// that is set while its handler is blocked.
+    fn load_profiles(&mut self) {
+        self.profiles = profiles(&self.data_dir).unwrap_or_else(|err| {
+            eprintln!("Failed to list profiles: {err}");
+            vec![]
+        });
+        let labels = std::iter::once("Anonymous")
+            .chain(self.profiles.iter().map(String::as_str))
+            .collect::<Vec<_>>();
+        self.profile_labels = gtk::StringList::new(&labels);
+    }
+
+    /// Switch to the files of another data folder or profile. The game in
+    /// progress is counted as abandoned where it was played and a game on
+    /// the board last chosen in the profile is started.
+    fn switch_data_dir(&mut self, data_dir: DataDir) {
+        let config = *self.board.config();
+        let preferences = load_preferences(&data_dir)
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to load preferences: {err}");
+                None
+            })
+            .unwrap_or(Preferences {
+                difficulty: config.difficulty(),
+                generation: config.generation(),
+                question_marks: self.board.question_marks(),
+            });
+        self.set_board(board(
+            preferences.difficulty,
+            preferences.generation,
+            config.practice(),
+            preferences.question_marks,
+            None,
+        ));
+        self.saved = load_game(&data_dir).unwrap_or_else(|err| {
+            eprintln!("Failed to load saved game: {err}");
+            None
+        });
+        self.data_dir = data_dir;
+        self.load_profiles();
+        self.history_window
+            .emit(HistoryMsg::DataDir(self.data_dir.clone()));
+        self.statistics_window
+            .emit(StatisticsMsg::DataDir(self.data_dir.clone()));
+    }
+
+    /// Keep the board of the game for the next start of the profile.
+    fn save_preferences(&self) {
+        let config = self.board.config();
+        let preferences = Preferences {
+            difficulty: config.difficulty(),
+            generation: config.generation(),
+            question_marks: self.board.question_marks(),
+        };
+        save_preferences(&self.data_dir, &preferences)
+            .unwrap_or_else(|e| eprintln!("Failed to save preferences {e}"));
+    }
+
+    /// Keep the active profile in the settings.
+    fn save_profile(&self) {
+        let profile = self.data_dir.profile().map(ToOwned::to_owned);
+        update_settings(|settings| settings.profile = profile)
+            .unwrap_or_else(|e| eprintln!("Failed to save settings {e}"));
+    }
+
+    /// Start a new game with a fresh board, keeping the practice mode and
+    /// question marks.
+    fn restart(&mut self, difficulty: Difficulty, generation: GenerationMode, seed: Option<u64>) {
+        let practice = self.board.config().practice();
+        let question_marks = self.board.question_marks();
+        self.set_board(board(
+            difficulty,
+            generation,
+            practice,
+            question_marks,
+            seed,
+        ));
+    }
+
+    /// Update the positions changed by the events of the shown board.
+    fn apply_events(&mut self) {
+        let events = match &self.playback {
+            Some(playback) => playback.events.try_iter().collect::<Vec<_>>(),
+            None => self.events.try_iter().collect(),
+        };
+        let positions = events
+            .iter()
+            .filter_map(BoardEvent::cell)
+            .flat_map(|(pos, cell)| {
+                self.pos_map.get(&pos).map(|&index| Position {
+                    pos,
+                    cell,
+                    index,
+                    hint: None,
+                    probability: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        self.update_positions(&positions);
+    }
+
+    /// Update the positions changed by playback.
+    fn handle_playback(&mut self) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        if playback.player.is_finished() {
+            playback.playing = false;
+        }
+        self.apply_events();
+    }
+
+    /// Stop playback and show the game again.
+    fn close_playback(&mut self) {
+        if let Some(playback) = self.playback.take() {
+            playback.timer.remove();
+            self.update_all_positions();
+        }
+    }
+
+    /// Play on the given board. A started game that is replaced is counted as
+    /// abandoned.
+    fn set_board(&mut self, board: Board) {
+        if *self.board.state() == GameState::Active {
+            record_game(&self.data_dir, &self.board)
+                .unwrap_or_else(|e| eprintln!("Failed to save statistics {e}"));
+        }
+        self.close_playback();
+        self.hint = None;
+        self.board = board;
+        self.events = self.board.subscribe();
+        self.update_all_positions();
+        self.paused = false;
+    }
+
+    /// Pause the clock of an active game while a window is shown over it.
+    fn pause(&mut self) {
+        if *self.board.state() == GameState::Active {
+            self.paused = true;
+            self.board.pause_clock();
         }
     }
 
@@ -57,6 +338,9 @@ impl AppModel {
     }
 }
 
+/// Labels for the difficulty selector.
+const DIFFICULTY_LABELS: [&str; 3] = ["Beginner", "Intermediate", "Expert"];
+
 /// User actions
 #[derive(Debug)]
 pub enum AppMsg {
@@ -64,14 +348,64 @@ pub enum AppMsg {
     Open(Pos),
     /// Flag a position on the board.
     Flag(Position),
+    /// Open the neighbours of a position on the board.
+    Chord(Pos),
     /// Start a new game, resetting the board.
     Start,
-    /// Timer tick.
-    Tick(u64),
+    /// Select difficulty level and start a new game.
+    Difficulty(Difficulty),
+    /// Start a new game from a seed.
+    PlaySeed(u64),
+    /// Toggle no guess generation and start a new game.
+    NoGuess(bool),
+    /// Toggle practice mode and start a new game.
+    Practice(bool),
+    /// Toggle question marks and start a new game.
+    QuestionMarks(bool),
+    /// Take back the last move.
+    Undo,
+    /// Play the last undone move again.
+    Redo,
+    /// Show a position that can be deduced safe or mined.
+    Hint,
+    /// Toggle the mine probability heatmap.
+    Heatmap(bool),
+    /// Continue the game saved on the last exit.
+    Continue,
+    /// Play back the finished game.
+    Replay,
+    /// Pause or resume playback.
+    PlaybackToggle,
+    /// Play the next move of the replay.
+    PlaybackStep,
+    /// Select the playback speed by index.
+    PlaybackSpeed(usize),
+    /// Playback timer tick.
+    PlaybackTick,
+    /// Stop playback and show the game.
+    PlaybackClose,
+    /// Clock redraw tick.
+    Tick,
     /// Show win history.
     ShowHistory,
+    /// Show lifetime statistics.
+    ShowStatistics,
     /// Resume an active game.
     Resume,
+    /// Use the data folder, the default location when empty, and save it in
+    /// the settings.
+    DataDir(String),
+    /// Switch to the profile at the selector index and save it in the
+    /// settings.
+    Profile(usize),
+    /// Profile name entered.
+    ProfileName(String),
+    /// Create a profile with the entered name and switch to it.
+    CreateProfile,
+    /// Rename the active profile to the entered name.
+    RenameProfile,
+    /// Delete the active profile and switch to the anonymous profile.
+    DeleteProfile,
 }
 
 #[relm4::component(pub)]
@@ -103,7 +437,12 @@ impl SimpleComponent for AppModel {
                 #[name = "time_label"]
                 gtk::Label {
                   #[watch]
-                  set_label: &format_elapsed(model.time_elapsed),
+                  set_label: &format_elapsed(
+                      model
+                          .playback
+                          .as_ref()
+                          .map_or(model.board.elapsed().as_secs(), |p| p.player.position() / 1000),
+                  ),
                   set_css_classes: &["time"],
                   set_halign: gtk::Align::Start,
                 }
@@ -116,7 +455,7 @@ impl SimpleComponent for AppModel {
                 #[name = "opened"]
                 gtk::Label {
                   #[watch]
-                  set_label: &format!("{}", model.board.opened()),
+                  set_label: &format!("{}", model.shown_board().opened()),
                 }
               },
 
@@ -127,7 +466,7 @@ impl SimpleComponent for AppModel {
                 #[name = "flagged"]
                 gtk::Label {
                   #[watch]
-                  set_label: &format!("{}", model.board.flagged()),
+                  set_label: &format!("{}", model.shown_board().flagged()),
                 }
               },
 
@@ -138,7 +477,30 @@ impl SimpleComponent for AppModel {
                 #[name = "mined"]
                 gtk::Label {
                   #[watch]
-                  set_label:&format!("{}", model.board.mined()),
+                  set_label:&format!("{}", model.shown_board().mined()),
+                }
+              },
+
+              gtk::Box {
+                gtk::Label {
+                  set_label: "Hints: ",
+                },
+                #[name = "hints"]
+                gtk::Label {
+                  #[watch]
+                  set_label: &format!("{}", model.shown_board().hints()),
+                }
+              },
+
+              gtk::Box {
+                gtk::Label {
+                  set_label: "Seed: ",
+                },
+                #[name = "seed"]
+                gtk::Label {
+                  #[watch]
+                  set_label: &format!("{}", model.board.seed()),
+                  set_selectable: true,
                 }
               },
             },
@@ -162,19 +524,248 @@ impl SimpleComponent for AppModel {
               },
             },
 
+            gtk::Label {
+              #[watch]
+              set_label: &model
+                  .hint
+                  .map(|hint| format!("Hint: {hint}"))
+                  .unwrap_or_default(),
+            },
+
             gtk::Box {
                 set_halign: gtk::Align::Center,
                 set_spacing: 10,
+                #[watch]
+                set_visible: model.playback.is_some(),
+
+                gtk::Button {
+                    #[watch]
+                    set_label: if model.playback.as_ref().is_some_and(|p| p.playing) {
+                        "Pause"
+                    } else {
+                        "Play"
+                    },
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_sensitive: model.playback.as_ref().is_some_and(|p| !p.player.is_finished()),
+                    connect_clicked => AppMsg::PlaybackToggle
+                },
+
+                gtk::Button {
+                    set_label: "Step",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_sensitive: model.playback.as_ref().is_some_and(|p| !p.player.is_finished()),
+                    connect_clicked => AppMsg::PlaybackStep
+                },
+
+                gtk::DropDown::from_strings(&SPEED_LABELS) {
+                    #[watch]
+                    #[block_signal(speed_handler)]
+                    set_selected: model.playback.as_ref().map_or(1, |p| p.speed) as u32,
+                    connect_selected_notify[sender] => move |dropdown| {
+                        sender.input(AppMsg::PlaybackSpeed(dropdown.selected() as usize));
+                    } @speed_handler
+                },
+
+                gtk::Label {
+                    #[watch]
+                    set_label: &model
+                        .playback
+                        .as_ref()
+                        .map(|p| format!(
+                            "Move {} of {}",
+                            p.player.played(),
+                            p.player.replay().actions.len()
+                        ))
+                        .unwrap_or_default(),
+                },
+
+                gtk::Button {
+                    set_label: "Close",
+                    set_css_classes: &["button"],
+                    connect_clicked => AppMsg::PlaybackClose
+                },
+            },
+
+            gtk::Box {
+                set_halign: gtk::Align::Center,
+                set_spacing: 10,
+                #[watch]
+                set_visible: model.playback.is_none(),
                 gtk::Button {
                   set_label: "Top Scores",
                   set_css_classes: &["button"],
                   connect_clicked => AppMsg::ShowHistory
                 },
 
+                gtk::Button {
+                  set_label: "Statistics",
+                  set_css_classes: &["button"],
+                  connect_clicked => AppMsg::ShowStatistics
+                },
+
                 gtk::Button {
                     set_label: "Restart",
                     set_css_classes: &["button"],
                     connect_clicked => AppMsg::Start
+                },
+
+                gtk::Button {
+                    set_label: "Continue",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_visible: model.saved.is_some(),
+                    connect_clicked => AppMsg::Continue
+                },
+
+                gtk::Button {
+                    set_label: "Replay",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_sensitive: matches!(model.board.state(), GameState::Win | GameState::Loss),
+                    connect_clicked => AppMsg::Replay
+                },
+
+                gtk::Button {
+                    set_label: "Hint",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_sensitive: *model.board.state() == GameState::Active,
+                    connect_clicked => AppMsg::Hint
+                },
+
+                gtk::Button {
+                    set_label: "Undo",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_visible: model.board.config().practice(),
+                    #[watch]
+                    set_sensitive: model.board.can_undo(),
+                    connect_clicked => AppMsg::Undo
+                },
+
+                gtk::Button {
+                    set_label: "Redo",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_visible: model.board.config().practice(),
+                    #[watch]
+                    set_sensitive: model.board.can_redo(),
+                    connect_clicked => AppMsg::Redo
+                },
+
+                gtk::CheckButton {
+                    set_label: Some("Heatmap"),
+                    connect_toggled[sender] => move |check| {
+                        sender.input(AppMsg::Heatmap(check.is_active()));
+                    }
+                },
+
+                gtk::CheckButton {
+                    set_label: Some("No guess"),
+                    #[watch]
+                    #[block_signal(no_guess_handler)]
+                    set_active: model.board.config().generation() == GenerationMode::NoGuess,
+                    connect_toggled[sender] => move |check| {
+                        sender.input(AppMsg::NoGuess(check.is_active()));
+                    } @no_guess_handler
+                },
+
+                gtk::CheckButton {
+                    set_label: Some("Practice"),
+                    #[watch]
+                    #[block_signal(practice_handler)]
+                    set_active: model.board.config().practice(),
+                    connect_toggled[sender] => move |check| {
+                        sender.input(AppMsg::Practice(check.is_active()));
+                    } @practice_handler
+                },
+
+                gtk::CheckButton {
+                    set_label: Some("Question marks"),
+                    #[watch]
+                    #[block_signal(question_marks_handler)]
+                    set_active: model.board.question_marks(),
+                    connect_toggled[sender] => move |check| {
+                        sender.input(AppMsg::QuestionMarks(check.is_active()));
+                    } @question_marks_handler
+                },
+
+                gtk::Entry {
+                    set_placeholder_text: Some("Seed"),
+                    connect_activate[sender] => move |entry| {
+                        if let Ok(seed) = entry.text().trim().parse() {
+                            sender.input(AppMsg::PlaySeed(seed));
+                        }
+                    }
+                },
+
+                gtk::Entry {
+                    set_placeholder_text: Some("Data folder"),
+                    set_text: &data_dir_text,
+                    connect_activate[sender] => move |entry| {
+                        sender.input(AppMsg::DataDir(entry.text().into()));
+                    }
+                },
+
+                gtk::DropDown {
+                    #[watch]
+                    #[block_signal(profile_handler)]
+                    set_model: Some(&model.profile_labels),
+                    #[watch]
+                    #[block_signal(profile_handler)]
+                    set_selected: model.profile_index() as u32,
+                    connect_selected_notify[sender] => move |dropdown| {
+                        sender.input(AppMsg::Profile(dropdown.selected() as usize));
+                    } @profile_handler
+                },
+
+                gtk::Entry {
+                    set_placeholder_text: Some("Profile name"),
+                    connect_changed[sender] => move |entry| {
+                        sender.input(AppMsg::ProfileName(entry.text().into()));
+                    },
+                    connect_activate => AppMsg::CreateProfile,
+                },
+
+                gtk::Button {
+                    set_label: "Create",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_sensitive: !model.profile_name.trim().is_empty(),
+                    connect_clicked => AppMsg::CreateProfile
+                },
+
+                gtk::Button {
+                    set_label: "Rename",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_sensitive: model.data_dir.profile().is_some()
+                        && !model.profile_name.trim().is_empty(),
+                    connect_clicked => AppMsg::RenameProfile
+                },
+
+                gtk::Button {
+                    set_label: "Delete",
+                    set_css_classes: &["button"],
+                    #[watch]
+                    set_sensitive: model.data_dir.profile().is_some(),
+                    connect_clicked => AppMsg::DeleteProfile
+                },
+
+                gtk::DropDown::from_strings(&DIFFICULTY_LABELS) {
+                    #[watch]
+                    #[block_signal(difficulty_handler)]
+                    set_selected: Difficulty::PRESETS
+                        .iter()
+                        .position(|&d| d == model.board.config().difficulty())
+                        .unwrap_or_default() as u32,
+                    connect_selected_notify[sender] => move |dropdown| {
+                        if let Some(&difficulty) = Difficulty::PRESETS.get(dropdown.selected() as usize) {
+                            sender.input(AppMsg::Difficulty(difficulty));
+                        }
+                    } @difficulty_handler
                 }
             }
           },
@@ -191,9 +782,10 @@ impl SimpleComponent for AppModel {
             .forward(sender.input_sender(), |output| match output {
                 PositionOutput::Open(p) => AppMsg::Open(p),
                 PositionOutput::Flag(p) => AppMsg::Flag(p),
+                PositionOutput::Chord(p) => AppMsg::Chord(p),
             });
 
-        for (&pos, &cell) in board.positions() {
+        for (pos, &cell) in board.positions() {
             positions.guard().push_back((pos, cell));
         }
 
@@ -202,30 +794,74 @@ impl SimpleComponent for AppModel {
             .map(|Position { index, pos, .. }| (*pos, *index))
             .collect::<HashMap<_, _>>();
 
-        let model = AppModel {
+        // Redraw the game clock every second.
+        let tick = sender.clone();
+        glib::timeout_add_local(Duration::from_secs(1), move || {
+            tick.input(AppMsg::Tick);
+            glib::ControlFlow::Continue
+        });
+
+        let settings = load_settings().unwrap_or_else(|err| {
+            eprintln!("Failed to load settings: {err}");
+            Settings::default()
+        });
+        let data_dir = data_dir(&settings);
+        let data_dir_text = settings
+            .data_dir
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        // Start on the board last chosen in the profile.
+        let mut board = match load_preferences(&data_dir) {
+            Ok(Some(preferences)) => crate::board(
+                preferences.difficulty,
+                preferences.generation,
+                false,
+                preferences.question_marks,
+                None,
+            ),
+            Ok(None) => board,
+            Err(err) => {
+                eprintln!("Failed to load preferences: {err}");
+                board
+            }
+        };
+
+        let events = board.subscribe();
+        let mut model = AppModel {
             board,
+            events,
+            saved: load_game(&data_dir).unwrap_or_else(|err| {
+                eprintln!("Failed to load saved game: {err}");
+                None
+            }),
+            data_dir: data_dir.clone(),
+            profiles: vec![],
+            profile_labels: gtk::StringList::new(&[]),
+            profile_name: String::new(),
             pos_map,
             positions,
             dialog: StatusDialogModel::builder()
                 .transient_for(&root)
                 .launch(true)
                 .detach(),
-            timer_worker: GameTimer::builder().detach_worker(()).forward(
-                sender.input_sender(),
-                |msg| match msg {
-                    GameTimerOutput::Tick(n) => AppMsg::Tick(n),
-                },
-            ),
-            time_elapsed: 0,
-            time_paused: 0,
             paused: false,
             history_window: WinHistoryView::builder()
                 .transient_for(&root)
-                .launch(())
+                .launch(data_dir.clone())
                 .forward(sender.input_sender(), |msg| match msg {
                     HistoryOut::Resume => AppMsg::Resume,
                 }),
+            statistics_window: StatisticsView::builder()
+                .transient_for(&root)
+                .launch(data_dir)
+                .forward(sender.input_sender(), |msg| match msg {
+                    StatisticsOut::Resume => AppMsg::Resume,
+                }),
+            hint: None,
+            heatmap: false,
+            playback: None,
         };
+        model.load_profiles();
 
         let factory_board = model.positions.widget();
         let widgets = view_output!();
@@ -233,87 +869,290 @@ impl SimpleComponent for AppModel {
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        // A hint only applies to the board it was given for.
+        if matches!(
+            msg,
+            AppMsg::Open(_) | AppMsg::Flag(_) | AppMsg::Chord(_) | AppMsg::Undo | AppMsg::Redo
+        ) {
+            self.show_hint(None);
+        }
+        // Playing or starting another game replaces the saved game on exit,
+        // abandoning it.
+        if matches!(
+            msg,
+            AppMsg::Open(_)
+                | AppMsg::Start
+                | AppMsg::Difficulty(_)
+                | AppMsg::PlaySeed(_)
+                | AppMsg::NoGuess(_)
+                | AppMsg::Practice(_)
+                | AppMsg::QuestionMarks(_)
+        ) {
+            if let Some(saved) = self.saved.take() {
+                record_game(&self.data_dir, &saved.board)
+                    .unwrap_or_else(|e| eprintln!("Failed to save statistics {e}"));
+            }
+        }
+        let board_changed = !matches!(
+            msg,
+            AppMsg::Tick
+                | AppMsg::ShowHistory
+                | AppMsg::ShowStatistics
+                | AppMsg::Resume
+                | AppMsg::Hint
+                | AppMsg::Replay
+                | AppMsg::PlaybackToggle
+                | AppMsg::PlaybackStep
+                | AppMsg::PlaybackSpeed(_)
+                | AppMsg::PlaybackTick
+                | AppMsg::PlaybackClose
+                | AppMsg::ProfileName(_)
+        );
+
         match msg {
             AppMsg::Open(p) => {
-                if self.board.state() == &GameState::New {
-                    self.timer_worker
-                        .sender()
-                        .send(GameTimerInput::Start)
-                        .unwrap();
-                }
-                let opened = self.board.open_cell(p);
-
-                match *self.board.state() {
-                    s @ GameState::Loss | s @ GameState::Win => {
-                        if s == GameState::Win {
-                            save_win(self.time_elapsed)
-                                .unwrap_or_else(|e| eprintln!("Failed to save game win {e}"));
-                            self.history_window.emit(HistoryMsg::Reload);
-                        }
-                        self.update_all_positions();
-                        self.timer_worker
-                            .sender()
-                            .send(GameTimerInput::Stop)
-                            .unwrap_or_else(|_| eprintln!("Failed to stop timer"));
-                        self.dialog
-                            .sender()
-                            .send(StatusMsg::Open(if s == GameState::Win {
-                                "You win!".into()
-                            } else {
-                                "You lose!".into()
-                            }))
-                            .unwrap_or_else(|_| eprintln!("Failed to send message"));
-                    }
-                    _ => {
-                        let matched_pos = opened
-                            .into_iter()
-                            .flat_map(|(pos, cell)| {
-                                self.pos_map
-                                    .get(&pos)
-                                    .map(|&index| Position { pos, cell, index })
-                            })
-                            .collect::<Vec<_>>();
-                        self.update_positions(&matched_pos);
-                    }
+                let before = *self.board.state();
+                match self.board.open_cell(p) {
+                    Ok(_) => self.handle_opened(before),
+                    Err(err) => self.dialog.emit(StatusMsg::Open(err.to_string())),
                 }
             }
+            AppMsg::Chord(p) => {
+                let before = *self.board.state();
+                self.board.chord(p);
+                self.handle_opened(before);
+            }
             AppMsg::Flag(p) => {
-                if let Some(position) = self.board.flag_cell(p.pos).and_then(|(pos, cell)| {
-                    self.pos_map
-                        .get(&pos)
-                        .map(|&index| Position { pos, cell, index })
-                }) {
-                    self.update_positions(&[position]);
+                self.board.flag_cell(p.pos);
+                self.apply_events();
+            }
+            AppMsg::Hint => {
+                let hint = self.board.hint();
+                self.show_hint(hint);
+            }
+            AppMsg::Heatmap(heatmap) => {
+                self.heatmap = heatmap;
+            }
+            AppMsg::Continue => {
+                if let Some(saved) = self.saved.take() {
+                    self.set_board(saved.board);
+                    self.board.resume_clock();
                 }
             }
-            AppMsg::Start => {
-                self.timer_worker.emit(GameTimerInput::Stop);
-                self.board = board();
+            AppMsg::Replay => {
+                self.close_playback();
+                let timer = glib::timeout_add_local(PLAYBACK_TICK, move || {
+                    sender.input(AppMsg::PlaybackTick);
+                    glib::ControlFlow::Continue
+                });
+                let mut player = Player::new(Replay::new(&self.board));
+                self.playback = Some(Playback {
+                    events: player.subscribe(),
+                    player,
+                    playing: true,
+                    speed: 1,
+                    timer,
+                });
                 self.update_all_positions();
-                self.time_elapsed = 0;
-                self.time_paused = 0;
-                self.paused = false;
             }
-            AppMsg::Tick(seconds) => {
-                if !self.paused && *self.board.state() == GameState::Active {
-                    self.time_elapsed = seconds + self.time_paused;
+            AppMsg::PlaybackToggle => {
+                if let Some(playback) = &mut self.playback {
+                    playback.playing = !playback.playing;
                 }
             }
-            AppMsg::ShowHistory => {
-                if *self.board.state() == GameState::Active {
-                    self.paused = true;
-                    self.timer_worker.emit(GameTimerInput::Stop);
-                    self.time_paused = self.time_elapsed;
+            AppMsg::PlaybackStep => {
+                if let Some(playback) = &mut self.playback {
+                    playback.playing = false;
+                    playback.player.step();
+                    self.handle_playback();
+                }
+            }
+            AppMsg::PlaybackSpeed(speed) => {
+                if let Some(playback) = &mut self.playback {
+                    playback.speed = speed.min(SPEEDS.len() - 1);
                 }
-                self.history_window.emit(super::history::HistoryMsg::Open);
+            }
+            AppMsg::PlaybackTick => {
+                if let Some(playback) = self.playback.as_mut().filter(|p| p.playing) {
+                    let millis = PLAYBACK_TICK.as_millis() as f64 * SPEEDS[playback.speed];
+                    playback.player.advance(millis as u64);
+                    self.handle_playback();
+                }
+            }
+            AppMsg::PlaybackClose => {
+                self.close_playback();
+            }
+            AppMsg::Start => {
+                let config = *self.board.config();
+                self.restart(config.difficulty(), config.generation(), None);
+            }
+            AppMsg::Difficulty(difficulty) => {
+                self.restart(difficulty, self.board.config().generation(), None);
+                self.save_preferences();
+            }
+            AppMsg::NoGuess(no_guess) => {
+                let generation = if no_guess {
+                    GenerationMode::NoGuess
+                } else {
+                    GenerationMode::Random
+                };
+                self.restart(self.board.config().difficulty(), generation, None);
+                self.save_preferences();
+            }
+            AppMsg::Practice(practice) => {
+                let config = *self.board.config();
+                self.set_board(board(
+                    config.difficulty(),
+                    config.generation(),
+                    practice,
+                    self.board.question_marks(),
+                    None,
+                ));
+            }
+            AppMsg::QuestionMarks(question_marks) => {
+                let config = *self.board.config();
+                self.set_board(board(
+                    config.difficulty(),
+                    config.generation(),
+                    config.practice(),
+                    question_marks,
+                    None,
+                ));
+                self.save_preferences();
+            }
+            AppMsg::Undo => match self.board.undo() {
+                Ok(_) => self.apply_events(),
+                Err(err) => eprintln!("Failed to undo: {err}"),
+            },
+            AppMsg::Redo => match self.board.redo() {
+                Ok(_) => self.apply_events(),
+                Err(err) => eprintln!("Failed to redo: {err}"),
+            },
+            AppMsg::PlaySeed(seed) => {
+                let config = *self.board.config();
+                self.restart(config.difficulty(), config.generation(), Some(seed));
+            }
+            // The clock label is redrawn from the board clock.
+            AppMsg::Tick => (),
+            AppMsg::ShowHistory => {
+                self.pause();
+                self.history_window
+                    .emit(HistoryMsg::Open(*self.board.config()));
+            }
+            AppMsg::ShowStatistics => {
+                self.pause();
+                self.statistics_window.emit(StatisticsMsg::Open);
             }
             AppMsg::Resume => {
-                if self.paused && *self.board.state() == GameState::Active {
-                    self.timer_worker.emit(GameTimerInput::Start);
+                if self.paused {
+                    self.board.resume_clock();
                     self.paused = false;
                 }
             }
+            AppMsg::DataDir(path) => {
+                let path = path.trim();
+                let settings = Settings {
+                    data_dir: (!path.is_empty()).then(|| path.into()),
+                    profile: self.data_dir.profile().map(ToOwned::to_owned),
+                };
+                self.switch_data_dir(data_dir(&settings));
+                update_settings(|saved| saved.data_dir = settings.data_dir)
+                    .unwrap_or_else(|e| eprintln!("Failed to save settings {e}"));
+            }
+            AppMsg::Profile(index) => {
+                if index != self.profile_index() {
+                    let profile = index
+                        .checked_sub(1)
+                        .and_then(|index| self.profiles.get(index))
+                        .cloned();
+                    match self.data_dir.clone().with_profile(profile.as_deref()) {
+                        Ok(data_dir) => {
+                            self.switch_data_dir(data_dir);
+                            self.save_profile();
+                        }
+                        Err(err) => eprintln!("Failed to switch profile: {err}"),
+                    }
+                }
+            }
+            AppMsg::ProfileName(name) => {
+                self.profile_name = name;
+            }
+            AppMsg::CreateProfile => {
+                let name = self.profile_name.trim();
+                let created = create_profile(&self.data_dir, name)
+                    .and_then(|()| self.data_dir.clone().with_profile(Some(name)));
+                match created {
+                    Ok(data_dir) => {
+                        self.switch_data_dir(data_dir);
+                        self.save_profile();
+                    }
+                    Err(err) => eprintln!("Failed to create profile: {err}"),
+                }
+            }
+            AppMsg::RenameProfile => {
+                if let Some(profile) = self.data_dir.profile() {
+                    let name = self.profile_name.trim();
+                    let renamed = rename_profile(&self.data_dir, profile, name)
+                        .and_then(|()| self.data_dir.clone().with_profile(Some(name)));
+                    match renamed {
+                        Ok(data_dir) => {
+                            // The game in progress continues in the renamed
+                            // profile.
+                            self.data_dir = data_dir;
+                            self.load_profiles();
+                            self.save_profile();
+                            self.history_window
+                                .emit(HistoryMsg::DataDir(self.data_dir.clone()));
+                            self.statistics_window
+                                .emit(StatisticsMsg::DataDir(self.data_dir.clone()));
+                        }
+                        Err(err) => eprintln!("Failed to rename profile: {err}"),
+                    }
+                }
+            }
+            AppMsg::DeleteProfile => {
+                if let Some(profile) = self.data_dir.profile().map(ToOwned::to_owned) {
+                    let deleted = self
+                        .data_dir
+                        .clone()
+                        .with_profile(None)
+                        .and_then(|data_dir| {
+                            delete_profile(&data_dir, &profile).map(|()| data_dir)
+                        });
+                    match deleted {
+                        Ok(data_dir) => {
+                            // The game in progress belonged to the deleted
+                            // profile and is dropped with it.
+                            let config = *self.board.config();
+                            self.board = board(
+                                config.difficulty(),
+                                config.generation(),
+                                config.practice(),
+                                self.board.question_marks(),
+                                None,
+                            );
+                            self.switch_data_dir(data_dir);
+                            self.save_profile();
+                        }
+                        Err(err) => eprintln!("Failed to delete profile: {err}"),
+                    }
+                }
+            }
+        }
+
+        if board_changed {
+            self.update_heatmap();
+        }
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        if self.saved.is_some() {
+            // The saved game was not continued.
+            return;
+        }
+        if let Err(err) = save_game(&self.data_dir, &self.board) {
+            eprintln!("Failed to save game: {err}");
         }
     }
 }
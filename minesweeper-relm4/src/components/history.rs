@@ -1,17 +1,61 @@
-use crate::format_elapsed;
+use crate::format_time;
 use chrono::{DateTime, Local};
-use minesweeper::history::{load_wins, Win};
+use minesweeper::{
+    history::{export_wins, import_wins, load_wins, DataDir, Format, Leaderboard, Win, WinHistory},
+    model::{BoardConfig, GenerationMode},
+};
 use relm4::{
     factory::FactoryVecDeque, gtk, gtk::prelude::*, prelude::FactoryComponent, ComponentParts,
     SimpleComponent,
 };
+use std::{path::Path, time::Duration};
 
 #[derive(Debug)]
 pub struct WinHistoryView {
+    /// Folder the win history is loaded from.
+    data_dir: DataDir,
+    history: WinHistory,
+    leaderboards: Vec<Leaderboard>,
+    /// Names of the leaderboards for the selector.
+    labels: gtk::StringList,
+    /// Index of the shown leaderboard.
+    selected: usize,
     win_history: FactoryVecDeque<WinData>,
+    /// File the wins are exported to or imported from.
+    file: String,
     hidden: bool,
 }
 
+impl WinHistoryView {
+    /// Load the win history and the leaderboards with wins.
+    fn load(&mut self) {
+        self.history = load_wins(&self.data_dir).unwrap_or_else(|err| {
+            eprintln!("Failed to load wins: {err}");
+            Default::default()
+        });
+        self.leaderboards = self.history.leaderboards();
+        let names = self
+            .leaderboards
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+        self.labels.splice(0, self.labels.n_items(), &names);
+    }
+
+    /// Show the wins of the leaderboard at the index.
+    fn show(&mut self, selected: usize) {
+        self.selected = selected;
+        let mut win_history = self.win_history.guard();
+        win_history.clear();
+        if let Some(&leaderboard) = self.leaderboards.get(selected) {
+            for win in self.history.wins_on(leaderboard) {
+                win_history.push_back(WinData(win.clone()));
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct WinData(Win);
 
@@ -20,16 +64,41 @@ impl WinData {
         self.0.date
     }
 
-    fn duration(&self) -> u64 {
-        self.0.duration
+    fn score(&self) -> Duration {
+        self.0.score()
+    }
+
+    fn hints(&self) -> String {
+        match self.0.hints {
+            0 => String::new(),
+            hints => format!("Hints: {hints}"),
+        }
+    }
+
+    fn generation(&self) -> &'static str {
+        match self.0.generation() {
+            GenerationMode::NoGuess => "No guess",
+            GenerationMode::Random => "",
+        }
     }
 }
 
 #[derive(Debug)]
 pub enum HistoryMsg {
-    Open,
+    /// Open showing the leaderboard of the board configuration.
+    Open(BoardConfig),
     Close,
     Reload,
+    /// Show the leaderboard at the index.
+    Select(usize),
+    /// Load the win history from another data folder.
+    DataDir(DataDir),
+    /// File entered for export and import.
+    File(String),
+    /// Export the wins to the entered file.
+    Export,
+    /// Import the wins from the entered file.
+    Import,
 }
 
 #[derive(Debug)]
@@ -41,7 +110,7 @@ pub enum HistoryOut {
 impl SimpleComponent for WinHistoryView {
     type Input = HistoryMsg;
     type Output = HistoryOut;
-    type Init = ();
+    type Init = DataDir;
 
     view! {
         gtk::Window {
@@ -63,11 +132,46 @@ impl SimpleComponent for WinHistoryView {
                     set_css_classes: &["winHistoryHeader"],
                 },
 
-                // #[local_ref]
-                gtk::Box {
+                gtk::DropDown {
+                    set_model: Some(&model.labels),
+                    #[watch]
+                    #[block_signal(select_handler)]
+                    set_selected: model.selected as u32,
+                    connect_selected_notify[sender] => move |dropdown| {
+                        sender.input(HistoryMsg::Select(dropdown.selected() as usize));
+                    } @select_handler
+                },
+
+                #[local_ref]
+                win_box -> gtk::Box {
                     set_vexpand: true,
                     set_orientation: gtk::Orientation::Vertical,
                 },
+                gtk::Box {
+                    set_spacing: 10,
+                    set_orientation: gtk::Orientation::Horizontal,
+                    gtk::Entry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some("Wins file (.json or .csv)"),
+                        connect_changed[sender] => move |entry| {
+                            sender.input(HistoryMsg::File(entry.text().into()));
+                        }
+                    },
+                    gtk::Button {
+                        set_css_classes: &["winHistoryButton"],
+                        set_label: "Export",
+                        #[watch]
+                        set_sensitive: !model.file.trim().is_empty(),
+                        connect_clicked => HistoryMsg::Export,
+                    },
+                    gtk::Button {
+                        set_css_classes: &["winHistoryButton"],
+                        set_label: "Import",
+                        #[watch]
+                        set_sensitive: !model.file.trim().is_empty(),
+                        connect_clicked => HistoryMsg::Import,
+                    },
+                },
                 gtk::Button {
                     set_css_classes: &["winHistoryButton"],
                     set_label: "Close",
@@ -78,21 +182,25 @@ impl SimpleComponent for WinHistoryView {
     }
 
     fn init(
-        _init: Self::Init,
+        data_dir: Self::Init,
         root: Self::Root,
         sender: relm4::ComponentSender<Self>,
     ) -> relm4::ComponentParts<Self> {
-        let wins = load_wins().map(|w| {
-            FactoryVecDeque::from_iter(w.wins.into_iter().map(WinData), gtk::Box::default())
-        });
-
-        let win_history = wins
-            .unwrap_or_else(|| FactoryVecDeque::from_iter(std::iter::empty(), gtk::Box::default()));
-        let model = WinHistoryView {
+        let mut model = WinHistoryView {
+            data_dir,
+            history: WinHistory::default(),
+            leaderboards: vec![],
+            labels: gtk::StringList::new(&[]),
+            selected: 0,
+            win_history: FactoryVecDeque::builder()
+                .launch(gtk::Box::default())
+                .detach(),
+            file: String::new(),
             hidden: true,
-            win_history,
         };
-        let _win_box = model.win_history.widget();
+        model.load();
+        model.show(0);
+        let win_box = model.win_history.widget();
         let widgets = view_output!();
 
         ComponentParts { model, widgets }
@@ -100,7 +208,13 @@ impl SimpleComponent for WinHistoryView {
 
     fn update(&mut self, message: Self::Input, sender: relm4::ComponentSender<Self>) {
         match message {
-            HistoryMsg::Open => {
+            HistoryMsg::Open(config) => {
+                let selected = self
+                    .history
+                    .default_leaderboard(&config)
+                    .and_then(|current| self.leaderboards.iter().position(|&l| l == current))
+                    .unwrap_or_default();
+                self.show(selected);
                 self.hidden = false;
             }
             HistoryMsg::Close => {
@@ -108,9 +222,38 @@ impl SimpleComponent for WinHistoryView {
                 sender.output_sender().emit(HistoryOut::Resume);
             }
             HistoryMsg::Reload => {
-                self.win_history.guard().clear();
-                for win in load_wins().into_iter().flat_map(|w| w.wins.into_iter()) {
-                    self.win_history.guard().push_back(WinData(win));
+                let shown = self.leaderboards.get(self.selected).copied();
+                self.load();
+                let selected = shown
+                    .and_then(|shown| self.leaderboards.iter().position(|&l| l == shown))
+                    .unwrap_or_default();
+                self.show(selected);
+            }
+            HistoryMsg::Select(selected) => {
+                self.show(selected);
+            }
+            HistoryMsg::DataDir(data_dir) => {
+                self.data_dir = data_dir;
+                self.load();
+                self.show(0);
+            }
+            HistoryMsg::File(file) => {
+                self.file = file;
+            }
+            HistoryMsg::Export => {
+                let path = Path::new(self.file.trim());
+                if let Err(err) =
+                    Format::of(path).and_then(|format| export_wins(&self.history, path, format))
+                {
+                    eprintln!("Failed to export wins: {err}");
+                }
+            }
+            HistoryMsg::Import => {
+                let path = Path::new(self.file.trim());
+                match Format::of(path).and_then(|format| import_wins(&self.data_dir, path, format))
+                {
+                    Ok(_) => sender.input(HistoryMsg::Reload),
+                    Err(err) => eprintln!("Failed to import wins: {err}"),
                 }
             }
         }
@@ -147,10 +290,16 @@ impl FactoryComponent for WinData {
                 set_orientation: gtk::Orientation::Horizontal,
                 set_hexpand: true,
                 gtk::Label {
-                    set_label: &format_elapsed(self.duration())
+                    set_label: &format_time(self.score())
                 },
                 gtk::Label {
                     set_label: &format!("{}", self.date().format("%b %e / %G %R"))
+                },
+                gtk::Label {
+                    set_label: self.generation()
+                },
+                gtk::Label {
+                    set_label: &self.hints()
                 }
             }
         }
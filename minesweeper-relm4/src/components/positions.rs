@@ -1,5 +1,8 @@
 use crate::{types::Position, BOMB, FLAG};
-use minesweeper::model::{Cell, CellState, Pos};
+use minesweeper::{
+    model::{Cell, CellState, Pos},
+    solver::Verdict,
+};
 use relm4::{
     factory::{positions::GridPosition, FactoryComponent, Position as FactoryPosition},
     gtk,
@@ -12,15 +15,18 @@ pub struct FactoryWidgets {
     container: gtk::Box,
     button: gtk::Button,
     _gesture: gtk::GestureClick,
+    _middle_gesture: gtk::GestureClick,
 }
 
 #[derive(Debug)]
 pub enum PositionOutput {
     Open(Pos),
     Flag(Position),
+    Chord(Pos),
 }
 
 static EMPTY: &str = "";
+static QUESTION: &str = "?";
 
 impl FactoryComponent for Position {
     type Init = (Pos, Cell);
@@ -41,6 +47,8 @@ impl FactoryComponent for Position {
             index: dyn_index.current_index(),
             pos,
             cell,
+            hint: None,
+            probability: None,
         }
     }
 
@@ -69,12 +77,21 @@ impl FactoryComponent for Position {
                     button = button.label(adjacent_mine_label(*self));
                 }
             }
-            CellState::Closed { flagged, .. } => {
+            CellState::Closed {
+                flagged,
+                questioned,
+                ..
+            } => {
                 if flagged {
                     button = button.css_classes(vec!["cell", "flagged"]).label(FLAG);
                     container = container.css_classes(vec!["flagged"]);
+                } else if questioned {
+                    button = button.css_classes(vec!["cell", "closed"]).label(QUESTION);
+                    container = container.css_classes(vec!["closed"]);
                 } else {
-                    button = button.css_classes(vec!["cell", "closed"]);
+                    button = button
+                        .css_classes(vec!["cell", "closed", hint_style(*self), heat_style(*self)])
+                        .label(probability_label(*self));
                     container = container.css_classes(vec!["closed"]);
                 }
             }
@@ -97,6 +114,7 @@ impl FactoryComponent for Position {
         let right_click = gtk::GestureClick::builder().button(3).build();
         {
             let pos_selected = *self;
+            let sender = sender.clone();
             right_click.connect_pressed(move |gesture, _, _, _| {
                 gesture.set_state(gtk::EventSequenceState::Claimed);
                 if let Err(err) = sender.output(PositionOutput::Flag(pos_selected)) {
@@ -104,8 +122,19 @@ impl FactoryComponent for Position {
                 };
             });
         }
+        let middle_click = gtk::GestureClick::builder().button(2).build();
+        {
+            let pos_selected = self.pos;
+            middle_click.connect_pressed(move |gesture, _, _, _| {
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                if let Err(err) = sender.output(PositionOutput::Chord(pos_selected)) {
+                    eprintln!("Failed to send chord cell {err:?}");
+                };
+            });
+        }
         container.append(&button);
         container.add_controller(right_click.clone());
+        container.add_controller(middle_click.clone());
 
         root.append(&container);
 
@@ -113,10 +142,12 @@ impl FactoryComponent for Position {
             container,
             button,
             _gesture: right_click,
+            _middle_gesture: middle_click,
         }
     }
 
     fn update_view(&self, widgets: &mut Self::Widgets, _sender: relm4::FactorySender<Self>) {
+        let probability = probability_label(*self);
         let label = match self.cell.state {
             CellState::Open => {
                 widgets.button.set_css_classes(&[
@@ -131,15 +162,28 @@ impl FactoryComponent for Position {
                     EMPTY
                 }
             }
-            CellState::Closed { flagged, .. } => {
+            CellState::Closed {
+                flagged,
+                questioned,
+                ..
+            } => {
                 if flagged {
                     widgets.button.set_css_classes(&["cell", "flagged"]);
                     widgets.container.set_css_classes(&["flagged"]);
                     FLAG
-                } else {
+                } else if questioned {
                     widgets.button.set_css_classes(&["cell", "closed"]);
                     widgets.container.set_css_classes(&["closed"]);
-                    EMPTY
+                    QUESTION
+                } else {
+                    widgets.button.set_css_classes(&[
+                        "cell",
+                        "closed",
+                        hint_style(*self),
+                        heat_style(*self),
+                    ]);
+                    widgets.container.set_css_classes(&["closed"]);
+                    probability.as_str()
                 }
             }
             CellState::ExposedMine => {
@@ -177,6 +221,33 @@ fn adjacent_mine_label(pos: Position) -> &'static str {
     }
 }
 
+fn hint_style(pos: Position) -> &'static str {
+    match pos.hint {
+        Some(Verdict::Safe) => "hintSafe",
+        Some(Verdict::Mine) => "hintMine",
+        None => "",
+    }
+}
+
+fn heat_style(pos: Position) -> &'static str {
+    match pos.probability.map(|p| p.value) {
+        Some(p) if p < 0.2 => "heatLow",
+        Some(p) if p < 0.5 => "heatMid",
+        Some(_) => "heatHigh",
+        None => "",
+    }
+}
+
+/// Probability in percent, estimates are marked with `~`.
+fn probability_label(pos: Position) -> String {
+    pos.probability
+        .map(|p| {
+            let mark = if p.exact { "" } else { "~" };
+            format!("{mark}{:.0}", p.value * 100.)
+        })
+        .unwrap_or_default()
+}
+
 fn adjacent_mine_style(pos: Position) -> Option<&'static str> {
     match pos.cell.adjacent_mines {
         0 => None?,
@@ -0,0 +1,161 @@
+use crate::format_time;
+use minesweeper::history::{load_statistics, DataDir};
+use relm4::{gtk, gtk::prelude::*, ComponentParts, SimpleComponent};
+use std::time::Duration;
+
+/// Column headings of the statistics table.
+const HEADINGS: [&str; 10] = [
+    "Board",
+    "Played",
+    "Won",
+    "Lost",
+    "Abandoned",
+    "Streak",
+    "Best streak",
+    "Best time",
+    "Average time",
+    "Efficiency",
+];
+
+/// Lifetime statistics of every board configuration played.
+#[derive(Debug)]
+pub struct StatisticsView {
+    /// Folder the statistics are loaded from.
+    data_dir: DataDir,
+    /// Table with a row for every board configuration.
+    grid: gtk::Grid,
+    hidden: bool,
+}
+
+impl StatisticsView {
+    /// Load the statistics into the table.
+    fn load(&self) {
+        while let Some(child) = self.grid.first_child() {
+            self.grid.remove(&child);
+        }
+        let statistics = load_statistics(&self.data_dir).unwrap_or_else(|err| {
+            eprintln!("Failed to load statistics: {err}");
+            Default::default()
+        });
+        if statistics.boards.is_empty() {
+            self.grid
+                .attach(&gtk::Label::new(Some("No games played")), 0, 0, 1, 1);
+            return;
+        }
+        for (column, heading) in (0..).zip(HEADINGS) {
+            let label = gtk::Label::new(Some(heading));
+            label.set_css_classes(&["statisticsHeading"]);
+            self.grid.attach(&label, column, 0, 1, 1);
+        }
+        let time = |time: Option<Duration>| time.map_or("-".into(), format_time);
+        for (row, (config, stats)) in (1..).zip(&statistics.boards) {
+            let cells = [
+                config.to_string(),
+                stats.played.to_string(),
+                format!("{:.0}%", stats.win_rate()),
+                stats.losses.to_string(),
+                stats.abandoned.to_string(),
+                stats.current_streak.to_string(),
+                stats.best_streak.to_string(),
+                time(stats.best_time),
+                time(stats.average_time()),
+                stats
+                    .efficiency()
+                    .map_or("-".into(), |efficiency| format!("{efficiency:.0}%")),
+            ];
+            for (column, cell) in (0..).zip(cells) {
+                let label = gtk::Label::new(Some(&cell));
+                label.set_halign(gtk::Align::Start);
+                self.grid.attach(&label, column, row, 1, 1);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum StatisticsMsg {
+    Open,
+    Close,
+    /// Load the statistics from another data folder.
+    DataDir(DataDir),
+}
+
+#[derive(Debug)]
+pub enum StatisticsOut {
+    Resume,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for StatisticsView {
+    type Input = StatisticsMsg;
+    type Output = StatisticsOut;
+    type Init = DataDir;
+
+    view! {
+        gtk::Window {
+            set_modal: true,
+            set_default_width: 400,
+            set_default_height: 200,
+            #[watch]
+            set_visible: !model.hidden,
+            set_deletable: false,
+            set_decorated: false,
+
+            #[wrap(Some)]
+            set_child = &gtk::Box {
+                set_css_classes: &["winHistoryWindow"],
+                set_orientation: gtk::Orientation::Vertical,
+
+                gtk::Label {
+                    set_label: "Statistics",
+                    set_css_classes: &["winHistoryHeader"],
+                },
+
+                #[local_ref]
+                stats_grid -> gtk::Grid {
+                    set_vexpand: true,
+                    set_css_classes: &["statistics"],
+                    set_column_spacing: 15,
+                    set_row_spacing: 5,
+                },
+                gtk::Button {
+                    set_css_classes: &["winHistoryButton"],
+                    set_label: "Close",
+                    connect_clicked => StatisticsMsg::Close,
+                }
+            },
+        }
+    }
+
+    fn init(
+        data_dir: Self::Init,
+        root: Self::Root,
+        sender: relm4::ComponentSender<Self>,
+    ) -> relm4::ComponentParts<Self> {
+        let model = StatisticsView {
+            data_dir,
+            grid: gtk::Grid::default(),
+            hidden: true,
+        };
+        let stats_grid = &model.grid;
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: relm4::ComponentSender<Self>) {
+        match message {
+            StatisticsMsg::Open => {
+                self.load();
+                self.hidden = false;
+            }
+            StatisticsMsg::Close => {
+                self.hidden = true;
+                sender.output_sender().emit(StatisticsOut::Resume);
+            }
+            StatisticsMsg::DataDir(data_dir) => {
+                self.data_dir = data_dir;
+            }
+        }
+    }
+}
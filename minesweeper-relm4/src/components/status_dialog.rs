@@ -34,6 +34,8 @@ impl SimpleComponent for StatusDialogModel {
                 #[watch]
                 set_label: model.message.as_deref().unwrap_or_default(),
                 set_css_classes: &["statusMessage"],
+                set_wrap: true,
+                set_justify: gtk::Justification::Center,
             },
             gtk::Box {
                 set_orientation: gtk::Orientation::Vertical,
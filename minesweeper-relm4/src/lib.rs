@@ -1,14 +1,42 @@
-use minesweeper::model::Board;
-use std::num::NonZeroU8;
+use minesweeper::{
+    history::{DataDir, Settings},
+    model::{Board, BoardConfig, Difficulty, GenerationMode},
+};
+use std::{env::temp_dir, time::Duration};
 
 mod components;
 mod types;
 
 pub use components::app::AppModel;
 
-/// Create a new board 20 x 20.
-pub fn board() -> Board {
-    Board::new(NonZeroU8::new(20).unwrap(), NonZeroU8::new(20).unwrap())
+/// Create a new board for the difficulty level, generation mode, practice
+/// mode and question marks. A random seed is used when none is given.
+pub fn board(
+    difficulty: Difficulty,
+    generation: GenerationMode,
+    practice: bool,
+    question_marks: bool,
+    seed: Option<u64>,
+) -> Board {
+    let config = BoardConfig::preset(difficulty)
+        .unwrap_or_default()
+        .with_generation(generation)
+        .with_practice(practice);
+    let mut board = match seed {
+        Some(seed) => Board::with_seed(config, seed),
+        None => Board::new(config),
+    };
+    board.set_question_marks(question_marks);
+    board
+}
+
+/// Data folder chosen in the settings, a temporary folder when there is no
+/// default location.
+fn data_dir(settings: &Settings) -> DataDir {
+    settings.data_dir().unwrap_or_else(|err| {
+        eprintln!("Failed to find the data folder: {err}");
+        DataDir::new(temp_dir().join("minesweeper"))
+    })
 }
 
 /// Displayable elapsed time.
@@ -24,5 +52,19 @@ fn format_elapsed(seconds: u64) -> String {
     }
 }
 
+/// Displayable recorded time with milliseconds.
+fn format_time(time: Duration) -> String {
+    let (seconds, millis) = (time.as_secs(), time.subsec_millis());
+    match seconds {
+        0..=59 => format!("{seconds}.{millis:03} seconds"),
+        60..=3599 => format!(
+            "{} minute(s) {}.{millis:03} seconds",
+            seconds.div_euclid(60),
+            seconds.rem_euclid(60)
+        ),
+        3600.. => format_elapsed(seconds),
+    }
+}
+
 pub static FLAG: &str = "🚩";
 pub static BOMB: &str = "💣";
@@ -1,8 +1,15 @@
+use minesweeper::model::{Difficulty, GenerationMode};
 use minesweeper_relm4::{board, AppModel};
 use relm4::RelmApp;
 
 fn main() {
     let relm = RelmApp::new("dr.minesweeper");
     relm4::set_global_css(include_str!("style.css"));
-    relm.run::<AppModel>(board());
+    relm.run::<AppModel>(board(
+        Difficulty::Intermediate,
+        GenerationMode::Random,
+        false,
+        false,
+        None,
+    ));
 }
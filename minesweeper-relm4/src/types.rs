@@ -1,8 +1,15 @@
-use minesweeper::model::{Cell, Pos};
+use minesweeper::{
+    model::{Cell, Pos},
+    solver::{Probability, Verdict},
+};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Position {
     pub index: usize,
     pub pos: Pos,
     pub cell: Cell,
+    /// Verdict of a hint given for this position.
+    pub hint: Option<Verdict>,
+    /// Mine probability shown by the heatmap.
+    pub probability: Option<Probability>,
 }
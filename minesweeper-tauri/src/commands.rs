@@ -1,43 +1,74 @@
 //! API commands for the tauri client. These API's expose
 //! game functions and state management.
 use crate::{
-    game::{FlagResult, Game, OpenResult, Position},
-    history::WinHistoryView,
-    AppGame,
+    format_time,
+    game::{FlagResult, Game, HintResult, NewGameResult, OpenResult, Position},
+    history::{ProfilesView, StatisticsView, WinHistoryView},
+    AppDataDir, AppGame,
 };
 use minesweeper::{
-    history::{load_wins, save_win},
-    model::GameState,
+    history::{
+        load_game, load_settings, load_statistics, load_wins, profiles, record_game,
+        remove_saved_game, save_preferences, save_replay, save_win, update_settings, DataDir,
+        Format, Preferences, Settings,
+    },
+    model::{Difficulty, GameState, GenerationMode},
+    replay::Replay,
 };
-use std::time::Instant;
+use std::path::Path;
 use tauri::State;
 
-/// Open a cell.
+/// Open a cell. Fails when the first move can't generate a mine layout.
 #[tauri::command]
-pub fn open(position: Position, game: State<AppGame>) -> OpenResult {
+pub fn open(
+    position: Position,
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<OpenResult, String> {
+    let data_dir = data_dir.read().unwrap();
     let mut g = game.write().unwrap();
-    // The first move will start the clock.
-    if matches!(g.board.state(), GameState::New) {
-        g.start_time = Some(Instant::now());
+    // The first move will abandon the saved game.
+    let before = *g.board.state();
+    if before == GameState::New {
+        discard_saved_game(&data_dir);
     }
-    let opened_cells = g.open_cell(position);
+    let opened_cells = g.open_cell(position).map_err(|err| err.to_string())?;
+    Ok(opened_result(&g, &data_dir, before, opened_cells))
+}
+
+/// Open the neighbours of an opened cell when its flags match its adjacent mines.
+#[tauri::command]
+pub fn chord(position: Position, game: State<AppGame>, data_dir: State<AppDataDir>) -> OpenResult {
+    let mut g = game.write().unwrap();
+    let before = *g.board.state();
+    let opened_cells = g.chord(position);
+    opened_result(&g, &data_dir.read().unwrap(), before, opened_cells)
+}
+
+/// Build the response for opened cells, save the replay and statistics
+/// when the game ended with the move and save the win history when it was
+/// won.
+fn opened_result(
+    g: &Game,
+    data_dir: &DataDir,
+    before: GameState,
+    opened_cells: Vec<Position>,
+) -> OpenResult {
     let game_state = *g.board.state();
+    let ended = game_state.is_over() && !before.is_over();
 
-    // If the opened position results in a win or loss then
-    // we'll return all positions on the board otherwise
-    // just the opened cells.
-    let opened_cells = match game_state {
-        GameState::Loss | GameState::Win => g.positions(),
-        _ => opened_cells,
-    };
+    if ended {
+        if let Err(err) = save_replay(data_dir, &Replay::new(&g.board)) {
+            eprintln!("Failed to save replay {err}");
+        }
+        if let Err(err) = record_game(data_dir, &g.board) {
+            eprintln!("Failed to save statistics {err}");
+        }
+    }
 
     // Save the win history.
-    if matches!(game_state, GameState::Win) {
-        let duration = g
-            .start_time
-            .map(|st| st.elapsed().as_secs() - g.paused_time)
-            .unwrap_or_default();
-        if let Err(err) = save_win(duration) {
+    if ended && game_state == GameState::Win {
+        if let Err(err) = save_win(data_dir, &g.board) {
             eprintln!("Failed to save game state {err}");
         }
     }
@@ -45,7 +76,13 @@ pub fn open(position: Position, game: State<AppGame>) -> OpenResult {
     OpenResult {
         opened_cells,
         game_state,
+        opened: g.board.opened(),
         total_mines: g.board.mined(),
+        metrics: g
+            .board
+            .metrics()
+            .filter(|_| game_state == GameState::Win)
+            .map(|metrics| format!("Time {}, {metrics}", format_time(g.board.elapsed()))),
     }
 }
 
@@ -53,42 +90,278 @@ pub fn open(position: Position, game: State<AppGame>) -> OpenResult {
 #[tauri::command]
 pub fn flag(position: Position, game: State<AppGame>) -> FlagResult {
     let mut g = game.write().unwrap();
+    let position = g.flag_cell(position);
     FlagResult {
-        position: g.flag_cell(position),
+        position,
+        flagged: g.board.flagged(),
     }
 }
 
-/// Start a new game.
+/// Find a cell that can be deduced safe or mined. Every hint is counted
+/// against the score of a win.
 #[tauri::command]
-pub fn new_game(game: State<AppGame>) -> Vec<Position> {
-    let new_game = Game::default();
-    let positions = new_game
-        .board
-        .positions()
-        .enumerate()
-        .map(|(index, (&pos, &cell))| Position { index, pos, cell })
-        .collect();
-    *game.write().unwrap() = new_game;
-    positions
+pub fn hint(game: State<AppGame>) -> Option<HintResult> {
+    game.write().unwrap().hint()
 }
 
-/// Get the top 10 wins.
+/// Start a new game. Keeps the current difficulty level, generation mode and
+/// question marks when none are given and uses a random seed when no seed is
+/// given.
 #[tauri::command]
-pub fn get_win_history(game: State<AppGame>) -> Option<WinHistoryView> {
-    {
-        let mut g = game.write().unwrap();
-        g.paused = Some(Instant::now());
+pub fn new_game(
+    difficulty: Option<Difficulty>,
+    no_guess: Option<bool>,
+    question_marks: Option<bool>,
+    seed: Option<String>,
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<NewGameResult, String> {
+    let (config, current_marks) = {
+        let g = game.read().unwrap();
+        (*g.board.config(), g.board.question_marks())
+    };
+    let chosen = difficulty.is_some() || no_guess.is_some() || question_marks.is_some();
+    let question_marks = question_marks.unwrap_or(current_marks);
+    let difficulty = difficulty.unwrap_or(config.difficulty());
+    let generation = match no_guess {
+        Some(true) => GenerationMode::NoGuess,
+        Some(false) => GenerationMode::Random,
+        None => config.generation(),
+    };
+    let seed = seed
+        .map(|seed| seed.trim().parse::<u64>())
+        .transpose()
+        .map_err(|err| format!("Invalid seed: {err}"))?;
+    let new_game = Game::new(difficulty, generation, question_marks, seed);
+    let result = new_game.new_game_result();
+    let data_dir = data_dir.read().unwrap();
+    replace_game(&game, &data_dir, new_game);
+    if chosen {
+        // Start the next session of the profile on the chosen board.
+        let preferences = Preferences {
+            difficulty,
+            generation,
+            question_marks,
+        };
+        if let Err(err) = save_preferences(&data_dir, &preferences) {
+            eprintln!("Failed to save preferences {err}");
+        }
+    }
+    Ok(result)
+}
+
+/// Play another game, counting a started game as abandoned.
+fn replace_game(game: &State<AppGame>, data_dir: &DataDir, new_game: Game) {
+    let mut g = game.write().unwrap();
+    if *g.board.state() == GameState::Active {
+        if let Err(err) = record_game(data_dir, &g.board) {
+            eprintln!("Failed to save statistics {err}");
+        }
+    }
+    *g = new_game;
+}
+
+/// Count the game saved on the last exit as abandoned and remove it.
+fn discard_saved_game(data_dir: &DataDir) {
+    if let Ok(Some(saved)) = load_game(data_dir) {
+        if let Err(err) = record_game(data_dir, &saved.board) {
+            eprintln!("Failed to save statistics {err}");
+        }
+        if let Err(err) = remove_saved_game(data_dir) {
+            eprintln!("Failed to remove saved game {err}");
+        }
+    }
+}
+
+/// Is there a game saved on the last exit that can be continued.
+#[tauri::command]
+pub fn has_saved_game(data_dir: State<AppDataDir>) -> bool {
+    load_game(&data_dir.read().unwrap()).is_ok_and(|saved| saved.is_some())
+}
+
+/// Continue the game saved on the last exit.
+#[tauri::command]
+pub fn continue_game(
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<NewGameResult, String> {
+    let data_dir = data_dir.read().unwrap();
+    let saved = load_game(&data_dir)
+        .map_err(|err| err.to_string())?
+        .ok_or("No saved game")?;
+    if let Err(err) = remove_saved_game(&data_dir) {
+        eprintln!("Failed to remove saved game {err}");
     }
-    load_wins().map(Into::into)
+    let resumed = Game::resume(saved);
+    let result = resumed.new_game_result();
+    replace_game(&game, &data_dir, resumed);
+    Ok(result)
+}
+
+/// Get the top 10 wins of the leaderboard at the index, or of the leaderboard
+/// for the current board when no index is given.
+#[tauri::command]
+pub fn get_win_history(
+    leaderboard: Option<usize>,
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<WinHistoryView, String> {
+    let config = {
+        let mut g = game.write().unwrap();
+        g.board.pause_clock();
+        *g.board.config()
+    };
+    load_wins(&data_dir.read().unwrap())
+        .map(|history| WinHistoryView::new(&history, leaderboard, &config))
+        .map_err(|err| err.to_string())
+}
+
+/// Get the lifetime statistics of every board configuration played. The
+/// clock is paused until resumed.
+#[tauri::command]
+pub fn get_statistics(
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<Vec<StatisticsView>, String> {
+    game.write().unwrap().board.pause_clock();
+    let statistics = load_statistics(&data_dir.read().unwrap()).map_err(|err| err.to_string())?;
+    Ok(statistics.boards.iter().map(StatisticsView::from).collect())
 }
 
 /// Resume a game that is paused.
 #[tauri::command]
 pub fn resume(game: State<AppGame>) {
-    let mut g = game.write().unwrap();
-    if let Some(p) = g.paused.take() {
-        g.paused_time += p.elapsed().as_secs();
-    }
+    game.write().unwrap().board.resume_clock();
+}
+
+/// Export the win history to a JSON or CSV file, returns the number of wins
+/// exported.
+#[tauri::command]
+pub fn export_wins(path: String, data_dir: State<AppDataDir>) -> Result<usize, String> {
+    let path = Path::new(path.trim());
+    let format = Format::of(path).map_err(|err| err.to_string())?;
+    let history = load_wins(&data_dir.read().unwrap()).map_err(|err| err.to_string())?;
+    minesweeper::history::export_wins(&history, path, format).map_err(|err| err.to_string())?;
+    Ok(history.wins.len())
+}
+
+/// Import wins from a JSON or CSV file, returns the number of new wins.
+#[tauri::command]
+pub fn import_wins(path: String, data_dir: State<AppDataDir>) -> Result<usize, String> {
+    let path = Path::new(path.trim());
+    Format::of(path)
+        .and_then(|format| {
+            minesweeper::history::import_wins(&data_dir.read().unwrap(), path, format)
+        })
+        .map_err(|err| err.to_string())
+}
+
+/// Data folder chosen in the settings, `None` for the default location.
+#[tauri::command]
+pub fn get_data_dir() -> Result<Option<String>, String> {
+    let settings = load_settings().map_err(|err| err.to_string())?;
+    Ok(settings.data_dir.map(|path| path.display().to_string()))
+}
+
+/// Use the data folder, the default location when empty, and save it in the
+/// settings. The active profile is kept.
+#[tauri::command]
+pub fn set_data_dir(path: String, data_dir: State<AppDataDir>) -> Result<(), String> {
+    let path = path.trim();
+    let mut data_dir = data_dir.write().unwrap();
+    let settings = Settings {
+        data_dir: (!path.is_empty()).then(|| path.into()),
+        profile: data_dir.profile().map(ToOwned::to_owned),
+    };
+    *data_dir = crate::data_dir(&settings);
+    update_settings(|saved| saved.data_dir = settings.data_dir).map_err(|err| err.to_string())
+}
+
+/// Profiles in the data folder and the active profile.
+#[tauri::command]
+pub fn get_profiles(data_dir: State<AppDataDir>) -> Result<ProfilesView, String> {
+    let data_dir = data_dir.read().unwrap();
+    Ok(ProfilesView {
+        profiles: profiles(&data_dir).map_err(|err| err.to_string())?,
+        active: data_dir.profile().map(ToOwned::to_owned),
+    })
+}
+
+/// Switch to the profile, the anonymous profile when `None`, and save it in
+/// the settings. The game in progress is counted as abandoned in the profile
+/// it was played in and a game on the board last chosen in the profile is
+/// started.
+#[tauri::command]
+pub fn set_profile(
+    profile: Option<String>,
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<NewGameResult, String> {
+    let mut data_dir = data_dir.write().unwrap();
+    let profile_dir = data_dir
+        .clone()
+        .with_profile(profile.as_deref())
+        .map_err(|err| err.to_string())?;
+    let new_game = crate::preferred_game(&profile_dir);
+    let result = new_game.new_game_result();
+    replace_game(&game, &data_dir, new_game);
+    *data_dir = profile_dir;
+    update_settings(|settings| settings.profile = profile).map_err(|err| err.to_string())?;
+    Ok(result)
+}
+
+/// Create a profile and switch to it.
+#[tauri::command]
+pub fn create_profile(
+    name: String,
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<NewGameResult, String> {
+    let name = name.trim();
+    minesweeper::history::create_profile(&data_dir.read().unwrap(), name)
+        .map_err(|err| err.to_string())?;
+    set_profile(Some(name.to_owned()), game, data_dir)
+}
+
+/// Rename the active profile, the game in progress continues.
+#[tauri::command]
+pub fn rename_profile(name: String, data_dir: State<AppDataDir>) -> Result<(), String> {
+    let name = name.trim();
+    let mut data_dir = data_dir.write().unwrap();
+    let profile = data_dir
+        .profile()
+        .ok_or("The anonymous profile can't be renamed")?;
+    let renamed = minesweeper::history::rename_profile(&data_dir, profile, name)
+        .and_then(|()| data_dir.clone().with_profile(Some(name)))
+        .map_err(|err| err.to_string())?;
+    *data_dir = renamed;
+    update_settings(|settings| settings.profile = Some(name.to_owned()))
+        .map_err(|err| err.to_string())
+}
+
+/// Delete the active profile and switch to the anonymous profile. The game in
+/// progress is dropped with the profile.
+#[tauri::command]
+pub fn delete_profile(
+    game: State<AppGame>,
+    data_dir: State<AppDataDir>,
+) -> Result<NewGameResult, String> {
+    let mut data_dir = data_dir.write().unwrap();
+    let profile = data_dir
+        .profile()
+        .ok_or("The anonymous profile can't be deleted")?
+        .to_owned();
+    let anonymous = data_dir
+        .clone()
+        .with_profile(None)
+        .map_err(|err| err.to_string())?;
+    minesweeper::history::delete_profile(&anonymous, &profile).map_err(|err| err.to_string())?;
+    let new_game = crate::preferred_game(&anonymous);
+    let result = new_game.new_game_result();
+    *game.write().unwrap() = new_game;
+    *data_dir = anonymous;
+    update_settings(|settings| settings.profile = None).map_err(|err| err.to_string())?;
+    Ok(result)
 }
 
 #[tauri::command]
@@ -1,8 +1,14 @@
 //! Wrapper for the minesweeper game used with a Tauri user
 //! interface.
-use minesweeper::model::{Board, Cell, GameState, Pos};
+use minesweeper::{
+    history::SavedGame,
+    model::{
+        Board, BoardConfig, Cell, Difficulty, GameState, GenerationError, GenerationMode, Pos,
+    },
+    solver::Verdict,
+};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, num::NonZeroU8, time::Instant};
+use std::collections::HashMap;
 
 /// Cell position with an index.
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -18,9 +24,6 @@ pub struct Game {
     pub board: Board,
     pub pos_map: HashMap<Pos, usize>,
     pub positions: Vec<Position>,
-    pub start_time: Option<Instant>,
-    pub paused_time: u64,
-    pub paused: Option<Instant>,
 }
 
 /// Command response for opening a cell.
@@ -29,7 +32,28 @@ pub struct Game {
 pub struct OpenResult {
     pub opened_cells: Vec<Position>,
     pub game_state: GameState,
+    pub opened: usize,
     pub total_mines: usize,
+    /// Speed and efficiency of a won game.
+    pub metrics: Option<String>,
+}
+
+/// Command response for starting a new game.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewGameResult {
+    pub positions: Vec<Position>,
+    pub columns: u16,
+    pub difficulty: Difficulty,
+    pub no_guess: bool,
+    pub question_marks: bool,
+    /// Seed as a string since it does not fit in a javascript number.
+    pub seed: String,
+    pub game_state: GameState,
+    pub opened: usize,
+    pub flagged: usize,
+    pub mined: usize,
+    pub hints: usize,
 }
 
 /// Command response for flagging a cell.
@@ -37,50 +61,130 @@ pub struct OpenResult {
 #[serde(rename_all = "camelCase")]
 pub struct FlagResult {
     pub position: Option<Position>,
+    pub flagged: usize,
+}
+
+/// Command response for a hint.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HintResult {
+    pub index: usize,
+    pub verdict: Verdict,
+    /// Rule proving the hint.
+    pub explanation: String,
+    /// Hints given so far in the game.
+    pub hints: usize,
 }
 
 impl Game {
+    /// Response for a started or resumed game.
+    pub fn new_game_result(&self) -> NewGameResult {
+        let config = self.board.config();
+        NewGameResult {
+            positions: self.positions(),
+            columns: self.board.total_columns().get(),
+            difficulty: config.difficulty(),
+            no_guess: config.generation() == GenerationMode::NoGuess,
+            question_marks: self.board.question_marks(),
+            seed: self.board.seed().to_string(),
+            game_state: *self.board.state(),
+            opened: self.board.opened(),
+            flagged: self.board.flagged(),
+            mined: self.board.mined(),
+            hints: self.board.hints(),
+        }
+    }
+
     /// Get positions with indices.
     pub fn positions(&self) -> Vec<Position> {
         self.board
             .positions()
             .enumerate()
-            .map(|(index, (&pos, &cell))| Position { index, pos, cell })
+            .map(|(index, (pos, &cell))| Position { index, pos, cell })
             .collect()
     }
 
-    /// Open a cell on the board.
-    pub fn open_cell(&mut self, position: Position) -> Vec<Position> {
-        self.board
-            .open_cell(position.pos)
+    /// Open a cell on the board. Returns the cells changed, including the
+    /// mines exposed by a loss.
+    pub fn open_cell(&mut self, position: Position) -> Result<Vec<Position>, GenerationError> {
+        let (opened, changed) = self.play(|board| board.open_cell(position.pos));
+        opened.map(|_| changed)
+    }
+
+    /// Find a cell that can be deduced safe or mined.
+    pub fn hint(&mut self) -> Option<HintResult> {
+        let hint = self.board.hint()?;
+        Some(HintResult {
+            index: *self.pos_map.get(&hint.pos)?,
+            verdict: hint.verdict,
+            explanation: hint.to_string(),
+            hints: self.board.hints(),
+        })
+    }
+
+    /// Open the neighbours of a cell on the board.
+    pub fn chord(&mut self, position: Position) -> Vec<Position> {
+        self.play(|board| board.chord(position.pos)).1
+    }
+
+    /// Flag a cell on the board.
+    pub fn flag_cell(&mut self, position: Position) -> Option<Position> {
+        self.play(|board| board.flag_cell(position.pos))
+            .1
             .into_iter()
+            .next()
+    }
+
+    /// Play on the board. Returns the result of the play and the cells
+    /// changed by its events with their view indices.
+    fn play<T>(&mut self, play: impl FnOnce(&mut Board) -> T) -> (T, Vec<Position>) {
+        let events = self.board.subscribe();
+        let result = play(&mut self.board);
+        let changed = events
+            .try_iter()
+            .filter_map(|event| event.cell())
             .flat_map(|(pos, cell)| {
                 self.pos_map
                     .get(&pos)
                     .map(|&index| Position { pos, cell, index })
             })
-            .collect()
+            .collect();
+        (result, changed)
     }
+}
 
-    /// Flag a cell on the board.
-    pub fn flag_cell(&mut self, position: Position) -> Option<Position> {
-        self.board
-            .flag_cell(position.pos)
-            .map(|(pos, cell)| Position {
-                index: position.index,
-                pos,
-                cell,
-            })
+impl Game {
+    /// Create a game with a new board for the difficulty level, generation
+    /// mode and question marks. A random seed is used when none is given.
+    pub fn new(
+        difficulty: Difficulty,
+        generation: GenerationMode,
+        question_marks: bool,
+        seed: Option<u64>,
+    ) -> Self {
+        let config = BoardConfig::preset(difficulty)
+            .unwrap_or_default()
+            .with_generation(generation);
+        let mut board = match seed {
+            Some(seed) => Board::with_seed(config, seed),
+            None => Board::new(config),
+        };
+        board.set_question_marks(question_marks);
+        Self::with_board(board)
     }
-}
 
-impl Default for Game {
-    fn default() -> Self {
-        let board = board();
+    /// Resume a saved game with its clock running.
+    pub fn resume(saved: SavedGame) -> Self {
+        let mut game = Self::with_board(saved.board);
+        game.board.resume_clock();
+        game
+    }
+
+    fn with_board(board: Board) -> Self {
         let positions = board
             .positions()
             .enumerate()
-            .map(|(index, (&pos, &cell))| Position { index, pos, cell })
+            .map(|(index, (pos, &cell))| Position { index, pos, cell })
             .collect::<Vec<_>>();
 
         let pos_map = positions
@@ -92,14 +196,17 @@ impl Default for Game {
             board,
             positions,
             pos_map,
-            start_time: None,
-            paused_time: 0,
-            paused: None,
         }
     }
 }
 
-/// Create a new un-mined 20 x 20 board.
-fn board() -> Board {
-    Board::new(NonZeroU8::new(20).unwrap(), NonZeroU8::new(20).unwrap())
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(
+            Difficulty::Intermediate,
+            GenerationMode::Random,
+            false,
+            None,
+        )
+    }
 }
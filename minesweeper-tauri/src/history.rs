@@ -1,32 +1,112 @@
-//! API for handling top 10 wins.
-use crate::format_elapsed;
-use minesweeper::history::{Win, WinHistory};
+//! API for handling top 10 wins of every leaderboard, the lifetime
+//! statistics and the player profiles.
+use crate::format_time;
+use minesweeper::{
+    history::{GameStats, Win, WinHistory},
+    model::{BoardConfig, GenerationMode},
+};
 use serde::Serialize;
 
+/// Profiles in the data folder with the active profile.
+#[derive(Serialize, Debug)]
+pub struct ProfilesView {
+    pub profiles: Vec<String>,
+    /// `None` for the anonymous profile.
+    pub active: Option<String>,
+}
+
+/// Wins of one leaderboard with the names of all leaderboards.
 #[derive(Serialize, Debug)]
 pub struct WinHistoryView {
+    leaderboards: Vec<String>,
+    /// Index of the shown leaderboard.
+    selected: usize,
     wins: Vec<WinView>,
 }
 
 #[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct WinView {
     date: String,
     duration: String,
+    no_guess: bool,
+    hints: usize,
 }
 
-impl From<WinHistory> for WinHistoryView {
-    fn from(history: WinHistory) -> Self {
+impl WinHistoryView {
+    /// View of the leaderboard at the index, or of the leaderboard for the
+    /// board configuration when no index is given.
+    pub fn new(history: &WinHistory, selected: Option<usize>, config: &BoardConfig) -> Self {
+        let leaderboards = history.leaderboards();
+        let selected = selected
+            .or_else(|| {
+                let current = history.default_leaderboard(config)?;
+                leaderboards.iter().position(|&l| l == current)
+            })
+            .unwrap_or_default();
         Self {
-            wins: history.wins.into_iter().map(WinView::from).collect(),
+            wins: leaderboards
+                .get(selected)
+                .map(|&leaderboard| {
+                    history
+                        .wins_on(leaderboard)
+                        .into_iter()
+                        .map(WinView::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            leaderboards: leaderboards.iter().map(ToString::to_string).collect(),
+            selected,
         }
     }
 }
 
-impl From<Win> for WinView {
-    fn from(win: Win) -> Self {
+impl From<&Win> for WinView {
+    fn from(win: &Win) -> Self {
         Self {
             date: format!("{}", win.date.format("%b %e / %G %R")),
-            duration: format_elapsed(win.duration),
+            duration: format_time(win.score()),
+            no_guess: win.generation() == GenerationMode::NoGuess,
+            hints: win.hints,
+        }
+    }
+}
+
+/// Lifetime statistics of one board configuration.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsView {
+    board: String,
+    played: u32,
+    wins: u32,
+    /// Percentage of games won.
+    win_rate: String,
+    losses: u32,
+    abandoned: u32,
+    current_streak: u32,
+    best_streak: u32,
+    best_time: Option<String>,
+    average_time: Option<String>,
+    /// Percentage of 3BV cleared per click.
+    efficiency: Option<String>,
+}
+
+impl From<&(BoardConfig, GameStats)> for StatisticsView {
+    fn from((config, stats): &(BoardConfig, GameStats)) -> Self {
+        Self {
+            board: config.to_string(),
+            played: stats.played,
+            wins: stats.wins,
+            win_rate: format!("{:.0}%", stats.win_rate()),
+            losses: stats.losses,
+            abandoned: stats.abandoned,
+            current_streak: stats.current_streak,
+            best_streak: stats.best_streak,
+            best_time: stats.best_time.map(format_time),
+            average_time: stats.average_time().map(format_time),
+            efficiency: stats
+                .efficiency()
+                .map(|efficiency| format!("{efficiency:.0}%")),
         }
     }
 }
@@ -1,7 +1,12 @@
 //! Tauri API for the minesweeper game.
 use game::Game;
+use minesweeper::history::{load_preferences, DataDir, Settings};
 use serde::Serialize;
-use std::sync::{Arc, RwLock};
+use std::{
+    env::temp_dir,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 pub mod commands;
 pub mod game;
@@ -16,6 +21,36 @@ pub struct TimeEvent {
 
 pub type AppGame = Arc<RwLock<Game>>;
 
+/// Folder for the history files.
+pub type AppDataDir = RwLock<DataDir>;
+
+/// Data folder chosen in the settings, a temporary folder when there is no
+/// default location.
+pub fn data_dir(settings: &Settings) -> DataDir {
+    settings.data_dir().unwrap_or_else(|err| {
+        eprintln!("Failed to find the data folder: {err}");
+        DataDir::new(temp_dir().join("minesweeper"))
+    })
+}
+
+/// Game on the board last chosen in the profile, intermediate when none was
+/// chosen.
+pub fn preferred_game(data_dir: &DataDir) -> Game {
+    match load_preferences(data_dir) {
+        Ok(Some(preferences)) => Game::new(
+            preferences.difficulty,
+            preferences.generation,
+            preferences.question_marks,
+            None,
+        ),
+        Ok(None) => Game::default(),
+        Err(err) => {
+            eprintln!("Failed to load preferences {err}");
+            Game::default()
+        }
+    }
+}
+
 /// Displayable elapsed time.
 pub fn format_elapsed(seconds: u64) -> String {
     match seconds {
@@ -28,3 +63,17 @@ pub fn format_elapsed(seconds: u64) -> String {
         3600.. => format!("{} hours", seconds.div_euclid(3600)),
     }
 }
+
+/// Displayable recorded time with milliseconds.
+pub fn format_time(time: Duration) -> String {
+    let (seconds, millis) = (time.as_secs(), time.subsec_millis());
+    match seconds {
+        0..=59 => format!("{seconds}.{millis:03} seconds"),
+        60..=3599 => format!(
+            "{} minute(s) {}.{millis:03} seconds",
+            seconds.div_euclid(60),
+            seconds.rem_euclid(60)
+        ),
+        3600.. => format_elapsed(seconds),
+    }
+}
@@ -4,57 +4,94 @@
 )]
 
 use app::{
-    commands::{flag, get_win_history, new_game, open, platform, resume},
-    game::Game,
-    AppGame, TimeEvent, __cmd__flag, __cmd__get_win_history, __cmd__new_game, __cmd__open,
-    __cmd__platform, __cmd__resume, format_elapsed,
+    commands::{
+        chord, continue_game, create_profile, delete_profile, export_wins, flag, get_data_dir,
+        get_profiles, get_statistics, get_win_history, has_saved_game, hint, import_wins,
+        new_game, open, platform, rename_profile, resume, set_data_dir, set_profile,
+    },
+    data_dir, preferred_game, AppDataDir, AppGame, TimeEvent, __cmd__chord,
+    __cmd__continue_game, __cmd__create_profile, __cmd__delete_profile, __cmd__export_wins,
+    __cmd__flag, __cmd__get_data_dir, __cmd__get_profiles, __cmd__get_statistics,
+    __cmd__get_win_history, __cmd__has_saved_game, __cmd__hint, __cmd__import_wins,
+    __cmd__new_game, __cmd__open, __cmd__platform, __cmd__rename_profile, __cmd__resume,
+    __cmd__set_data_dir, __cmd__set_profile, format_elapsed,
+};
+use minesweeper::{
+    history::{load_settings, save_game},
+    model::GameState,
 };
-use minesweeper::model::GameState;
 use std::{
     sync::{Arc, RwLock},
     time::Duration,
 };
-use tauri::{Emitter, Manager};
+use tauri::{Emitter, Manager, WindowEvent};
 
 fn main() {
-    let game: AppGame = Arc::new(RwLock::new(Game::default()));
+    let settings = load_settings().unwrap_or_else(|err| {
+        eprintln!("Failed to load settings {err}");
+        Default::default()
+    });
+    let data_dir = data_dir(&settings);
+    let game: AppGame = Arc::new(RwLock::new(preferred_game(&data_dir)));
+    let data_dir: AppDataDir = RwLock::new(data_dir);
     tauri::Builder::default()
         .manage(game.clone())
+        .manage(data_dir)
         .setup(move |app| {
             let main_window = app.get_webview_window("main").unwrap();
             std::thread::spawn(move || loop {
-                if let Some((state, duration, paused)) = {
+                if let Some(duration) = {
                     game.read()
                         .map(|g| {
-                            g.start_time
-                                .and_then(|st| st.elapsed().as_secs().checked_sub(g.paused_time))
-                                .map(|elapsed| {
-                                    (
-                                        *g.board.state(),
-                                        format_elapsed(elapsed),
-                                        g.paused.is_some(),
-                                    )
-                                })
+                            g.board
+                                .clock()
+                                .is_running()
+                                .then(|| format_elapsed(g.board.elapsed().as_secs()))
                         })
                         .unwrap()
                 } {
-                    if !paused && matches!(state, GameState::Active) {
-                        main_window
-                            .emit("time-event", TimeEvent { duration })
-                            .unwrap_or_else(|e| eprintln!("Failed to emit time event {e}"));
-                    }
+                    main_window
+                        .emit("time-event", TimeEvent { duration })
+                        .unwrap_or_else(|e| eprintln!("Failed to emit time event {e}"));
                 }
 
                 std::thread::sleep(Duration::from_secs(1));
             });
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { .. } = event {
+                let game = window.state::<AppGame>();
+                let data_dir = window.state::<AppDataDir>();
+                let g = game.read().unwrap();
+                // An untouched board keeps the game saved on the last exit.
+                if *g.board.state() != GameState::New {
+                    if let Err(err) = save_game(&data_dir.read().unwrap(), &g.board) {
+                        eprintln!("Failed to save game {err}");
+                    }
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             open,
+            chord,
+            hint,
             new_game,
+            has_saved_game,
+            continue_game,
             flag,
             get_win_history,
+            get_statistics,
+            export_wins,
+            import_wins,
             resume,
+            get_data_dir,
+            set_data_dir,
+            get_profiles,
+            set_profile,
+            create_profile,
+            rename_profile,
+            delete_profile,
             platform
         ])
         .run(tauri::generate_context!())
@@ -7,10 +7,21 @@ import {
 } from "react";
 import "./App.css";
 import { invoke } from "@tauri-apps/api/core";
-import { FlagResult, GameState, OpenResult, Position } from "./common/types";
+import {
+	DIFFICULTIES,
+	Difficulty,
+	FlagResult,
+	GameState,
+	HintResult,
+	NewGameResult,
+	OpenResult,
+	Position,
+	Profiles,
+} from "./common/types";
 import CellComp from "./components/Cell/Cell";
 import DurationCounter from "./components/DurationCounter/DurationCounter";
 import Wins from "./components/Wins/Wins";
+import Statistics from "./components/Statistics/Statistics";
 import StatusDialog from "./components/StatusDialog/StatusDialog";
 import { LogicalSize } from "@tauri-apps/api/dpi";
 import {
@@ -21,20 +32,33 @@ const appWindow = getCurrentWebviewWindow();
 
 type GameAppState = {
 	board: Position[];
+	columns: number;
+	difficulty: Difficulty;
+	noGuess: boolean;
+	questionMarks: boolean;
+	seed: string;
+	error?: string;
+	hint?: HintResult;
+	metrics?: string;
+	hints: number;
 	state: GameState;
 	opened: number;
 	mined: number;
 	flagged: number;
 	active: boolean;
 	showWins: boolean;
+	showStatistics: boolean;
 	statusDialog: boolean;
 };
 
 type GameAction =
 	| { type: "open"; result: OpenResult }
-	| { type: "restart"; board: Position[] }
-	| { type: "flag"; position: Position }
+	| { type: "restart"; result: NewGameResult }
+	| { type: "error"; message: string }
+	| { type: "hint"; result: HintResult | null }
+	| { type: "flag"; position: Position; flagged: number }
 	| { type: "showWins" }
+	| { type: "showStatistics" }
 	| { type: "statusDialog" };
 
 function gameReducer(state: GameAppState, action: GameAction): GameAppState {
@@ -46,11 +70,13 @@ function gameReducer(state: GameAppState, action: GameAction): GameAppState {
 			}
 			return {
 				...state,
+				hint: undefined,
 				board: updatedBoard,
 				state: action.result.gameState,
 				active: action.result.gameState == "Active",
-				opened: state.opened + action.result.openedCells.length,
+				opened: action.result.opened,
 				mined: action.result.totalMines,
+				metrics: action.result.metrics,
 				statusDialog:
 					action.result.gameState === "Loss" ||
 					action.result.gameState === "Win",
@@ -59,25 +85,53 @@ function gameReducer(state: GameAppState, action: GameAction): GameAppState {
 		case "restart":
 			return {
 				...INITIAL_STATE,
-				board: action.board,
+				board: action.result.positions,
+				columns: action.result.columns,
+				difficulty: action.result.difficulty,
+				noGuess: action.result.noGuess,
+				questionMarks: action.result.questionMarks,
+				seed: action.result.seed,
+				state: action.result.gameState,
+				active:
+					action.result.gameState === "New" ||
+					action.result.gameState === "Active",
+				opened: action.result.opened,
+				flagged: action.result.flagged,
+				mined: action.result.mined,
+				hints: action.result.hints,
+			};
+		case "hint":
+			return {
+				...state,
+				hint: action.result ?? undefined,
+				hints: action.result?.hints ?? state.hints,
+			};
+		case "error":
+			return {
+				...state,
+				error: action.message,
+				active: false,
+				statusDialog: true,
 			};
-		case "flag": {
-			const flagged =
-				action.position.cell.state.type === "Closed" &&
-				action.position.cell.state.content.flagged;
+		case "flag":
 			return {
 				...state,
+				hint: undefined,
 				board: state.board.map((pos) =>
 					pos.index === action.position.index ? action.position : pos,
 				),
-				flagged: flagged ? state.flagged + 1 : state.flagged - 1,
+				flagged: action.flagged,
 			};
-		}
 		case "showWins":
 			return {
 				...state,
 				showWins: !state.showWins,
 			};
+		case "showStatistics":
+			return {
+				...state,
+				showStatistics: !state.showStatistics,
+			};
 		case "statusDialog":
 			return {
 				...state,
@@ -91,12 +145,19 @@ function gameReducer(state: GameAppState, action: GameAction): GameAppState {
 
 const INITIAL_STATE: GameAppState = {
 	board: [],
+	columns: 16,
+	difficulty: "Intermediate",
+	noGuess: false,
+	questionMarks: false,
+	seed: "",
+	hints: 0,
 	state: "New",
 	opened: 0,
 	mined: 0,
 	flagged: 0,
 	active: true,
 	showWins: false,
+	showStatistics: false,
 	statusDialog: false,
 };
 
@@ -105,6 +166,14 @@ function App() {
 	const [resized, setResized] = useState(false);
 	const [platform, setPlatform] = useState<string>();
 	const [dimensions, setDimensions] = useState<LogicalSize>();
+	const [seedInput, setSeedInput] = useState("");
+	const [dataDirInput, setDataDirInput] = useState("");
+	const [canContinue, setCanContinue] = useState(false);
+	const [profiles, setProfiles] = useState<Profiles>({
+		profiles: [],
+		active: null,
+	});
+	const [profileInput, setProfileInput] = useState("");
 	const ref = useRef<HTMLDivElement>(null);
 
 	useEffect(() => {
@@ -132,31 +201,146 @@ function App() {
 
 	useEffect(() => {
 		newGame();
+		invoke<boolean>("has_saved_game").then(setCanContinue);
+		loadProfiles();
+		invoke<string | null>("get_data_dir")
+			.then((path) => setDataDirInput(path ?? ""))
+			.catch((err) => console.error("Failed to load settings", err));
 	}, []);
 
 	async function openCell(position: Position) {
 		if (position.cell.state.type === "Closed") {
-			const result = await invoke<OpenResult>("open", { position });
+			try {
+				const result = await invoke<OpenResult>("open", { position });
+				// Playing another game abandons the saved game.
+				setCanContinue(false);
+				dispatch({ type: "open", result });
+			} catch (err) {
+				dispatch({ type: "error", message: String(err) });
+			}
+		}
+	}
+
+	async function chordCell(position: Position) {
+		if (position.cell.state.type === "Open") {
+			const result = await invoke<OpenResult>("chord", { position });
 			dispatch({ type: "open", result });
 		}
 	}
 
+	function continueGame() {
+		setCanContinue(false);
+		invoke<NewGameResult>("continue_game")
+			.then((result) => {
+				if (result.columns !== gameState.columns) {
+					setResized(false);
+				}
+				dispatch({ type: "restart", result });
+			})
+			.catch((err) => console.error("Failed to continue game", err));
+	}
+
+	async function hintCell() {
+		const result = await invoke<HintResult | null>("hint");
+		dispatch({ type: "hint", result });
+	}
+
 	async function flagCell(position: Position): Promise<Position | undefined> {
 		const result = await invoke<FlagResult>("flag", { position });
 		if (result.position) {
 			if (result.position.cell.state.type == "Closed") {
-				dispatch({ type: "flag", position: result.position });
+				dispatch({
+					type: "flag",
+					position: result.position,
+					flagged: result.flagged,
+				});
 			}
 		}
 		return result.position;
 	}
 
-	function newGame() {
-		invoke<Position[]>("new_game")
-			.then((board) => dispatch({ type: "restart", board }))
+	function newGame(
+		difficulty?: Difficulty,
+		seed?: string,
+		noGuess?: boolean,
+		questionMarks?: boolean,
+	) {
+		invoke<NewGameResult>("new_game", {
+			difficulty,
+			noGuess,
+			questionMarks,
+			seed,
+		})
+			.then((result) => {
+				// Board dimensions change with the difficulty level.
+				if (result.columns !== gameState.columns) {
+					setResized(false);
+				}
+				dispatch({ type: "restart", result });
+			})
 			.catch((err) => console.error("Failed to start game", err));
 	}
 
+	function setDataDir() {
+		invoke("set_data_dir", { path: dataDirInput })
+			// The saved game and profiles are read from the new folder.
+			.then(() => {
+				loadProfiles();
+				return invoke<boolean>("has_saved_game");
+			})
+			.then(setCanContinue)
+			.catch((err) => console.error("Failed to set data folder", err));
+	}
+
+	function loadProfiles() {
+		invoke<Profiles>("get_profiles")
+			.then(setProfiles)
+			.catch((err) => console.error("Failed to load profiles", err));
+	}
+
+	// Show the game started on the board last chosen in another profile.
+	function startProfileGame(result: NewGameResult) {
+		if (result.columns !== gameState.columns) {
+			setResized(false);
+		}
+		dispatch({ type: "restart", result });
+		loadProfiles();
+		invoke<boolean>("has_saved_game").then(setCanContinue);
+	}
+
+	function setProfile(profile: string | null) {
+		invoke<NewGameResult>("set_profile", { profile })
+			.then(startProfileGame)
+			.catch((err) => console.error("Failed to switch profile", err));
+	}
+
+	function createProfile() {
+		invoke<NewGameResult>("create_profile", { name: profileInput })
+			.then((result) => {
+				setProfileInput("");
+				startProfileGame(result);
+			})
+			.catch((err) => console.error("Failed to create profile", err));
+	}
+
+	function renameProfile() {
+		invoke("rename_profile", { name: profileInput })
+			.then(() => {
+				setProfileInput("");
+				loadProfiles();
+			})
+			.catch((err) => console.error("Failed to rename profile", err));
+	}
+
+	function deleteProfile() {
+		invoke<NewGameResult>("delete_profile")
+			.then(startProfileGame)
+			.catch((err) => console.error("Failed to delete profile", err));
+	}
+
+	const dialogOpen =
+		gameState.statusDialog || gameState.showWins || gameState.showStatistics;
+
 	return (
 		<div
 			className="App"
@@ -168,28 +352,51 @@ function App() {
 				<span>Opened: {gameState.opened}</span>
 				<span>Flagged: {gameState.flagged}</span>
 				<span>Mined: {gameState.mined}</span>
+				<span>Hints: {gameState.hints}</span>
+				<span>Seed: {gameState.seed}</span>
+			</div>
+			<div className="hint">
+				{gameState.hint ? `Hint: ${gameState.hint.explanation}` : ""}
 			</div>
 
 			<div className="boardContainer">
 				{gameState.showWins && (
 					<Wins close={() => dispatch({ type: "showWins" })} />
 				)}
+				{gameState.showStatistics && (
+					<Statistics close={() => dispatch({ type: "showStatistics" })} />
+				)}
 				{gameState.statusDialog && (
 					<StatusDialog
 						close={() => dispatch({ type: "statusDialog" })}
-						message={gameState.state === "Win" ? "You Won!" : "You Lose!"}
+						message={
+							gameState.error ??
+							(gameState.state === "Win" ? "You Won!" : "You Lose!")
+						}
 						emoji={gameState.state === "Win" ? "😀" : "😞"}
+						details={gameState.error ? undefined : gameState.metrics}
 					/>
 				)}
 				{gameState.board.length > 0 && (
-					<div className={`board ${!gameState.active ? "gameOver" : ""}`}>
+					<div
+						className={`board ${!gameState.active ? "gameOver" : ""}`}
+						style={{
+							gridTemplateColumns: `repeat(${gameState.columns}, 1fr)`,
+						}}
+					>
 						{gameState.board.map((cell) => (
 							<CellComp
 								key={cell.index}
 								position={cell}
 								open={openCell}
+								chord={chordCell}
 								gameActive={gameState.active}
 								flag={flagCell}
+								hint={
+									gameState.hint?.index === cell.index
+										? gameState.hint.verdict
+										: undefined
+								}
 							/>
 						))}
 					</div>
@@ -200,17 +407,149 @@ function App() {
 				<button
 					className="buttons topScores"
 					onClick={() => dispatch({ type: "showWins" })}
-					disabled={gameState.statusDialog || gameState.showWins}
+					disabled={dialogOpen}
 				>
 					Top Scores
 				</button>
+				<button
+					className="buttons"
+					onClick={() => dispatch({ type: "showStatistics" })}
+					disabled={dialogOpen}
+				>
+					Statistics
+				</button>
 				<button
 					className="buttons newGame"
-					onClick={() => newGame()}
-					disabled={gameState.statusDialog || gameState.showWins}
+					onClick={() => newGame(gameState.difficulty)}
+					disabled={dialogOpen}
 				>
 					New Game
 				</button>
+				{canContinue && (
+					<button
+						className="buttons"
+						onClick={continueGame}
+						disabled={dialogOpen}
+					>
+						Continue
+					</button>
+				)}
+				<button
+					className="buttons"
+					onClick={hintCell}
+					disabled={gameState.state !== "Active" || dialogOpen}
+				>
+					Hint
+				</button>
+				<input
+					className="buttons"
+					placeholder="Seed"
+					value={seedInput}
+					onChange={(event) => setSeedInput(event.target.value)}
+					onKeyDown={(event) => {
+						if (event.key === "Enter" && seedInput.trim()) {
+							newGame(gameState.difficulty, seedInput);
+						}
+					}}
+					disabled={dialogOpen}
+				/>
+				<input
+					className="buttons"
+					placeholder="Data folder"
+					value={dataDirInput}
+					onChange={(event) => setDataDirInput(event.target.value)}
+					onKeyDown={(event) => {
+						if (event.key === "Enter") {
+							setDataDir();
+						}
+					}}
+					disabled={dialogOpen}
+				/>
+				<select
+					className="buttons"
+					value={profiles.active ?? ""}
+					onChange={(event) => setProfile(event.target.value || null)}
+					disabled={dialogOpen}
+				>
+					<option value="">Anonymous</option>
+					{profiles.profiles.map((name) => (
+						<option key={name} value={name}>
+							{name}
+						</option>
+					))}
+				</select>
+				<input
+					className="buttons"
+					placeholder="Profile name"
+					value={profileInput}
+					onChange={(event) => setProfileInput(event.target.value)}
+					onKeyDown={(event) => {
+						if (event.key === "Enter" && profileInput.trim()) {
+							createProfile();
+						}
+					}}
+					disabled={dialogOpen}
+				/>
+				<button
+					className="buttons"
+					onClick={createProfile}
+					disabled={!profileInput.trim() || dialogOpen}
+				>
+					Create
+				</button>
+				<button
+					className="buttons"
+					onClick={renameProfile}
+					disabled={!profiles.active || !profileInput.trim() || dialogOpen}
+				>
+					Rename
+				</button>
+				<button
+					className="buttons"
+					onClick={deleteProfile}
+					disabled={!profiles.active || dialogOpen}
+				>
+					Delete
+				</button>
+				<label className="buttons">
+					<input
+						type="checkbox"
+						checked={gameState.noGuess}
+						onChange={(event) =>
+							newGame(gameState.difficulty, undefined, event.target.checked)
+						}
+						disabled={dialogOpen}
+					/>
+					No guess
+				</label>
+				<label className="buttons">
+					<input
+						type="checkbox"
+						checked={gameState.questionMarks}
+						onChange={(event) =>
+							newGame(
+								gameState.difficulty,
+								undefined,
+								undefined,
+								event.target.checked,
+							)
+						}
+						disabled={dialogOpen}
+					/>
+					Question marks
+				</label>
+				<select
+					className="buttons"
+					value={gameState.difficulty}
+					onChange={(event) => newGame(event.target.value as Difficulty)}
+					disabled={dialogOpen}
+				>
+					{DIFFICULTIES.map((difficulty) => (
+						<option key={difficulty} value={difficulty}>
+							{difficulty}
+						</option>
+					))}
+				</select>
 			</div>
 		</div>
 	);
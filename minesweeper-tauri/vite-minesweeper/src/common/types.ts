@@ -13,7 +13,7 @@ export type Cell = {
 }
 
 export type State =
-  { type: "Closed", content: { flagged: boolean, mined: boolean } } |
+  { type: "Closed", content: { flagged: boolean, mined: boolean, questioned: boolean } } |
   { type: "Open" } |
   { type: "ExposedMine" }
 
@@ -25,11 +25,14 @@ export type ModifiedPosition = {
 export type OpenResult = {
   openedCells: Position[],
   gameState: GameState,
+  opened: number,
   totalMines: number,
+  metrics?: string,
 }
 
 export type FlagResult = {
   position?: Position,
+  flagged: number,
 }
 
 export type TimeEvent = {
@@ -38,12 +41,62 @@ export type TimeEvent = {
 
 export type GameState = "New" | "Active" | "Win" | "Loss";
 
+export type Difficulty = "Beginner" | "Intermediate" | "Expert";
+
+export const DIFFICULTIES: Difficulty[] = ["Beginner", "Intermediate", "Expert"];
+
+export type NewGameResult = {
+  positions: Position[],
+  columns: number,
+  difficulty: Difficulty,
+  noGuess: boolean,
+  questionMarks: boolean,
+  seed: string,
+  gameState: GameState,
+  opened: number,
+  flagged: number,
+  mined: number,
+  hints: number,
+}
+
+export type Verdict = "Safe" | "Mine";
+
+export type HintResult = {
+  index: number,
+  verdict: Verdict,
+  explanation: string,
+  hints: number,
+}
+
 export type WinHistory = {
+  leaderboards: string[],
+  selected: number,
   wins: Win[],
 }
 
+export type Profiles = {
+  profiles: string[],
+  active: string | null,
+}
+
 export type Win = {
   date: string,
   duration: string,
+  noGuess: boolean,
+  hints: number,
 }
 
+
+export type Statistics = {
+  board: string,
+  played: number,
+  wins: number,
+  winRate: string,
+  losses: number,
+  abandoned: number,
+  currentStreak: number,
+  bestStreak: number,
+  bestTime?: string,
+  averageTime?: string,
+  efficiency?: string,
+}
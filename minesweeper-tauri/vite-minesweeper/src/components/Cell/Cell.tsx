@@ -1,12 +1,14 @@
-import { Position } from "../../common/types"
+import { Position, Verdict } from "../../common/types"
 import classes from "./Cell.module.css";
 import { MouseEvent, useEffect, useState } from "react";
 
 type CellProps = {
     position: Position,
     open: (position: Position) => Promise<void>,
+    chord: (position: Position) => Promise<void>,
     flag: (position: Position) => Promise<Position | undefined>,
     gameActive: boolean,
+    hint?: Verdict,
 }
 
 function mineCountStyle(count: number): string | undefined {
@@ -21,7 +23,7 @@ function mineCountStyle(count: number): string | undefined {
 /**
  * A Cell component.
  */
-export default function CellComp({ position, open, gameActive, flag }: CellProps) {
+export default function CellComp({ position, open, chord, gameActive, flag, hint }: CellProps) {
     const [localPos, setLocalPos] = useState(position);
 
     useEffect(() => {
@@ -32,7 +34,7 @@ export default function CellComp({ position, open, gameActive, flag }: CellProps
         switch (localPos.cell.state.type) {
             case "Closed": return localPos.cell.state.content.flagged
                 ? "🚩"
-                : ""
+                : localPos.cell.state.content.questioned ? "?" : ""
             case "ExposedMine": return "💣"
             case "Open": return localPos.cell.adjacentMines > 0
                 ? localPos.cell.adjacentMines
@@ -43,7 +45,10 @@ export default function CellComp({ position, open, gameActive, flag }: CellProps
     function getClassName(): string | undefined {
         switch (localPos.cell.state.type) {
             case "Closed": {
-                return localPos.cell.state.content.flagged ? classes["flagged"] : classes["closed"];
+                if (localPos.cell.state.content.flagged) {
+                    return classes["flagged"];
+                }
+                return hint ? `${classes["closed"]} ${classes[`hint${hint}`]}` : classes["closed"];
             };
             case "ExposedMine": return classes["exposed"];
             case "Open": return `${classes["open"]} ${mineCountStyle(localPos.cell.adjacentMines)}`;
@@ -51,7 +56,15 @@ export default function CellComp({ position, open, gameActive, flag }: CellProps
     }
 
     async function handleClick(event: MouseEvent) {
-        if (event.altKey || event.button == 2) {
+        if (event.button == 1 || (event.buttons & 3) == 3) {
+            if (localPos.cell.state.type == "Open") {
+                try {
+                    await chord(localPos);
+                } catch (err) {
+                    console.error("failed to chord cell", err);
+                }
+            }
+        } else if (event.altKey || event.button == 2) {
             try {
                 const pos = await flag(position);
                 if (pos) {
@@ -79,6 +92,8 @@ export default function CellComp({ position, open, gameActive, flag }: CellProps
             className={`${classes["container"]} ${getClassName()}`}
             onClick={handleClick}
             onContextMenu={handleClick}
+            onAuxClick={(event) => event.button == 1 && handleClick(event)}
+            onMouseDown={(event) => (event.buttons & 3) == 3 && handleClick(event)}
             disabled={!gameActive}>
             {renderCell()}
         </button>
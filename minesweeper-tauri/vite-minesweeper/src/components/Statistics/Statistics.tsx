@@ -0,0 +1,109 @@
+import { useEffect, useLayoutEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Statistics as BoardStatistics } from "../../common/types";
+import modalClasses from "../Wins/Wins.module.css";
+import classes from "./Statistics.module.css";
+
+type StatisticsProps = {
+	close: () => void;
+};
+
+function Statistics({ close }: StatisticsProps) {
+	const [statistics, setStatistics] = useState<BoardStatistics[]>();
+	const [closing, setClosing] = useState(false);
+	const containerDiv = useRef<HTMLDivElement>(null);
+	const [containerClasses, setContainerClasses] = useState(
+		modalClasses["modal"],
+	);
+	const [height, setHeight] = useState("0px");
+
+	useEffect(() => {
+		invoke<BoardStatistics[]>("get_statistics")
+			.then(setStatistics)
+			.catch((err) => console.error("Failed to get statistics", err));
+		return () => {
+			invoke("resume").catch((err) =>
+				console.error("Failed to resume clock", err),
+			);
+		};
+	}, []);
+
+	useLayoutEffect(() => {
+		if (containerDiv.current) {
+			const { height } = containerDiv.current.getBoundingClientRect();
+			setHeight(`-${height}px`);
+			setContainerClasses(
+				`${modalClasses["modal"]} ${modalClasses["modalReady"]}`,
+			);
+		}
+	}, [containerDiv]);
+
+	useEffect(() => {
+		if (closing) {
+			setContainerClasses(
+				`${modalClasses["modal"]} ${modalClasses["closing"]}`,
+			);
+		}
+	}, [closing]);
+
+	return (
+		<div
+			className={containerClasses}
+			onAnimationEnd={() => {
+				if (closing) {
+					close();
+				}
+			}}
+			ref={containerDiv}
+			style={{ "--wins-height": height } as React.CSSProperties}
+		>
+			<div className={modalClasses["container"]}>
+				<div
+					className={modalClasses["closeButton"]}
+					onClick={() => setClosing(true)}
+				>
+					X
+				</div>
+				<div className={modalClasses["title"]}>Statistics</div>
+				{statistics && statistics.length > 0 ? (
+					<table className={classes["table"]}>
+						<thead>
+							<tr>
+								<th>Board</th>
+								<th>Played</th>
+								<th>Won</th>
+								<th>Lost</th>
+								<th>Abandoned</th>
+								<th>Streak</th>
+								<th>Best streak</th>
+								<th>Best time</th>
+								<th>Average time</th>
+								<th>Efficiency</th>
+							</tr>
+						</thead>
+						<tbody>
+							{statistics.map((stats) => (
+								<tr key={stats.board}>
+									<td>{stats.board}</td>
+									<td>{stats.played}</td>
+									<td>{stats.winRate}</td>
+									<td>{stats.losses}</td>
+									<td>{stats.abandoned}</td>
+									<td>{stats.currentStreak}</td>
+									<td>{stats.bestStreak}</td>
+									<td>{stats.bestTime ?? "-"}</td>
+									<td>{stats.averageTime ?? "-"}</td>
+									<td>{stats.efficiency ?? "-"}</td>
+								</tr>
+							))}
+						</tbody>
+					</table>
+				) : (
+					<span className={modalClasses["noWins"]}>No games played.</span>
+				)}
+			</div>
+		</div>
+	);
+}
+
+export default Statistics;
@@ -5,9 +5,10 @@ type StatusDialogProps = {
     close: () => void;
     message: string;
     emoji: string;
+    details?: string;
 };
 
-function StatusDialog({ close, message, emoji }: StatusDialogProps) {
+function StatusDialog({ close, message, emoji, details }: StatusDialogProps) {
     const [closing, setClosing] = useState(false);
 
     const closeDialog = () => {
@@ -24,6 +25,7 @@ function StatusDialog({ close, message, emoji }: StatusDialogProps) {
                         <div className={classes["emoji"]}>{emoji}</div>
                         <div className={classes["text"]}>{message}</div>
                     </div>
+                    {details && <div className={classes["details"]}>{details}</div>}
                 </div>
             </div>
         </div>
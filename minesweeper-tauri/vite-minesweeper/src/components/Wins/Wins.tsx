@@ -13,11 +13,25 @@ function Wins({ close }: WinsProps) {
 	const containerDiv = useRef<HTMLDivElement>(null);
 	const [containerClasses, setContainerClasses] = useState(classes["modal"]);
 	const [height, setHeight] = useState("0px");
+	const [file, setFile] = useState("");
 
-	useEffect(() => {
-		invoke<WinHistory>("get_win_history")
+	const loadWins = (leaderboard?: number) =>
+		invoke<WinHistory>("get_win_history", { leaderboard })
 			.then(setWins)
 			.catch((err) => console.error("failed to get win history", err));
+
+	const exportWins = () =>
+		invoke<number>("export_wins", { path: file })
+			.then((count) => console.log(`Exported ${count} wins`))
+			.catch((err) => console.error("Failed to export wins", err));
+
+	const importWins = () =>
+		invoke<number>("import_wins", { path: file })
+			.then(() => loadWins(wins?.selected))
+			.catch((err) => console.error("Failed to import wins", err));
+
+	useEffect(() => {
+		loadWins();
 		return () => {
 			invoke("resume").catch((err) =>
 				console.error("Failed to resume clock", err),
@@ -59,11 +73,38 @@ function Wins({ close }: WinsProps) {
 					X
 				</div>
 				<div className={classes["title"]}>Top 10 Wins</div>
+				{wins && (
+					<select
+						className={classes["leaderboard"]}
+						value={wins.selected}
+						onChange={(e) => loadWins(Number(e.target.value))}
+					>
+						{wins.leaderboards.map((name, index) => (
+							<option key={name} value={index}>
+								{name}
+							</option>
+						))}
+					</select>
+				)}
 				{wins?.wins &&
 					wins.wins.map((win, index) => (
 						<WinComponent key={win.date} win={win} rank={index + 1} />
 					))}
 				{!wins && <span className={classes["noWins"]}>No wins yet.</span>}
+				<div className={classes["transfer"]}>
+					<input
+						type="text"
+						placeholder="Wins file (.json or .csv)"
+						value={file}
+						onChange={(e) => setFile(e.target.value)}
+					/>
+					<button onClick={exportWins} disabled={!file.trim()}>
+						Export
+					</button>
+					<button onClick={importWins} disabled={!file.trim()}>
+						Import
+					</button>
+				</div>
 			</div>
 		</div>
 	);
@@ -82,7 +123,11 @@ const WinComponent = ({ win, rank }: WinComponentProps) => (
 
 		<div className={classes["win"]}>
 			<div className={classes["duration"]}>{win.duration}</div>
-			<div className={classes["date"]}>{win.date}</div>
+			<div className={classes["date"]}>
+				{win.date}
+				{win.noGuess && " · No guess"}
+				{win.hints > 0 && ` · Hints: ${win.hints}`}
+			</div>
 		</div>
 	</div>
 );
@@ -0,0 +1,82 @@
+//! Time to create, mine, flood fill and flag large boards.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use minesweeper::model::{Board, BoardConfig, Mines};
+use std::hint::black_box;
+
+/// Square board sizes measured.
+const SIZES: [u16; 3] = [100, 250, 1000];
+
+/// Square board with the ratio of mined cells.
+fn config(size: u16, density: f64) -> BoardConfig {
+    let size = size.try_into().expect("Board size");
+    BoardConfig::custom(size, size, Mines::Density(density)).expect("Valid board")
+}
+
+fn create(c: &mut Criterion) {
+    let mut group = c.benchmark_group("create");
+    for size in SIZES {
+        let config = config(size, 0.2);
+        group.bench_with_input(BenchmarkId::from_parameter(size), &config, |b, config| {
+            b.iter(|| Board::with_seed(*config, black_box(7)))
+        });
+    }
+    group.finish();
+}
+
+/// First move on a board with a fifth of the cells mined.
+fn mine(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mine");
+    group.sample_size(10);
+    for size in SIZES {
+        let board = Board::with_seed(config(size, 0.2), 7);
+        let first = (size / 2, size / 2).try_into().expect("Position");
+        group.bench_with_input(BenchmarkId::from_parameter(size), &board, |b, board| {
+            b.iter_batched(
+                || board.clone(),
+                |mut board| board.open_cell(first).map(|opened| opened.len()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Opening a corner of an almost empty board, which opens most cells.
+fn flood_fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flood fill");
+    group.sample_size(10);
+    for size in SIZES {
+        let board = Board::with_seed(config(size, 0.001), 7);
+        let corner = (1, 1).try_into().expect("Position");
+        group.bench_with_input(BenchmarkId::from_parameter(size), &board, |b, board| {
+            b.iter_batched(
+                || board.clone(),
+                |mut board| board.open_cell(corner).map(|opened| opened.len()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Flagging a cell after the first move, which changes a single cell.
+fn flag(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flag");
+    for size in SIZES {
+        let mut board = Board::with_seed(config(size, 0.2), 7);
+        let first = (size / 2, size / 2).try_into().expect("Position");
+        board.open_cell(first).expect("Mined board");
+        let corner = (1, 1).try_into().expect("Position");
+        group.bench_with_input(BenchmarkId::from_parameter(size), &board, |b, board| {
+            b.iter_batched(
+                || board.clone(),
+                |mut board| board.flag_cell(corner),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, create, mine, flood_fill, flag);
+criterion_main!(benches);
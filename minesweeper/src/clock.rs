@@ -0,0 +1,242 @@
+//! Game clock with millisecond precision that can be paused and resumed.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Source of the current time for a clock.
+pub trait TimeSource: Debug + Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// Time source reading the system monotonic clock.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SystemTime;
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Run {
+    /// Not started yet.
+    Idle,
+    /// Counting since the instant.
+    Running(Instant),
+    Paused,
+    /// Stopped at the end of the game.
+    Stopped,
+}
+
+/// Clock timing a game. It counts only while running so time spent paused is
+/// left out.
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    source: Arc<dyn TimeSource>,
+    /// Time counted before the current run.
+    counted: Duration,
+    run: Run,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self::with_source(Arc::new(SystemTime))
+    }
+}
+
+impl GameClock {
+    /// Create a clock reading time from the source.
+    pub fn with_source(source: Arc<dyn TimeSource>) -> Self {
+        Self {
+            source,
+            counted: Duration::ZERO,
+            run: Run::Idle,
+        }
+    }
+
+    /// Set the time already counted. The clock is paused until resumed.
+    pub fn with_elapsed(self, elapsed: Duration) -> Self {
+        Self {
+            counted: elapsed,
+            run: Run::Paused,
+            ..self
+        }
+    }
+
+    /// Use another time source, keeping the time counted so far.
+    pub fn set_source(&mut self, source: Arc<dyn TimeSource>) {
+        let running = self.is_running();
+        self.pause();
+        self.source = source;
+        if running {
+            self.resume();
+        }
+    }
+
+    /// Start counting a clock that was not started or is paused.
+    pub fn start(&mut self) {
+        if matches!(self.run, Run::Idle | Run::Paused) {
+            self.run = Run::Running(self.source.now());
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if let Run::Running(since) = self.run {
+            self.counted += self.source.now().saturating_duration_since(since);
+            self.run = Run::Paused;
+        }
+    }
+
+    /// Count again after a pause or a stop.
+    pub fn resume(&mut self) {
+        if !self.is_running() {
+            self.run = Run::Running(self.source.now());
+        }
+    }
+
+    /// Stop the clock at the end of the game.
+    pub fn stop(&mut self) {
+        self.pause();
+        self.run = Run::Stopped;
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.run, Run::Running(_))
+    }
+
+    /// Time counted while running, truncated to milliseconds.
+    pub fn elapsed(&self) -> Duration {
+        let running = match self.run {
+            Run::Running(since) => self.source.now().saturating_duration_since(since),
+            _ => Duration::ZERO,
+        };
+        let elapsed = self.counted + running;
+        Duration::from_millis(elapsed.as_millis() as u64)
+    }
+}
+
+/// A clock is saved as the milliseconds counted and restored paused.
+impl Serialize for GameClock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.elapsed().as_millis() as u64)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameClock {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let millis = u64::deserialize(deserializer)?;
+        Ok(Self::default().with_elapsed(Duration::from_millis(millis)))
+    }
+}
+
+/// Time saved by earlier versions in whole seconds or as a duration.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedTime {
+    Seconds(u64),
+    Exact(Duration),
+}
+
+impl From<SavedTime> for Duration {
+    fn from(time: SavedTime) -> Self {
+        match time {
+            SavedTime::Seconds(seconds) => Duration::from_secs(seconds),
+            SavedTime::Exact(duration) => duration,
+        }
+    }
+}
+
+/// Deserialize a duration that earlier versions saved in whole seconds.
+pub(crate) fn deserialize_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Duration, D::Error> {
+    SavedTime::deserialize(deserializer).map(Into::into)
+}
+
+/// Deserialize an optional duration that earlier versions saved in whole
+/// seconds.
+pub(crate) fn deserialize_optional_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Option::<SavedTime>::deserialize(deserializer).map(|time| time.map(Into::into))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Time source that only moves when advanced.
+    #[derive(Debug)]
+    pub(crate) struct ManualTime(Mutex<Instant>);
+
+    impl ManualTime {
+        pub(crate) fn new() -> Arc<Self> {
+            Arc::new(Self(Mutex::new(Instant::now())))
+        }
+
+        pub(crate) fn advance(&self, millis: u64) {
+            *self.0.lock().unwrap() += Duration::from_millis(millis);
+        }
+    }
+
+    impl TimeSource for ManualTime {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    #[test]
+    fn test_clock() {
+        let time = ManualTime::new();
+        let mut clock = GameClock::with_source(time.clone());
+        time.advance(500);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+
+        clock.start();
+        time.advance(1_250);
+        assert_eq!(clock.elapsed(), Duration::from_millis(1_250));
+        clock.pause();
+        time.advance(10_000);
+        assert_eq!(clock.elapsed(), Duration::from_millis(1_250));
+        clock.resume();
+        time.advance(3);
+        clock.stop();
+        time.advance(100);
+        // Starting a stopped clock does nothing, resuming it counts again.
+        clock.start();
+        assert_eq!(clock.elapsed(), Duration::from_millis(1_253));
+        clock.resume();
+        time.advance(7);
+        assert_eq!(clock.elapsed(), Duration::from_millis(1_260));
+
+        let saved = rmp_serde::to_vec(&clock).unwrap();
+        let restored: GameClock = rmp_serde::from_slice(&saved).unwrap();
+        assert!(!restored.is_running());
+        assert_eq!(restored.elapsed(), Duration::from_millis(1_260));
+    }
+
+    #[test]
+    fn test_saved_time() {
+        #[derive(Serialize)]
+        struct Seconds {
+            time: u64,
+        }
+        #[derive(Serialize, Deserialize)]
+        struct Time {
+            #[serde(deserialize_with = "deserialize_time")]
+            time: Duration,
+        }
+        let read = |bytes: Vec<u8>| rmp_serde::from_slice::<Time>(&bytes).unwrap().time;
+
+        let seconds = rmp_serde::to_vec_named(&Seconds { time: 42 }).unwrap();
+        assert_eq!(read(seconds), Duration::from_secs(42));
+        let exact = Duration::from_millis(42_250);
+        let millis = rmp_serde::to_vec_named(&Time { time: exact }).unwrap();
+        assert_eq!(read(millis), exact);
+    }
+}
@@ -1,89 +1,460 @@
-use anyhow::{Context, Result};
+use crate::{
+    clock::deserialize_time,
+    model::{Board, BoardConfig, GameState, GenerationMode, Metrics},
+    replay::Replay,
+};
 use chrono::{DateTime, Local};
-use rmp_serde::{encode::write_named, from_read};
+use rmp_serde::{decode, from_slice};
 use serde::{Deserialize, Serialize};
 use std::{
-    fs::{create_dir_all, File, OpenOptions},
-    io::BufWriter,
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    fs::remove_file,
+    io::ErrorKind,
+    path::Path,
+    time::Duration,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+mod export;
+mod location;
+mod profile;
+mod settings;
+mod stats;
+mod storage;
+pub use export::{
+    export_statistics, export_wins, import_statistics, import_wins, read_statistics, read_wins,
+    Format,
+};
+pub use location::DataDir;
+pub use profile::{
+    create_profile, delete_profile, hall_of_fame, load_preferences, profiles, rename_profile,
+    save_preferences, Preferences,
+};
+pub use settings::{load_settings, save_settings, update_settings, Settings};
+pub use stats::{load_statistics, record_game, GameStats, Outcome, Statistics};
+pub use storage::HistoryError;
+use storage::{
+    read, read_bytes, read_versioned, update_versioned, write_atomic, write_bytes, write_versioned,
+    Versioned,
+};
+
+type Result<T, E = HistoryError> = std::result::Result<T, E>;
+
+/// Number of wins kept for every leaderboard.
+pub const TOP_WINS: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Win {
     pub date: DateTime<Local>,
-    pub duration: u64,
+    #[serde(deserialize_with = "deserialize_time")]
+    pub duration: Duration,
+    /// Generation of wins recorded before boards were stored, see
+    /// [`Win::generation`].
+    #[serde(default)]
+    pub(crate) generation: GenerationMode,
+    #[serde(default)]
+    pub hints: usize,
+    #[serde(default)]
+    pub replay: Option<Replay>,
+    /// Board the game was won on. Missing for wins recorded before boards
+    /// were stored.
+    #[serde(default)]
+    pub config: Option<BoardConfig>,
+    /// 3BV and efficiency of the game. Missing for wins recorded before
+    /// metrics were kept.
+    #[serde(default)]
+    pub metrics: Option<Metrics>,
+    /// Name of the profile that won, `None` for the anonymous profile.
+    #[serde(default)]
+    pub player: Option<String>,
+}
+
+/// Seconds added to the duration of a win for every hint taken.
+pub const HINT_PENALTY: u64 = 10;
+
+impl Win {
+    /// Time including the penalty for hints, used for ranking.
+    pub fn score(&self) -> Duration {
+        self.duration + Duration::from_secs(self.hints as u64 * HINT_PENALTY)
+    }
+
+    /// How the mines of the board were placed.
+    pub fn generation(&self) -> GenerationMode {
+        self.config
+            .map_or(self.generation, |config| config.generation())
+    }
+
+    pub fn leaderboard(&self) -> Leaderboard {
+        Leaderboard(self.config)
+    }
+}
+
+/// Table of the best wins on one board configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Leaderboard(pub Option<BoardConfig>);
+
+impl Leaderboard {
+    /// Leaderboard for wins on boards with the configuration.
+    pub fn of(config: &BoardConfig) -> Self {
+        Self(Some(config.with_practice(false)))
+    }
+}
+
+impl Display for Leaderboard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(config) => write!(f, "{config}"),
+            None => write!(f, "Earlier wins"),
+        }
+    }
 }
 
+/// Best wins of every leaderboard, ordered by score.
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct WinHistory {
     pub wins: Vec<Win>,
 }
 
-#[cfg(test)]
-const SAVE_FILE_PATH: &str = "/tmp/minesweeper/";
+impl WinHistory {
+    /// Leaderboards with at least one win.
+    pub fn leaderboards(&self) -> Vec<Leaderboard> {
+        let mut leaderboards = self.wins.iter().map(Win::leaderboard).collect::<Vec<_>>();
+        leaderboards.sort();
+        leaderboards.dedup();
+        leaderboards
+    }
 
-#[cfg(not(test))]
-const SAVE_FILE_PATH: &str = ".local/share/minesweeper/";
+    /// Wins on the leaderboard, best score first.
+    pub fn wins_on(&self, leaderboard: Leaderboard) -> Vec<&Win> {
+        self.wins
+            .iter()
+            .filter(|win| win.leaderboard() == leaderboard)
+            .collect()
+    }
 
-#[cfg(not(test))]
-fn get_save_file() -> Result<String> {
-    std::env::var("HOME")
-        .with_context(|| "Could not lookup $HOME environment variable")
-        .map(|home| [&home, "/", SAVE_FILE_PATH, SAVE_FILE].concat())
-        .map(Ok)?
-}
+    /// Leaderboard to show first, the one for the board configuration when
+    /// it has wins.
+    pub fn default_leaderboard(&self, config: &BoardConfig) -> Option<Leaderboard> {
+        let leaderboards = self.leaderboards();
+        let current = Leaderboard::of(config);
+        leaderboards
+            .contains(&current)
+            .then_some(current)
+            .or(leaderboards.first().copied())
+    }
 
-#[cfg(test)]
-fn get_save_file() -> Result<String> {
-    Ok([SAVE_FILE_PATH, SAVE_FILE].concat())
+    /// Add the wins that are not in the history yet, keeping the best
+    /// [TOP_WINS] of every leaderboard. Returns the number of wins that were
+    /// new.
+    pub fn merge(&mut self, wins: impl IntoIterator<Item = Win>) -> usize {
+        let mut added = 0;
+        for win in wins {
+            let known = self
+                .wins
+                .iter()
+                .any(|known| known.date == win.date && known.duration == win.duration);
+            if !known {
+                self.add(win);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    /// Add a win keeping the best [TOP_WINS] of every leaderboard.
+    fn add(&mut self, win: Win) {
+        self.wins.push(win);
+        self.wins.sort_by_key(Win::score);
+        let mut counts = HashMap::new();
+        self.wins.retain(|win| {
+            let count = counts.entry(win.leaderboard()).or_insert(0);
+            *count += 1;
+            *count <= TOP_WINS
+        });
+    }
 }
 
-#[cfg(not(test))]
-fn get_full_save_path() -> Result<String> {
-    std::env::var("HOME")
-        .with_context(|| "Could not lookup up $HOME environment variable")
-        .map(|home| [&home, "/", SAVE_FILE_PATH].concat())
-        .map(Ok)?
+/// Version 1 wraps the history in a versioned envelope.
+impl Versioned for WinHistory {
+    const VERSION: u32 = 1;
+
+    fn migrate(_version: u32, bytes: &[u8]) -> Result<Self, decode::Error> {
+        // The unversioned history is the only earlier format.
+        from_slice(bytes)
+    }
 }
 
-#[cfg(test)]
-fn get_full_save_path() -> Result<String> {
-    Ok(SAVE_FILE_PATH.into())
+/// Version of the saved game format.
+pub const SAVED_GAME_VERSION: u32 = 1;
+
+/// An in-progress game saved to be resumed later.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedGame {
+    pub version: u32,
+    pub date: DateTime<Local>,
+    /// Board with its mine layout, seed and counters.
+    pub board: Board,
+    /// Time of play, not counting time paused. Boards saved before they kept
+    /// their clock are restored with this time.
+    #[serde(deserialize_with = "deserialize_time")]
+    pub elapsed: Duration,
 }
 
 const SAVE_FILE: &str = "stats.bin";
+const GAME_FILE: &str = "game.bin";
+const REPLAY_FILE: &str = "replay.bin";
 
-/// Save the win to the win history.
-pub fn save_win(duration: u64) -> Result<()> {
-    persist_win(Win {
-        duration,
+/// Save the win for the board to the win history. Fails unless the game is
+/// won. Practice wins are not recorded. A corrupt win history is backed up
+/// and replaced by a history with only this win, returning the error once
+/// the win is saved.
+pub fn save_win(dir: &DataDir, board: &Board) -> Result<()> {
+    if *board.state() != GameState::Win {
+        return Err(HistoryError::NotWon {
+            state: *board.state(),
+        });
+    }
+    if board.config().practice() {
+        return Ok(());
+    }
+    let win = Win {
+        duration: board.elapsed(),
         date: Local::now(),
+        generation: board.config().generation(),
+        hints: board.hints(),
+        replay: Some(Replay::new(board)),
+        config: Some(*board.config()),
+        metrics: board.metrics(),
+        player: dir.profile().map(ToOwned::to_owned),
+    };
+    update_versioned(&dir.file(SAVE_FILE), |history: &mut WinHistory| {
+        history.add(win)
     })
 }
 
-/// Load win history.
-pub fn load_wins() -> Option<WinHistory> {
-    OpenOptions::new()
-        .read(true)
-        .open(get_save_file().ok()?)
-        .ok()
-        .and_then(|stats_file| from_read(&stats_file).ok())
-}
-
-// Save the best 10 results as a MessagePack format.
-fn persist_win(win: Win) -> anyhow::Result<()> {
-    create_dir_all(get_full_save_path()?)
-        .with_context(|| "Could not create folder for stats file")?;
-
-    let save_file = get_save_file()?;
-    let mut history: WinHistory = from_read(File::open(&save_file)?).unwrap_or_else(|err| {
-        eprintln!("Failed to read stats file: {err}. Creating new WinHistory");
-        WinHistory::default()
-    });
-    history.wins.push(win);
-    history.wins.sort_by_key(|win| win.duration);
-    history.wins = history.wins.into_iter().take(10).collect();
-    let stats_file = File::create(&save_file)?;
-    let mut writer = BufWriter::new(stats_file);
-    write_named(&mut writer, &history)?;
-    Ok(())
+/// Load the win history. Empty when no game was won.
+pub fn load_wins(dir: &DataDir) -> Result<WinHistory> {
+    read_versioned(&dir.file(SAVE_FILE)).map(Option::unwrap_or_default)
+}
+
+/// Save an active game to be resumed later. Games that are over are not
+/// saved and replace any previously saved game.
+pub fn save_game(dir: &DataDir, board: &Board) -> Result<()> {
+    if *board.state() != GameState::Active {
+        return remove_saved_game(dir);
+    }
+    let game = SavedGame {
+        version: SAVED_GAME_VERSION,
+        date: Local::now(),
+        board: board.clone(),
+        elapsed: board.elapsed(),
+    };
+    write_atomic(&dir.file(GAME_FILE), &game)
+}
+
+/// Load the saved game. Returns `None` when no game was saved.
+pub fn load_game(dir: &DataDir) -> Result<Option<SavedGame>> {
+    let path = dir.file(GAME_FILE);
+    let Some(mut game) = read::<SavedGame>(&path)? else {
+        return Ok(None);
+    };
+    if game.version != SAVED_GAME_VERSION {
+        return Err(HistoryError::UnsupportedVersion {
+            path,
+            version: game.version,
+        });
+    }
+    if game.board.elapsed().is_zero() {
+        game.board.set_elapsed(game.elapsed);
+    }
+    Ok(Some(game))
+}
+
+/// Save the replay of the last finished game, replacing the previous one.
+pub fn save_replay(dir: &DataDir, replay: &Replay) -> Result<()> {
+    write_atomic(&dir.file(REPLAY_FILE), replay)
+}
+
+/// Load a replay from the given file, or the replay of the last finished
+/// game when no file is given.
+pub fn load_replay(dir: &DataDir, file: Option<&Path>) -> Result<Replay> {
+    let file = match file {
+        Some(file) => file.to_path_buf(),
+        None => dir.file(REPLAY_FILE),
+    };
+    read(&file)?.ok_or_else(|| HistoryError::io(&file)(ErrorKind::NotFound.into()))
+}
+
+/// Remove the saved game once it has been resumed or replaced.
+pub fn remove_saved_game(dir: &DataDir) -> Result<()> {
+    let path = dir.file(GAME_FILE);
+    match remove_file(&path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => Err(HistoryError::io(&path)(err)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{clock::test::ManualTime, model::BoardConfig};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_save_game() {
+        let folder = tempdir().unwrap();
+        let dir = DataDir::new(folder.path());
+        let mut board = Board::with_seed(BoardConfig::default(), 7);
+        let time = ManualTime::new();
+        board.set_time_source(time.clone());
+        save_game(&dir, &board).unwrap();
+        assert!(load_game(&dir).unwrap().is_none());
+
+        board.open_cell((5, 5).try_into().unwrap()).unwrap();
+        board.flag_cell((1, 1).try_into().unwrap());
+        time.advance(42_250);
+        save_game(&dir, &board).unwrap();
+
+        let saved = load_game(&dir).unwrap().unwrap();
+        assert_eq!(saved.version, SAVED_GAME_VERSION);
+        assert_eq!(saved.elapsed, Duration::from_millis(42_250));
+        assert_eq!(saved.board.elapsed(), saved.elapsed);
+        assert!(!saved.board.clock().is_running());
+        assert_eq!(saved.board.to_string(), board.to_string());
+        assert_eq!(saved.board.flagged(), board.flagged());
+        assert_eq!(saved.board.state(), board.state());
+
+        remove_saved_game(&dir).unwrap();
+        assert!(load_game(&dir).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_save_win_needs_win() {
+        let folder = tempdir().unwrap();
+        let dir = DataDir::new(folder.path());
+        let mut board = Board::with_seed(BoardConfig::default(), 7);
+        board.open_cell((5, 5).try_into().unwrap()).unwrap();
+        assert!(matches!(
+            save_win(&dir, &board),
+            Err(HistoryError::NotWon {
+                state: GameState::Active
+            })
+        ));
+        assert!(load_wins(&dir).unwrap().wins.is_empty());
+    }
+
+    #[test]
+    fn test_leaderboards() {
+        let beginner = BoardConfig::default();
+        let expert = BoardConfig::preset(crate::model::Difficulty::Expert).unwrap();
+        let win = |config: Option<BoardConfig>, duration| Win {
+            date: Local::now(),
+            duration: Duration::from_secs(duration),
+            generation: GenerationMode::Random,
+            hints: 0,
+            replay: None,
+            config,
+            metrics: None,
+            player: None,
+        };
+
+        let mut history = WinHistory::default();
+        for duration in (1..=TOP_WINS as u64 + 2).rev() {
+            history.add(win(Some(beginner), duration));
+        }
+        history.add(win(Some(expert), 500));
+        history.add(win(None, 100));
+
+        assert_eq!(
+            history.leaderboards(),
+            vec![
+                Leaderboard(None),
+                Leaderboard(Some(beginner)),
+                Leaderboard(Some(expert))
+            ]
+        );
+        let beginner_wins = history.wins_on(Leaderboard(Some(beginner)));
+        assert_eq!(beginner_wins.len(), TOP_WINS);
+        assert_eq!(beginner_wins[0].duration, Duration::from_secs(1));
+        // A slow win on a bigger board is kept next to fast small board wins.
+        assert_eq!(
+            history.wins_on(Leaderboard(Some(expert)))[0].score(),
+            Duration::from_secs(500)
+        );
+
+        // Practice games show the leaderboard of their board.
+        assert_eq!(
+            history.default_leaderboard(&expert.with_practice(true)),
+            Some(Leaderboard(Some(expert)))
+        );
+        let custom = BoardConfig::custom(
+            5.try_into().unwrap(),
+            5.try_into().unwrap(),
+            crate::model::Mines::Count(3),
+        )
+        .unwrap();
+        assert_eq!(
+            history.default_leaderboard(&custom),
+            Some(Leaderboard(None))
+        );
+    }
+
+    #[test]
+    fn test_win_generation() {
+        let no_guess = BoardConfig::default().with_generation(GenerationMode::NoGuess);
+        let mut win = Win {
+            date: Local::now(),
+            duration: Duration::from_secs(30),
+            generation: GenerationMode::Random,
+            hints: 0,
+            replay: None,
+            config: Some(no_guess),
+            metrics: None,
+            player: None,
+        };
+        assert_eq!(win.generation(), GenerationMode::NoGuess);
+
+        // Wins recorded before boards were stored keep their own generation.
+        win.config = None;
+        win.generation = GenerationMode::NoGuess;
+        assert_eq!(win.generation(), GenerationMode::NoGuess);
+    }
+
+    #[test]
+    fn test_unversioned_history() {
+        let folder = tempdir().unwrap();
+        let dir = DataDir::new(folder.path());
+        let mut history = WinHistory::default();
+        history.add(Win {
+            date: Local::now(),
+            duration: Duration::from_secs(30),
+            generation: GenerationMode::Random,
+            hints: 1,
+            replay: None,
+            config: None,
+            metrics: None,
+            player: None,
+        });
+        write_atomic(&dir.file(SAVE_FILE), &history).unwrap();
+
+        let migrated = load_wins(&dir).unwrap();
+        assert_eq!(migrated.wins[0].score(), Duration::from_secs(40));
+    }
+
+    #[test]
+    fn test_save_replay() {
+        let folder = tempdir().unwrap();
+        let dir = DataDir::new(folder.path());
+        let mut board = Board::with_seed(BoardConfig::default(), 7);
+        board.open_cell((5, 5).try_into().unwrap()).unwrap();
+        save_replay(&dir, &Replay::new(&board)).unwrap();
+
+        let replay = load_replay(&dir, None).unwrap();
+        assert_eq!(replay.seed, 7);
+        assert_eq!(replay.actions.len(), 1);
+        assert!(replay.duration <= board.elapsed());
+        assert_eq!(replay.verify(), Ok(()));
+        let missing = folder.path().join("missing.bin");
+        assert!(load_replay(&dir, Some(&missing)).is_err());
+    }
 }
@@ -0,0 +1,576 @@
+//! Export and import of the win history and lifetime statistics as JSON or
+//! CSV files that can be read outside the game.
+use super::{
+    read_bytes, stats::STATS_FILE, update_versioned, write_bytes, DataDir, GameStats, HistoryError,
+    Result, Statistics, Win, WinHistory, SAVE_FILE,
+};
+use crate::model::{
+    BoardConfig, Clicks, Coord, Difficulty, GenerationMode, Metrics, Mines, TopologyMode, WinRule,
+};
+use chrono::{DateTime, Local};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{io::ErrorKind, path::Path, time::Duration};
+
+/// Format of an exported file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Every record with all its details.
+    Json,
+    /// A row for every record for spreadsheets. Replays are left out.
+    Csv,
+}
+
+impl Format {
+    /// Format for the extension of the file.
+    pub fn of(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(Self::Json),
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Ok(Self::Csv),
+            _ => Err(HistoryError::Format {
+                path: path.to_path_buf(),
+            }),
+        }
+    }
+}
+
+/// Board of a row from its columns. The preset of the difficulty is used
+/// when its dimensions match.
+fn board_config(
+    path: &Path,
+    difficulty: Option<Difficulty>,
+    columns: Coord,
+    rows: Coord,
+    mines: usize,
+    generation: GenerationMode,
+    topology: TopologyMode,
+) -> Result<BoardConfig> {
+    let preset = difficulty.and_then(BoardConfig::preset).filter(|preset| {
+        (preset.columns(), preset.rows(), preset.mines()) == (columns, rows, mines)
+    });
+    let config = match preset {
+        Some(preset) => preset,
+        None => BoardConfig::custom(columns, rows, Mines::Count(mines)).map_err(|source| {
+            HistoryError::Board {
+                path: path.to_path_buf(),
+                source,
+            }
+        })?,
+    };
+    Ok(config.with_generation(generation).with_topology(topology))
+}
+
+/// Check a board read from a JSON file, which CSV rows get from
+/// [`board_config`].
+fn validate(path: &Path, config: BoardConfig) -> Result<BoardConfig> {
+    config.validate().map_err(|source| HistoryError::Board {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Row of a win in a CSV file.
+#[derive(Serialize, Deserialize)]
+struct WinRow {
+    date: DateTime<Local>,
+    /// Empty for wins of the anonymous profile and files exported before
+    /// profiles.
+    #[serde(default)]
+    player: Option<String>,
+    milliseconds: u64,
+    hints: usize,
+    difficulty: Option<Difficulty>,
+    columns: Option<Coord>,
+    rows: Option<Coord>,
+    mines: Option<usize>,
+    generation: Option<GenerationMode>,
+    /// Empty in files exported before board topologies.
+    #[serde(default)]
+    topology: Option<TopologyMode>,
+    /// Empty in files exported before win rules.
+    #[serde(default)]
+    win_rule: Option<WinRule>,
+    bbbv: Option<u32>,
+    solved_bbbv: Option<u32>,
+    left_clicks: Option<u32>,
+    right_clicks: Option<u32>,
+    chord_clicks: Option<u32>,
+    /// Written for spreadsheets, computed again on import.
+    efficiency: Option<f64>,
+}
+
+impl From<&Win> for WinRow {
+    fn from(win: &Win) -> Self {
+        Self {
+            date: win.date,
+            player: win.player.clone(),
+            milliseconds: win.duration.as_millis() as u64,
+            hints: win.hints,
+            difficulty: win.config.map(|config| config.difficulty()),
+            columns: win.config.map(|config| config.columns()),
+            rows: win.config.map(|config| config.rows()),
+            mines: win.config.map(|config| config.mines()),
+            generation: win.config.map(|config| config.generation()),
+            topology: win.config.map(|config| config.topology()),
+            win_rule: win.config.map(|config| config.win_rule()),
+            bbbv: win.metrics.map(|metrics| metrics.bbbv),
+            solved_bbbv: win.metrics.map(|metrics| metrics.solved_bbbv),
+            left_clicks: win.metrics.map(|metrics| metrics.clicks.left),
+            right_clicks: win.metrics.map(|metrics| metrics.clicks.right),
+            chord_clicks: win.metrics.map(|metrics| metrics.clicks.chord),
+            efficiency: win.metrics.map(|metrics| metrics.efficiency),
+        }
+    }
+}
+
+impl WinRow {
+    fn win(self, path: &Path) -> Result<Win> {
+        // Wins recorded before boards were stored have no board columns.
+        let config = match (self.columns, self.rows, self.mines) {
+            (Some(columns), Some(rows), Some(mines)) => Some(
+                board_config(
+                    path,
+                    self.difficulty,
+                    columns,
+                    rows,
+                    mines,
+                    self.generation.unwrap_or_default(),
+                    self.topology.unwrap_or_default(),
+                )?
+                .with_win_rule(self.win_rule.unwrap_or_default()),
+            ),
+            _ => None,
+        };
+        let duration = Duration::from_millis(self.milliseconds);
+        let metrics = match (self.bbbv, self.solved_bbbv) {
+            (Some(bbbv), Some(solved_bbbv)) => {
+                let clicks = Clicks {
+                    left: self.left_clicks.unwrap_or_default(),
+                    right: self.right_clicks.unwrap_or_default(),
+                    chord: self.chord_clicks.unwrap_or_default(),
+                };
+                Some(Metrics::new(bbbv, solved_bbbv, clicks, duration))
+            }
+            _ => None,
+        };
+        Ok(Win {
+            date: self.date,
+            duration,
+            generation: config.map(|config| config.generation()).unwrap_or_default(),
+            hints: self.hints,
+            replay: None,
+            config,
+            metrics,
+            player: self.player,
+        })
+    }
+}
+
+/// Row of the statistics of a board in a CSV file. The rows only keep the
+/// totals, so games their source imported from others are counted with it.
+#[derive(Serialize, Deserialize)]
+struct StatsRow {
+    /// Source of the statistics, zero in files exported before sources.
+    #[serde(default)]
+    source: u64,
+    difficulty: Difficulty,
+    columns: Coord,
+    rows: Coord,
+    mines: usize,
+    generation: GenerationMode,
+    /// Grid in files exported before board topologies.
+    #[serde(default)]
+    topology: TopologyMode,
+    /// Classic in files exported before win rules.
+    #[serde(default)]
+    win_rule: WinRule,
+    played: u32,
+    wins: u32,
+    losses: u32,
+    abandoned: u32,
+    current_streak: u32,
+    best_streak: u32,
+    win_milliseconds: u64,
+    best_milliseconds: Option<u64>,
+    bbbv: u64,
+    clicks: u64,
+}
+
+impl From<&(BoardConfig, GameStats)> for StatsRow {
+    fn from((config, stats): &(BoardConfig, GameStats)) -> Self {
+        Self {
+            source: 0,
+            difficulty: config.difficulty(),
+            columns: config.columns(),
+            rows: config.rows(),
+            mines: config.mines(),
+            generation: config.generation(),
+            topology: config.topology(),
+            win_rule: config.win_rule(),
+            played: stats.played,
+            wins: stats.wins,
+            losses: stats.losses,
+            abandoned: stats.abandoned,
+            current_streak: stats.current_streak,
+            best_streak: stats.best_streak,
+            win_milliseconds: stats.win_time.as_millis() as u64,
+            best_milliseconds: stats.best_time.map(|time| time.as_millis() as u64),
+            bbbv: stats.bbbv,
+            clicks: stats.clicks,
+        }
+    }
+}
+
+impl StatsRow {
+    fn stats(self, path: &Path) -> Result<(BoardConfig, GameStats)> {
+        let config = board_config(
+            path,
+            Some(self.difficulty),
+            self.columns,
+            self.rows,
+            self.mines,
+            self.generation,
+            self.topology,
+        )?
+        .with_win_rule(self.win_rule);
+        let stats = GameStats {
+            played: self.played,
+            wins: self.wins,
+            losses: self.losses,
+            abandoned: self.abandoned,
+            current_streak: self.current_streak,
+            best_streak: self.best_streak,
+            win_time: Duration::from_millis(self.win_milliseconds),
+            best_time: self.best_milliseconds.map(Duration::from_millis),
+            bbbv: self.bbbv,
+            clicks: self.clicks,
+        };
+        Ok((config, stats))
+    }
+}
+
+/// Write the win history to a file.
+pub fn export_wins(history: &WinHistory, path: &Path, format: Format) -> Result<()> {
+    match format {
+        Format::Json => write_json(path, history),
+        Format::Csv => write_csv(path, history.wins.iter().map(WinRow::from)),
+    }
+}
+
+/// Read the wins of an exported win history.
+pub fn read_wins(path: &Path, format: Format) -> Result<Vec<Win>> {
+    match format {
+        Format::Json => read_json::<WinHistory>(path)?
+            .wins
+            .into_iter()
+            .map(|win| {
+                let configs = win
+                    .config
+                    .iter()
+                    .chain(win.replay.iter().map(|r| &r.config));
+                for config in configs {
+                    validate(path, *config)?;
+                }
+                Ok(win)
+            })
+            .collect(),
+        Format::Csv => read_csv::<WinRow>(path)?
+            .into_iter()
+            .map(|row| row.win(path))
+            .collect(),
+    }
+}
+
+/// Merge the wins of an exported win history into the win history. Returns
+/// the number of wins that were new.
+pub fn import_wins(dir: &DataDir, path: &Path, format: Format) -> Result<usize> {
+    let wins = read_wins(path, format)?;
+    let mut added = 0;
+    update_versioned(&dir.file(SAVE_FILE), |history: &mut WinHistory| {
+        added = history.merge(wins)
+    })?;
+    Ok(added)
+}
+
+/// Write the lifetime statistics to a file.
+pub fn export_statistics(statistics: &Statistics, path: &Path, format: Format) -> Result<()> {
+    match format {
+        Format::Json => write_json(path, statistics),
+        Format::Csv => write_csv(
+            path,
+            statistics.boards.iter().map(|board| StatsRow {
+                source: statistics.source,
+                ..StatsRow::from(board)
+            }),
+        ),
+    }
+}
+
+/// Read exported lifetime statistics.
+pub fn read_statistics(path: &Path, format: Format) -> Result<Statistics> {
+    match format {
+        Format::Json => {
+            let statistics = read_json::<Statistics>(path)?;
+            let imported = statistics.imports.iter().flat_map(|import| &import.boards);
+            for (config, _) in statistics
+                .boards
+                .iter()
+                .chain(&statistics.own)
+                .chain(imported)
+            {
+                validate(path, *config)?;
+            }
+            Ok(statistics)
+        }
+        Format::Csv => {
+            let rows = read_csv::<StatsRow>(path)?;
+            let source = rows.first().map_or(0, |row| row.source);
+            let boards = rows
+                .into_iter()
+                .map(|row| row.stats(path))
+                .collect::<Result<_>>()?;
+            Ok(Statistics {
+                boards,
+                source,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+/// Merge exported lifetime statistics into the lifetime statistics, replacing
+/// the games counted from an earlier export of the same source. Returns the
+/// number of boards counted from the file.
+pub fn import_statistics(dir: &DataDir, path: &Path, format: Format) -> Result<usize> {
+    let imported = read_statistics(path, format)?;
+    let mut taken = 0;
+    update_versioned(&dir.file(STATS_FILE), |statistics: &mut Statistics| {
+        taken = statistics.merge(imported)
+    })?;
+    Ok(taken)
+}
+
+fn write_json<T: Serialize>(path: &Path, data: &T) -> Result<()> {
+    let bytes = serde_json::to_vec_pretty(data).map_err(json_error(path))?;
+    write_bytes(path, &bytes)
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let bytes = read_file(path)?;
+    serde_json::from_slice(&bytes).map_err(json_error(path))
+}
+
+fn write_csv<T: Serialize>(path: &Path, rows: impl Iterator<Item = T>) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for row in rows {
+        writer.serialize(row).map_err(csv_error(path))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|err| HistoryError::io(path)(err.into_error()))?;
+    write_bytes(path, &bytes)
+}
+
+fn read_csv<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let bytes = read_file(path)?;
+    csv::Reader::from_reader(bytes.as_slice())
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .map_err(csv_error(path))
+}
+
+/// Read a file that must exist.
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    read_bytes(path)?.ok_or_else(|| HistoryError::io(path)(ErrorKind::NotFound.into()))
+}
+
+fn json_error(path: &Path) -> impl FnOnce(serde_json::Error) -> HistoryError + '_ {
+    move |source| HistoryError::Json {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+fn csv_error(path: &Path) -> impl FnOnce(csv::Error) -> HistoryError + '_ {
+    move |source| HistoryError::Csv {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        history::{load_statistics, record_game},
+        model::{Board, ConfigError},
+    };
+    use tempfile::tempdir;
+
+    fn win(duration: u64) -> Win {
+        let clicks = Clicks {
+            left: 10,
+            right: 2,
+            chord: 1,
+        };
+        Win {
+            date: Local::now(),
+            duration: Duration::from_millis(duration),
+            generation: GenerationMode::Random,
+            hints: 1,
+            replay: None,
+            config: Some(BoardConfig::default()),
+            metrics: Some(Metrics::new(
+                12,
+                12,
+                clicks,
+                Duration::from_millis(duration),
+            )),
+            player: Some("Ada".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_wins() {
+        let folder = tempdir().unwrap();
+        let mut history = WinHistory::default();
+        history.merge([win(20_250), win(30_500)]);
+        history.merge([Win {
+            config: None,
+            metrics: None,
+            player: None,
+            ..win(40_000)
+        }]);
+
+        for format in [Format::Json, Format::Csv] {
+            let path = folder.path().join(format!("wins.{format:?}"));
+            export_wins(&history, &path, format).unwrap();
+            let wins = read_wins(&path, format).unwrap();
+            assert_eq!(wins.len(), 3);
+            for (read, win) in wins.iter().zip(&history.wins) {
+                assert_eq!(read.date, win.date);
+                assert_eq!(read.score(), win.score());
+                assert_eq!(read.config, win.config);
+                assert_eq!(read.metrics, win.metrics);
+                assert_eq!(read.player, win.player);
+            }
+
+            let dir = DataDir::new(folder.path().join(format!("{format:?}")));
+            assert_eq!(import_wins(&dir, &path, format).unwrap(), 3);
+            // Wins already in the history are not added again.
+            assert_eq!(import_wins(&dir, &path, format).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn test_statistics() {
+        let folder = tempdir().unwrap();
+        let beginner = BoardConfig::default();
+        let expert = BoardConfig::preset(Difficulty::Expert).unwrap();
+        let custom = BoardConfig::custom(
+            5.try_into().unwrap(),
+            5.try_into().unwrap(),
+            Mines::Count(3),
+        )
+        .unwrap()
+        .with_topology(TopologyMode::Torus);
+        let stats = |played| GameStats {
+            played,
+            wins: 1,
+            win_time: Duration::from_millis(12_345),
+            best_time: Some(Duration::from_millis(12_345)),
+            ..Default::default()
+        };
+        let statistics = Statistics {
+            boards: vec![(beginner, stats(3)), (custom, stats(2))],
+            ..Default::default()
+        };
+
+        let path = folder.path().join("statistics.csv");
+        export_statistics(&statistics, &path, Format::of(&path).unwrap()).unwrap();
+        let read = read_statistics(&path, Format::Csv).unwrap();
+        assert_eq!(read.boards, statistics.boards);
+
+        // Boards of JSON files are checked like the rows of CSV files.
+        let json = folder.path().join("statistics.json");
+        export_statistics(&statistics, &json, Format::Json).unwrap();
+        let text = std::fs::read_to_string(&json).unwrap();
+        std::fs::write(&json, text.replace("\"mines\": 3,", "\"mines\": 30,")).unwrap();
+        assert!(matches!(
+            read_statistics(&json, Format::Json),
+            Err(HistoryError::Board {
+                source: ConfigError::TooManyMines { mines: 30, .. },
+                ..
+            })
+        ));
+
+        let mut known = Statistics {
+            boards: vec![(beginner, stats(5)), (expert, stats(1))],
+            ..Default::default()
+        };
+        // Beginner games are added up, the custom board is new.
+        assert_eq!(known.merge(read), 2);
+        let merged = known.of(&beginner).unwrap();
+        assert_eq!((merged.played, merged.wins), (8, 2));
+        assert_eq!(merged.best_time, Some(Duration::from_millis(12_345)));
+        assert_eq!(merged.average_time(), Some(Duration::from_millis(12_345)));
+        assert_eq!(known.of(&custom), Some(&stats(2)));
+        assert_eq!(known.boards.len(), 3);
+
+        // The same statistics and exports of these statistics are only
+        // counted once.
+        let read = read_statistics(&path, Format::Csv).unwrap();
+        assert_eq!(known.merge(read), 0);
+        assert_eq!(known.of(&beginner).unwrap().played, 8);
+        assert_eq!(known.merge(known.clone()), 0);
+        assert_eq!(known.of(&beginner).unwrap().played, 8);
+    }
+
+    #[test]
+    fn test_import_later_statistics() {
+        let folder = tempdir().unwrap();
+        let dir = DataDir::new(folder.path());
+        let beginner = BoardConfig::default();
+        let mut board = Board::with_seed(beginner, 7);
+        board.open_cell((5, 5).try_into().unwrap()).unwrap();
+        record_game(&dir, &board).unwrap();
+        let source = load_statistics(&dir).unwrap().source;
+        assert_ne!(source, 0);
+
+        let other = |played| Statistics {
+            boards: vec![(
+                beginner,
+                GameStats {
+                    played,
+                    wins: played,
+                    ..Default::default()
+                },
+            )],
+            source: 42,
+            ..Default::default()
+        };
+        let path = folder.path().join("other.json");
+        export_statistics(&other(2), &path, Format::Json).unwrap();
+        assert_eq!(import_statistics(&dir, &path, Format::Json).unwrap(), 1);
+        assert_eq!(
+            load_statistics(&dir).unwrap().of(&beginner).unwrap().played,
+            3
+        );
+
+        // A later export of the same source replaces its games.
+        export_statistics(&other(5), &path, Format::Json).unwrap();
+        assert_eq!(import_statistics(&dir, &path, Format::Json).unwrap(), 1);
+        assert_eq!(import_statistics(&dir, &path, Format::Json).unwrap(), 0);
+        let statistics = load_statistics(&dir).unwrap();
+        assert_eq!(statistics.source, source);
+        let stats = statistics.of(&beginner).unwrap();
+        assert_eq!((stats.played, stats.wins, stats.abandoned), (6, 5, 1));
+
+        // Games played here are counted again there, without the games
+        // imported from there.
+        record_game(&dir, &board).unwrap();
+        let mut other = other(5);
+        other.merge(load_statistics(&dir).unwrap());
+        let stats = other.of(&beginner).unwrap();
+        assert_eq!((stats.played, stats.wins, stats.abandoned), (7, 5, 2));
+    }
+}
@@ -0,0 +1,149 @@
+//! Folders holding the history and settings files.
+use super::{profile::check_name, HistoryError, Result};
+use std::{
+    env::var_os,
+    ffi::OsString,
+    fmt::{self, Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+/// Name of the folder created in the XDG data and config folders.
+const APP_FOLDER: &str = "minesweeper";
+
+/// Folder in the data folder holding a folder for every profile.
+pub(super) const PROFILES_FOLDER: &str = "profiles";
+
+/// Folder holding the win history, statistics, saved game and replay of the
+/// active profile. Files of the anonymous profile are kept in the data folder
+/// itself, those of a named profile in its folder under `profiles`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDir {
+    path: PathBuf,
+    profile: Option<String>,
+}
+
+impl DataDir {
+    /// Use the given folder with the anonymous profile.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            profile: None,
+        }
+    }
+
+    /// Default folder, `minesweeper` in `$XDG_DATA_HOME` or
+    /// `~/.local/share` when it is not set.
+    pub fn locate() -> Result<Self> {
+        xdg_folder(|name| var_os(name), "XDG_DATA_HOME", ".local/share").map(Self::new)
+    }
+
+    /// Same folder with the named profile active, or the anonymous profile
+    /// when `None`. Fails for names that can't be used as a folder.
+    pub fn with_profile(self, profile: Option<&str>) -> Result<Self> {
+        profile.map(check_name).transpose()?;
+        Ok(Self {
+            profile: profile.map(ToOwned::to_owned),
+            ..self
+        })
+    }
+
+    /// The data folder shared by every profile.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Name of the active profile, `None` for the anonymous profile.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Folder of the named profile.
+    pub(super) fn profile_folder(&self, name: &str) -> PathBuf {
+        self.path.join(PROFILES_FOLDER).join(name)
+    }
+
+    /// Path of a file of the active profile.
+    pub(super) fn file(&self, name: &str) -> PathBuf {
+        match &self.profile {
+            Some(profile) => self.profile_folder(profile).join(name),
+            None => self.path.join(name),
+        }
+    }
+}
+
+impl Display for DataDir {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())
+    }
+}
+
+/// Default folder for the settings, `minesweeper` in `$XDG_CONFIG_HOME` or
+/// `~/.config` when it is not set.
+pub(super) fn config_dir() -> Result<PathBuf> {
+    xdg_folder(|name| var_os(name), "XDG_CONFIG_HOME", ".config")
+}
+
+/// The application folder in an XDG base directory. Inside Flatpak the XDG
+/// variables point to folders private to the app, the folders of the host
+/// are used instead so other builds share the same files.
+fn xdg_folder(
+    env: impl Fn(&str) -> Option<OsString>,
+    variable: &str,
+    fallback: &str,
+) -> Result<PathBuf> {
+    let variable = if env("FLATPAK_ID").is_some() {
+        format!("HOST_{variable}")
+    } else {
+        variable.to_owned()
+    };
+    // Relative paths are invalid and ignored as the specification requires.
+    let absolute = |name: &str| {
+        env(name)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+    };
+    absolute(&variable)
+        .or_else(|| absolute("HOME").map(|home| home.join(fallback)))
+        .map(|base| base.join(APP_FOLDER))
+        .ok_or(HistoryError::Home)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_xdg_folder() {
+        let folder = |vars: &[(&str, &str)]| {
+            let env = |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.into())
+            };
+            xdg_folder(env, "XDG_DATA_HOME", ".local/share").ok()
+        };
+        let path = |path: &str| Some(PathBuf::from(path));
+
+        assert_eq!(
+            folder(&[("HOME", "/home/mines")]),
+            path("/home/mines/.local/share/minesweeper")
+        );
+        assert_eq!(
+            folder(&[("HOME", "/home/mines"), ("XDG_DATA_HOME", "/data")]),
+            path("/data/minesweeper")
+        );
+        assert_eq!(
+            folder(&[("HOME", "/home/mines"), ("XDG_DATA_HOME", "data")]),
+            path("/home/mines/.local/share/minesweeper")
+        );
+        assert_eq!(
+            folder(&[
+                ("FLATPAK_ID", "io.github.darrellroberts.minesweeper"),
+                ("XDG_DATA_HOME", "/home/mines/.var/app/data"),
+                ("HOST_XDG_DATA_HOME", "/data"),
+            ]),
+            path("/data/minesweeper")
+        );
+        assert_eq!(folder(&[("XDG_DATA_HOME", "data")]), None);
+    }
+}
@@ -0,0 +1,231 @@
+//! Named player profiles, each with its own win history, statistics,
+//! preferences and saved game.
+use super::{
+    load_wins, location::PROFILES_FOLDER, read_versioned, update_versioned, write_versioned,
+    DataDir, HistoryError, Result, Versioned, WinHistory, SAVE_FILE,
+};
+use crate::model::{Difficulty, GenerationMode};
+use rmp_serde::{decode, from_slice};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{create_dir_all, read_dir, remove_dir_all, rename},
+    io::ErrorKind,
+    path::PathBuf,
+};
+
+const PREFERENCES_FILE: &str = "preferences.bin";
+
+/// Board and marks last chosen by the player of a profile.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Preferences {
+    pub difficulty: Difficulty,
+    pub generation: GenerationMode,
+    /// Flag a flagged cell again to mark it with a question mark.
+    #[serde(default)]
+    pub question_marks: bool,
+}
+
+impl Versioned for Preferences {
+    const VERSION: u32 = 1;
+
+    fn migrate(_version: u32, bytes: &[u8]) -> Result<Self, decode::Error> {
+        // Preferences were versioned from the start, there is no earlier
+        // format.
+        from_slice(bytes)
+    }
+}
+
+/// Load the preferences of the active profile. Returns `None` when none
+/// were saved.
+pub fn load_preferences(dir: &DataDir) -> Result<Option<Preferences>> {
+    read_versioned(&dir.file(PREFERENCES_FILE))
+}
+
+pub fn save_preferences(dir: &DataDir, preferences: &Preferences) -> Result<()> {
+    write_versioned(&dir.file(PREFERENCES_FILE), preferences)
+}
+
+/// Names of the profiles in the data folder, sorted by name.
+pub fn profiles(dir: &DataDir) -> Result<Vec<String>> {
+    let folder = dir.path().join(PROFILES_FOLDER);
+    let entries = match read_dir(&folder) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(HistoryError::io(&folder)(err)),
+    };
+    let mut names = vec![];
+    for entry in entries {
+        let entry = entry.map_err(HistoryError::io(&folder))?;
+        let is_dir = entry
+            .file_type()
+            .map_err(HistoryError::io(&entry.path()))?
+            .is_dir();
+        if let Some(name) = entry.file_name().to_str().filter(|_| is_dir) {
+            names.push(name.to_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Create an empty profile.
+pub fn create_profile(dir: &DataDir, name: &str) -> Result<()> {
+    check_name(name)?;
+    let folder = dir.profile_folder(name);
+    if folder.exists() {
+        return Err(HistoryError::ProfileExists {
+            name: name.to_owned(),
+        });
+    }
+    create_dir_all(&folder).map_err(HistoryError::io(&folder))
+}
+
+/// Rename a profile along with the player name of its wins.
+pub fn rename_profile(dir: &DataDir, from: &str, to: &str) -> Result<()> {
+    let source = existing_profile(dir, from)?;
+    check_name(to)?;
+    let target = dir.profile_folder(to);
+    if target.exists() {
+        return Err(HistoryError::ProfileExists {
+            name: to.to_owned(),
+        });
+    }
+    rename(&source, &target).map_err(HistoryError::io(&source))?;
+    let renamed = dir.clone().with_profile(Some(to))?;
+    if load_wins(&renamed)?.wins.is_empty() {
+        return Ok(());
+    }
+    update_versioned(&renamed.file(SAVE_FILE), |history: &mut WinHistory| {
+        for win in &mut history.wins {
+            win.player = Some(to.to_owned());
+        }
+    })
+}
+
+/// Delete a profile with all its files.
+pub fn delete_profile(dir: &DataDir, name: &str) -> Result<()> {
+    let folder = existing_profile(dir, name)?;
+    remove_dir_all(&folder).map_err(HistoryError::io(&folder))
+}
+
+/// Wins of the anonymous profile and every named profile in one history,
+/// keeping the best [TOP_WINS](super::TOP_WINS) of every leaderboard.
+pub fn hall_of_fame(dir: &DataDir) -> Result<WinHistory> {
+    let mut history = load_wins(&dir.clone().with_profile(None)?)?;
+    for name in profiles(dir)? {
+        let wins = load_wins(&dir.clone().with_profile(Some(&name))?)?.wins;
+        history.merge(wins);
+    }
+    Ok(history)
+}
+
+/// Folder of a profile that exists.
+fn existing_profile(dir: &DataDir, name: &str) -> Result<PathBuf> {
+    check_name(name)?;
+    let folder = dir.profile_folder(name);
+    if folder.is_dir() {
+        Ok(folder)
+    } else {
+        Err(HistoryError::UnknownProfile {
+            name: name.to_owned(),
+        })
+    }
+}
+
+/// Profile names are used as folder names, they can't be empty, padded,
+/// contain path separators or control characters or refer to a parent
+/// folder.
+pub(super) fn check_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.trim() == name
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+        && !name.chars().any(char::is_control);
+    if valid {
+        Ok(())
+    } else {
+        Err(HistoryError::ProfileName {
+            name: name.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        history::save_win,
+        model::{Board, BoardConfig, GameState},
+    };
+    use tempfile::tempdir;
+
+    fn win_game(dir: &DataDir) {
+        let mut board = Board::with_mines(BoardConfig::default(), [(1, 1).try_into().unwrap()]);
+        board.open_cell((9, 9).try_into().unwrap()).unwrap();
+        assert_eq!(board.state(), &GameState::Win);
+        save_win(dir, &board).unwrap();
+    }
+
+    #[test]
+    fn test_profiles() {
+        let folder = tempdir().unwrap();
+        let dir = DataDir::new(folder.path());
+        assert_eq!(profiles(&dir).unwrap(), Vec::<String>::new());
+        create_profile(&dir, "Ada").unwrap();
+        create_profile(&dir, "Grace").unwrap();
+        assert!(matches!(
+            create_profile(&dir, "Ada"),
+            Err(HistoryError::ProfileExists { .. })
+        ));
+        for name in ["", " Ada", "..", "a/b"] {
+            assert!(matches!(
+                create_profile(&dir, name),
+                Err(HistoryError::ProfileName { .. })
+            ));
+        }
+        assert_eq!(profiles(&dir).unwrap(), ["Ada", "Grace"]);
+
+        let ada = dir.clone().with_profile(Some("Ada")).unwrap();
+        win_game(&ada);
+        win_game(&dir);
+        assert_eq!(
+            load_wins(&ada).unwrap().wins[0].player.as_deref(),
+            Some("Ada")
+        );
+        assert_eq!(load_wins(&dir).unwrap().wins[0].player, None);
+        let preferences = Preferences {
+            difficulty: Difficulty::Expert,
+            generation: GenerationMode::NoGuess,
+            question_marks: true,
+        };
+        save_preferences(&ada, &preferences).unwrap();
+        assert_eq!(load_preferences(&ada).unwrap(), Some(preferences));
+        assert_eq!(load_preferences(&dir).unwrap(), None);
+
+        rename_profile(&dir, "Ada", "Lovelace").unwrap();
+        assert_eq!(profiles(&dir).unwrap(), ["Grace", "Lovelace"]);
+        let lovelace = dir.clone().with_profile(Some("Lovelace")).unwrap();
+        assert_eq!(
+            load_wins(&lovelace).unwrap().wins[0].player.as_deref(),
+            Some("Lovelace")
+        );
+        assert_eq!(load_preferences(&lovelace).unwrap(), Some(preferences));
+
+        let players = hall_of_fame(&dir)
+            .unwrap()
+            .wins
+            .into_iter()
+            .map(|win| win.player)
+            .collect::<Vec<_>>();
+        assert_eq!(players.len(), 2);
+        assert!(players.contains(&Some("Lovelace".to_owned())));
+
+        delete_profile(&dir, "Lovelace").unwrap();
+        assert!(matches!(
+            delete_profile(&dir, "Lovelace"),
+            Err(HistoryError::UnknownProfile { .. })
+        ));
+        assert_eq!(profiles(&dir).unwrap(), ["Grace"]);
+    }
+}
@@ -0,0 +1,56 @@
+//! Settings shared by every frontend.
+use super::{
+    location::config_dir, read_versioned, update_versioned, write_versioned, DataDir, Result,
+    Versioned,
+};
+use rmp_serde::{decode, from_slice};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const SETTINGS_FILE: &str = "settings.bin";
+
+/// Settings chosen by the player.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Folder for the history files, the default location when not set.
+    #[serde(default)]
+    pub data_dir: Option<PathBuf>,
+    /// Active profile, the anonymous profile when not set.
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+impl Settings {
+    /// Folder for the history files chosen in the settings or the default
+    /// location, with the active profile.
+    pub fn data_dir(&self) -> Result<DataDir> {
+        let dir = match &self.data_dir {
+            Some(path) => DataDir::new(path),
+            None => DataDir::locate()?,
+        };
+        dir.with_profile(self.profile.as_deref())
+    }
+}
+
+impl Versioned for Settings {
+    const VERSION: u32 = 1;
+
+    fn migrate(_version: u32, bytes: &[u8]) -> Result<Self, decode::Error> {
+        // Settings were versioned from the start, there is no earlier format.
+        from_slice(bytes)
+    }
+}
+
+/// Load the settings. Default settings when none were saved.
+pub fn load_settings() -> Result<Settings> {
+    read_versioned(&config_dir()?.join(SETTINGS_FILE)).map(Option::unwrap_or_default)
+}
+
+pub fn save_settings(settings: &Settings) -> Result<()> {
+    write_versioned(&config_dir()?.join(SETTINGS_FILE), settings)
+}
+
+/// Change some of the saved settings, keeping the others.
+pub fn update_settings(update: impl FnOnce(&mut Settings)) -> Result<()> {
+    update_versioned(&config_dir()?.join(SETTINGS_FILE), update)
+}
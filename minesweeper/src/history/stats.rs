@@ -0,0 +1,372 @@
+//! Lifetime statistics of every finished game.
+use super::{read_versioned, update_versioned, DataDir, Leaderboard, Result, Versioned};
+use crate::clock::{deserialize_optional_time, deserialize_time};
+use crate::model::{Board, BoardConfig, GameState, Metrics};
+use rmp_serde::{decode, from_slice};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub(super) const STATS_FILE: &str = "lifetime.bin";
+
+/// How a game ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    /// Left unfinished for another game or on exit without saving it.
+    Abandoned,
+}
+
+impl Outcome {
+    /// Outcome of the game on the board, `None` when no move was made.
+    pub fn of(board: &Board) -> Option<Self> {
+        match board.state() {
+            GameState::New => None,
+            GameState::Active => Some(Outcome::Abandoned),
+            GameState::Win => Some(Outcome::Win),
+            GameState::Loss => Some(Outcome::Loss),
+        }
+    }
+}
+
+/// Totals of the games played on one board configuration.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
+pub struct GameStats {
+    pub played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub abandoned: u32,
+    /// Wins in a row up to the last game.
+    pub current_streak: u32,
+    pub best_streak: u32,
+    /// Time taken by all wins.
+    #[serde(deserialize_with = "deserialize_time")]
+    pub win_time: Duration,
+    #[serde(deserialize_with = "deserialize_optional_time")]
+    pub best_time: Option<Duration>,
+    /// 3BV cleared in finished games.
+    #[serde(default)]
+    pub bbbv: u64,
+    /// Clicks made in finished games.
+    #[serde(default)]
+    pub clicks: u64,
+}
+
+impl GameStats {
+    /// Percentage of games played that were won.
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            100.0 * self.wins as f64 / self.played as f64
+        }
+    }
+
+    /// Average time taken by a win.
+    pub fn average_time(&self) -> Option<Duration> {
+        (self.wins > 0).then(|| self.win_time / self.wins)
+    }
+
+    /// Percentage of 3BV cleared per click over all finished games.
+    pub fn efficiency(&self) -> Option<f64> {
+        (self.clicks > 0).then(|| 100. * self.bbbv as f64 / self.clicks as f64)
+    }
+
+    /// Count the games of other statistics of the same board. The order of
+    /// the games is unknown, so the current streak is kept.
+    fn merge(&mut self, other: &GameStats) {
+        self.played += other.played;
+        self.wins += other.wins;
+        self.losses += other.losses;
+        self.abandoned += other.abandoned;
+        self.best_streak = self.best_streak.max(other.best_streak);
+        self.win_time += other.win_time;
+        self.best_time = match (self.best_time, other.best_time) {
+            (Some(best), Some(other)) => Some(best.min(other)),
+            (best, other) => best.or(other),
+        };
+        self.bbbv += other.bbbv;
+        self.clicks += other.clicks;
+    }
+
+    fn add(&mut self, outcome: Outcome, duration: Duration, metrics: Option<Metrics>) {
+        self.played += 1;
+        if let Some(metrics) = metrics {
+            self.bbbv += u64::from(metrics.solved_bbbv);
+            self.clicks += u64::from(metrics.clicks.total());
+        }
+        match outcome {
+            Outcome::Win => {
+                self.wins += 1;
+                self.current_streak += 1;
+                self.best_streak = self.best_streak.max(self.current_streak);
+                self.win_time += duration;
+                self.best_time = Some(self.best_time.map_or(duration, |best| best.min(duration)));
+            }
+            Outcome::Loss => {
+                self.losses += 1;
+                self.current_streak = 0;
+            }
+            Outcome::Abandoned => {
+                self.abandoned += 1;
+                self.current_streak = 0;
+            }
+        }
+    }
+}
+
+/// Lifetime statistics of every board configuration played.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Statistics {
+    /// Games of every board, played with these statistics or imported.
+    pub boards: Vec<(BoardConfig, GameStats)>,
+    /// Random id of the machine or profile playing the games, zero until the
+    /// first game or import.
+    #[serde(default)]
+    pub(crate) source: u64,
+    /// Games played with these statistics, only kept apart from the boards
+    /// while other sources are imported.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) own: Vec<(BoardConfig, GameStats)>,
+    /// Latest statistics imported from each other source.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) imports: Vec<Import>,
+}
+
+/// Games of a source counted in the statistics of another.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub(crate) struct Import {
+    pub(super) source: u64,
+    pub(super) boards: Vec<(BoardConfig, GameStats)>,
+}
+
+impl Import {
+    fn played(&self) -> u64 {
+        self.boards
+            .iter()
+            .map(|(_, stats)| u64::from(stats.played))
+            .sum()
+    }
+}
+
+impl Statistics {
+    /// Statistics of the board configuration, practice or not.
+    pub fn of(&self, config: &BoardConfig) -> Option<&GameStats> {
+        let key = config.with_practice(false);
+        self.boards
+            .iter()
+            .find(|(config, _)| *config == key)
+            .map(|(_, stats)| stats)
+    }
+
+    /// Count a game on the board configuration.
+    fn add(
+        &mut self,
+        config: &BoardConfig,
+        outcome: Outcome,
+        duration: Duration,
+        metrics: Option<Metrics>,
+    ) {
+        self.identify();
+        let key = config.with_practice(false);
+        if !self.imports.is_empty() {
+            stats_of(&mut self.own, key).add(outcome, duration, metrics);
+        }
+        stats_of(&mut self.boards, key).add(outcome, duration, metrics);
+        self.sort();
+    }
+
+    /// Count the games of other statistics. Each source is counted with the
+    /// latest statistics seen from it, so importing a later export of the
+    /// same source replaces its games instead of adding them again. Exports
+    /// of these statistics are skipped. Statistics imported by the other
+    /// source are counted with their own source. Returns the number of
+    /// boards counted.
+    pub fn merge(&mut self, other: Statistics) -> usize {
+        self.identify();
+        if self.imports.is_empty() {
+            self.own = self.boards.clone();
+        }
+        let own = Import {
+            source: other.source,
+            boards: if other.imports.is_empty() {
+                other.boards
+            } else {
+                other.own
+            },
+        };
+        let mut taken = 0;
+        for import in other.imports.into_iter().chain([own]) {
+            if import.source == self.source {
+                continue;
+            }
+            let boards = import.boards.len();
+            match self
+                .imports
+                .iter_mut()
+                .find(|known| known.source == import.source)
+            {
+                Some(known) if known.played() >= import.played() => continue,
+                Some(known) => *known = import,
+                None => self.imports.push(import),
+            }
+            taken += boards;
+        }
+        if self.imports.is_empty() {
+            self.own.clear();
+        } else {
+            self.count();
+        }
+        taken
+    }
+
+    /// Count the own games and the imported games of every board again.
+    fn count(&mut self) {
+        let mut boards: Vec<(BoardConfig, GameStats)> = vec![];
+        let imported = self.imports.iter().flat_map(|import| &import.boards);
+        for (config, stats) in self.own.iter().chain(imported) {
+            let key = config.with_practice(false);
+            match boards.iter_mut().find(|(config, _)| *config == key) {
+                Some((_, known)) => known.merge(stats),
+                None => boards.push((key, stats.clone())),
+            }
+        }
+        self.boards = boards;
+        self.sort();
+    }
+
+    /// Give statistics without a source a random one.
+    fn identify(&mut self) {
+        while self.source == 0 {
+            self.source = rand::random();
+        }
+    }
+
+    /// Order the boards like their leaderboards.
+    fn sort(&mut self) {
+        self.boards
+            .sort_by_key(|(config, _)| Leaderboard(Some(*config)));
+    }
+}
+
+/// Statistics of the board, added when missing.
+fn stats_of(boards: &mut Vec<(BoardConfig, GameStats)>, key: BoardConfig) -> &mut GameStats {
+    let index = match boards.iter().position(|(config, _)| *config == key) {
+        Some(index) => index,
+        None => {
+            boards.push((key, GameStats::default()));
+            boards.len() - 1
+        }
+    };
+    &mut boards[index].1
+}
+
+/// Version 1 wraps the statistics in a versioned envelope.
+impl Versioned for Statistics {
+    const VERSION: u32 = 1;
+
+    fn migrate(_version: u32, bytes: &[u8]) -> Result<Self, decode::Error> {
+        // The unversioned statistics are the only earlier format.
+        from_slice(bytes)
+    }
+}
+
+/// Load the lifetime statistics. Empty when no game was recorded.
+pub fn load_statistics(dir: &DataDir) -> Result<Statistics> {
+    let path = dir.file(STATS_FILE);
+    match read_versioned::<Statistics>(&path)? {
+        // Statistics recorded before sources keep the source given to them,
+        // so all their exports have the same one.
+        Some(statistics) if statistics.source == 0 && !statistics.boards.is_empty() => {
+            let mut loaded = statistics;
+            update_versioned(&path, |statistics: &mut Statistics| {
+                statistics.identify();
+                loaded = statistics.clone();
+            })?;
+            Ok(loaded)
+        }
+        statistics => Ok(statistics.unwrap_or_default()),
+    }
+}
+
+/// Count the game on the board in the lifetime statistics. Games without a
+/// move and practice games are not counted, an active game is counted as
+/// abandoned. Corrupt statistics are backed up and counting starts again.
+pub fn record_game(dir: &DataDir, board: &Board) -> Result<()> {
+    let Some(outcome) = Outcome::of(board) else {
+        return Ok(());
+    };
+    if board.config().practice() {
+        return Ok(());
+    }
+    update_versioned(&dir.file(STATS_FILE), |statistics: &mut Statistics| {
+        statistics.add(board.config(), outcome, board.elapsed(), board.metrics())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{CellState, Difficulty};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_statistics() {
+        let beginner = BoardConfig::default();
+        let expert = BoardConfig::preset(Difficulty::Expert).unwrap();
+        let mut statistics = Statistics::default();
+        for (outcome, duration) in [
+            (Outcome::Win, 30_250),
+            (Outcome::Win, 20_500),
+            (Outcome::Loss, 5_000),
+            (Outcome::Win, 40_000),
+            (Outcome::Abandoned, 2_000),
+        ] {
+            statistics.add(&beginner, outcome, Duration::from_millis(duration), None);
+        }
+        statistics.add(&expert, Outcome::Win, Duration::from_secs(300), None);
+
+        let stats = statistics.of(&beginner).unwrap();
+        assert_eq!((stats.played, stats.wins, stats.losses), (5, 3, 1));
+        assert_eq!(stats.abandoned, 1);
+        assert_eq!((stats.current_streak, stats.best_streak), (0, 2));
+        assert_eq!(stats.best_time, Some(Duration::from_millis(20_500)));
+        assert_eq!(stats.average_time(), Some(Duration::from_millis(30_250)));
+        assert_eq!(stats.win_rate(), 60.0);
+        // Practice boards share the statistics of their board.
+        assert_eq!(statistics.of(&expert.with_practice(true)).unwrap().wins, 1);
+        assert_eq!(statistics.boards[0].0, beginner);
+        assert_eq!(stats.efficiency(), None);
+
+        let mut lost = Board::with_seed(expert, 3);
+        lost.open_cell((5, 5).try_into().unwrap()).unwrap();
+        let mine = lost
+            .positions()
+            .find(|(_, cell)| matches!(cell.state, CellState::Closed { mined: true, .. }))
+            .map(|(pos, _)| pos)
+            .unwrap();
+        lost.open_cell(mine).unwrap();
+        let metrics = lost.metrics().unwrap();
+        statistics.add(&expert, Outcome::Loss, lost.elapsed(), Some(metrics));
+        let stats = statistics.of(&expert).unwrap();
+        assert_eq!(stats.clicks, 2);
+        assert_eq!(stats.bbbv, u64::from(metrics.solved_bbbv));
+        assert_eq!(stats.efficiency(), Some(metrics.efficiency));
+    }
+
+    #[test]
+    fn test_record_game() {
+        let folder = tempdir().unwrap();
+        let dir = DataDir::new(folder.path());
+        let mut board = Board::with_seed(BoardConfig::default(), 7);
+        assert!(load_statistics(&dir).unwrap().boards.is_empty());
+        record_game(&dir, &board).unwrap();
+        board.open_cell((5, 5).try_into().unwrap()).unwrap();
+        record_game(&dir, &board).unwrap();
+
+        let stats = load_statistics(&dir).unwrap();
+        let stats = stats.of(board.config()).unwrap();
+        assert_eq!(stats.played, 1);
+        assert_eq!(stats.current_streak, 0);
+    }
+}
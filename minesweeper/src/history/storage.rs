@@ -0,0 +1,307 @@
+//! Versioned MessagePack files written atomically, with corrupt files backed
+//! up instead of overwritten.
+use crate::model::{ConfigError, GameState};
+use chrono::Local;
+use rmp_serde::{decode, encode, from_slice, to_vec_named};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    fs::{create_dir_all, rename, File},
+    io::{self, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Failure to read or write the history files.
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("Could not find the home folder, $HOME is not set")]
+    Home,
+    #[error("Could not access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Could not read {}: {source}", path.display())]
+    Decode {
+        path: PathBuf,
+        #[source]
+        source: decode::Error,
+    },
+    #[error("Could not write {}: {source}", path.display())]
+    Encode {
+        path: PathBuf,
+        #[source]
+        source: encode::Error,
+    },
+    #[error(
+        "{} is corrupt and was moved to {}: {source}",
+        path.display(),
+        backup.display()
+    )]
+    Corrupt {
+        path: PathBuf,
+        backup: PathBuf,
+        #[source]
+        source: decode::Error,
+    },
+    #[error(
+        "{} has version {version}, which is newer than this version supports",
+        path.display()
+    )]
+    UnsupportedVersion { path: PathBuf, version: u32 },
+    #[error("Could not convert {} to or from JSON: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Could not convert {} to or from CSV: {source}", path.display())]
+    Csv {
+        path: PathBuf,
+        #[source]
+        source: csv::Error,
+    },
+    #[error("Unknown format of {}, expected a .json or .csv file", path.display())]
+    Format { path: PathBuf },
+    #[error("Invalid profile name {name:?}")]
+    ProfileName { name: String },
+    #[error("A profile named {name} already exists")]
+    ProfileExists { name: String },
+    #[error("There is no profile named {name}")]
+    UnknownProfile { name: String },
+    #[error("Invalid board in {}: {source}", path.display())]
+    Board {
+        path: PathBuf,
+        #[source]
+        source: ConfigError,
+    },
+    #[error("Only a won game is kept as a win, the game is {state:?}")]
+    NotWon { state: GameState },
+}
+
+impl HistoryError {
+    pub(super) fn io(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    fn decode(path: &Path) -> impl FnOnce(decode::Error) -> Self + '_ {
+        move |source| Self::Decode {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+/// Data stored with its format version so older files can be migrated.
+pub(super) trait Versioned: Serialize + DeserializeOwned {
+    /// Version written by this build.
+    const VERSION: u32;
+
+    /// Read data written by an earlier version. Version 0 is the format
+    /// written before files had a version, the bytes are the whole file.
+    fn migrate(version: u32, bytes: &[u8]) -> Result<Self, decode::Error>;
+}
+
+/// Versioned file contents.
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u32,
+    data: T,
+}
+
+/// Version of a file, missing for files written before versions.
+#[derive(Deserialize)]
+struct Header {
+    version: Option<u32>,
+}
+
+/// Read a versioned file, migrating data written by earlier versions.
+/// Returns `None` when there is no file. A file that can't be read is moved
+/// to a backup so it is not overwritten by the next write.
+pub(super) fn read_versioned<T: Versioned>(path: &Path) -> Result<Option<T>, HistoryError> {
+    let Some(bytes) = read_bytes(path)? else {
+        return Ok(None);
+    };
+    let version = match from_slice::<Header>(&bytes) {
+        Ok(header) => header.version.unwrap_or(0),
+        Err(source) => return Err(back_up(path, source)),
+    };
+    let data = match version {
+        version if version > T::VERSION => {
+            return Err(HistoryError::UnsupportedVersion {
+                path: path.to_path_buf(),
+                version,
+            })
+        }
+        version if version == T::VERSION => {
+            from_slice::<Envelope<T>>(&bytes).map(|envelope| envelope.data)
+        }
+        version => T::migrate(version, &bytes),
+    };
+    data.map(Some).map_err(|source| back_up(path, source))
+}
+
+/// Write data in a versioned file.
+pub(super) fn write_versioned<T: Versioned>(path: &Path, data: &T) -> Result<(), HistoryError> {
+    let envelope = Envelope {
+        version: T::VERSION,
+        data,
+    };
+    write_atomic(path, &envelope)
+}
+
+/// Update the data in a versioned file. A corrupt file is backed up and
+/// the update applies to empty data, returning the error once written.
+pub(super) fn update_versioned<T: Versioned + Default>(
+    path: &Path,
+    update: impl FnOnce(&mut T),
+) -> Result<(), HistoryError> {
+    let (mut data, corrupt) = match read_versioned(path) {
+        Ok(data) => (data.unwrap_or_default(), None),
+        Err(err @ HistoryError::Corrupt { .. }) => (T::default(), Some(err)),
+        Err(err) => return Err(err),
+    };
+    update(&mut data);
+    write_versioned(path, &data)?;
+    corrupt.map_or(Ok(()), Err)
+}
+
+/// Read an unversioned file. Returns `None` when there is no file.
+pub(super) fn read<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, HistoryError> {
+    read_bytes(path)?
+        .map(|bytes| from_slice(&bytes).map_err(HistoryError::decode(path)))
+        .transpose()
+}
+
+/// Write a file by writing a temporary file next to it and renaming it, so
+/// a failed write leaves the previous file intact.
+pub(super) fn write_atomic<T: Serialize + ?Sized>(
+    path: &Path,
+    data: &T,
+) -> Result<(), HistoryError> {
+    let bytes = to_vec_named(data).map_err(|source| HistoryError::Encode {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    write_bytes(path, &bytes)
+}
+
+/// Write the bytes to a file atomically like [write_atomic].
+pub(super) fn write_bytes(path: &Path, bytes: &[u8]) -> Result<(), HistoryError> {
+    if let Some(folder) = path.parent() {
+        create_dir_all(folder).map_err(HistoryError::io(path))?;
+    }
+    let temp = with_suffix(path, "tmp");
+    let mut file = File::create(&temp).map_err(HistoryError::io(&temp))?;
+    file.write_all(bytes)
+        .and_then(|()| file.sync_all())
+        .map_err(HistoryError::io(&temp))?;
+    rename(&temp, path).map_err(HistoryError::io(path))
+}
+
+pub(super) fn read_bytes(path: &Path) -> Result<Option<Vec<u8>>, HistoryError> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(HistoryError::io(path)(err)),
+    };
+    let mut bytes = vec![];
+    file.read_to_end(&mut bytes)
+        .map_err(HistoryError::io(path))?;
+    Ok(Some(bytes))
+}
+
+/// Move a corrupt file to a dated backup next to it.
+fn back_up(path: &Path, source: decode::Error) -> HistoryError {
+    let date = Local::now().format("%Y%m%d%H%M%S");
+    let backup = with_suffix(path, &format!("{date}.corrupt"));
+    match rename(path, &backup) {
+        Ok(()) => HistoryError::Corrupt {
+            path: path.to_path_buf(),
+            backup,
+            source,
+        },
+        Err(err) => HistoryError::io(path)(err),
+    }
+}
+
+/// Path with a suffix added to the file name.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(suffix);
+    path.into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::{read as read_file, write};
+    use tempfile::tempdir;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Names {
+        names: Vec<String>,
+    }
+
+    /// The format before versions, with a single name.
+    #[derive(Serialize, Deserialize)]
+    struct Name {
+        name: String,
+    }
+
+    impl Versioned for Names {
+        const VERSION: u32 = 1;
+
+        fn migrate(version: u32, bytes: &[u8]) -> Result<Self, decode::Error> {
+            assert_eq!(version, 0);
+            from_slice::<Name>(bytes).map(|old| Names {
+                names: vec![old.name],
+            })
+        }
+    }
+
+    #[test]
+    fn test_versioned() {
+        let folder = tempdir().unwrap();
+        let path = folder.path().join("names.bin");
+        assert_eq!(read_versioned::<Names>(&path).unwrap(), None);
+
+        let name = Name {
+            name: "mines".into(),
+        };
+        write_atomic(&path, &name).unwrap();
+        let names = read_versioned::<Names>(&path).unwrap().unwrap();
+        assert_eq!(names.names, vec!["mines".to_owned()]);
+
+        write_versioned(&path, &names).unwrap();
+        assert_eq!(read_versioned(&path).unwrap(), Some(names));
+
+        let newer = Envelope {
+            version: 2,
+            data: name,
+        };
+        write_atomic(&path, &newer).unwrap();
+        assert!(matches!(
+            read_versioned::<Names>(&path),
+            Err(HistoryError::UnsupportedVersion { version: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_corrupt_backup() {
+        let folder = tempdir().unwrap();
+        let path = folder.path().join("corrupt.bin");
+        write(&path, b"not a history").unwrap();
+
+        let Err(HistoryError::Corrupt { backup, .. }) = read_versioned::<Names>(&path) else {
+            panic!("Corrupt file was read");
+        };
+        assert!(!path.exists());
+        assert_eq!(read_file(backup).unwrap(), b"not a history");
+    }
+}
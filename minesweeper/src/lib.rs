@@ -1,18 +1,25 @@
-use crate::model::Pos;
-use std::{
-    num::{NonZeroU8, ParseIntError},
-    str::FromStr,
-};
+use crate::model::{Coord, Pos};
+use std::{num::ParseIntError, str::FromStr};
 use thiserror::Error;
 
+pub mod clock;
 pub mod history;
 pub mod model;
+pub mod protocol;
+pub mod replay;
+pub mod solver;
 
 /// User command.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Command {
     Open(Pos),
     Flag(Pos),
+    Chord(Pos),
+    Hint,
+    Probabilities,
+    Save,
+    Undo,
+    Redo,
     Quit,
 }
 
@@ -22,8 +29,14 @@ impl FromStr for Command {
         let parts = s.split(' ').collect::<Vec<_>>();
         match parts.as_slice() {
             ["q"] => Ok(Command::Quit),
+            ["h"] => Ok(Command::Hint),
+            ["p"] => Ok(Command::Probabilities),
+            ["s"] => Ok(Command::Save),
+            ["u"] => Ok(Command::Undo),
+            ["r"] => Ok(Command::Redo),
             &["o", x, y] => parse_coords(x, y).map(Command::Open),
             &["f", x, y] => parse_coords(x, y).map(Command::Flag),
+            &["c", x, y] => parse_coords(x, y).map(Command::Chord),
             _ => Err(InvalidCommand::Command(s.to_owned())),
         }
     }
@@ -31,7 +44,7 @@ impl FromStr for Command {
 
 /// Parse coordinates provided by user.
 fn parse_coords(x: &str, y: &str) -> Result<Pos, InvalidCommand> {
-    x.parse::<NonZeroU8>()
+    x.parse::<Coord>()
         .and_then(|x| y.parse().map(|y| (x, y).into()))
         .map(Ok)?
 }
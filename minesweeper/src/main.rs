@@ -1,20 +1,216 @@
 use clap::Parser;
 use minesweeper::{
-    model::{Board, GameState},
+    history::{
+        export_statistics, export_wins, import_statistics, import_wins, load_game, load_replay,
+        load_settings, load_statistics, load_wins, record_game, remove_saved_game, save_game,
+        save_replay, DataDir, Format, HistoryError, Settings,
+    },
+    model::{
+        Board, BoardConfig, ConfigError, Coord, Difficulty, GameState, GenerationMode, Mines,
+        TopologyMode, WinRule,
+    },
+    protocol::Session,
+    replay::{Player, Replay},
+    solver::probabilities,
     Command, InvalidCommand,
 };
 use std::{
     io::{stdin, stdout, Write},
-    num::NonZeroU8,
+    path::{Path, PathBuf},
+    process::exit,
+    time::Duration,
 };
 
+#[cfg(feature = "tui")]
+mod tui;
+
 /// Command line arguments.
 #[derive(Parser)]
 pub struct ProgramArgs {
-    #[clap(short, help = "Number of rows", action, default_value = "10")]
-    pub rows: NonZeroU8,
-    #[clap(short, help = "Number of columns", action, default_value = "10")]
-    pub columns: NonZeroU8,
+    #[clap(
+        short,
+        long,
+        help = "Difficulty level",
+        value_enum,
+        default_value = "beginner"
+    )]
+    pub difficulty: Difficulty,
+    #[clap(
+        short,
+        help = "Number of rows for a custom board",
+        requires = "columns"
+    )]
+    pub rows: Option<Coord>,
+    #[clap(
+        short,
+        help = "Number of columns for a custom board",
+        requires = "rows"
+    )]
+    pub columns: Option<Coord>,
+    #[clap(
+        short,
+        help = "Number of mines for a custom board",
+        requires = "rows",
+        conflicts_with = "density"
+    )]
+    pub mines: Option<usize>,
+    #[clap(
+        long,
+        help = "Ratio of mined cells for a custom board",
+        requires = "rows",
+        default_value = "0.1"
+    )]
+    pub density: f64,
+    #[clap(short, long, help = "Seed for a reproducible mine layout")]
+    pub seed: Option<u64>,
+    #[clap(long, help = "Generate a board that can be solved without guessing")]
+    pub no_guess: bool,
+    #[clap(long, help = "Allow undo and keep wins off the leaderboard")]
+    pub practice: bool,
+    #[clap(
+        long,
+        help = "Mark a flagged cell with a question mark when it is flagged again"
+    )]
+    pub question_marks: bool,
+    #[clap(
+        long,
+        value_enum,
+        default_value = "grid",
+        help = "Cells counted as neighbours of a cell"
+    )]
+    pub topology: TopologyMode,
+    #[clap(
+        long,
+        value_enum,
+        default_value = "classic",
+        help = "When the game is won"
+    )]
+    pub win_rule: WinRule,
+    #[clap(long, help = "Resume the saved game", conflicts_with = "seed")]
+    pub load: bool,
+    #[cfg(feature = "tui")]
+    #[clap(
+        long,
+        conflicts_with = "protocol",
+        help = "Play in a full screen terminal interface"
+    )]
+    pub tui: bool,
+    #[clap(
+        long,
+        value_enum,
+        conflicts_with = "load",
+        help = "Read commands and write responses in a machine readable format"
+    )]
+    pub protocol: Option<Protocol>,
+    #[clap(
+        long,
+        global = true,
+        help = "Folder for the history and saved games [default: from the settings or $XDG_DATA_HOME/minesweeper]"
+    )]
+    pub data_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        global = true,
+        help = "Profile keeping the wins, statistics and saved game [default: the active profile in the settings]"
+    )]
+    pub profile: Option<String>,
+    #[clap(subcommand)]
+    pub command: Option<ProgramCommand>,
+}
+
+/// Commands run instead of playing a game.
+#[derive(clap::Subcommand)]
+pub enum ProgramCommand {
+    /// Print the board after every move of a replay and verify it.
+    Replay {
+        #[clap(help = "Replay file [default: the last finished game]")]
+        file: Option<PathBuf>,
+    },
+    /// Print the lifetime statistics of every board played.
+    Stats,
+    /// Write the win history or statistics to a JSON or CSV file.
+    Export {
+        #[clap(value_enum)]
+        records: Records,
+        file: PathBuf,
+        #[clap(
+            long,
+            value_enum,
+            help = "File format [default: from the file extension]"
+        )]
+        format: Option<Format>,
+    },
+    /// Merge wins or statistics from a JSON or CSV file, skipping records
+    /// that are already kept.
+    Import {
+        #[clap(value_enum)]
+        records: Records,
+        file: PathBuf,
+        #[clap(
+            long,
+            value_enum,
+            help = "File format [default: from the file extension]"
+        )]
+        format: Option<Format>,
+    },
+}
+
+/// Records that can be exported and imported.
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum Records {
+    /// The win history of every leaderboard.
+    Wins,
+    /// The lifetime statistics.
+    Stats,
+}
+
+/// Machine readable formats of commands and responses.
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum Protocol {
+    /// One JSON object per line.
+    Json,
+}
+
+impl ProgramArgs {
+    /// Board configuration from a preset or custom dimensions.
+    fn board_config(&self) -> Result<BoardConfig, ConfigError> {
+        let generation = if self.no_guess {
+            GenerationMode::NoGuess
+        } else {
+            GenerationMode::Random
+        };
+        let config = match (self.columns, self.rows) {
+            (Some(columns), Some(rows)) => BoardConfig::custom(
+                columns,
+                rows,
+                self.mines
+                    .map(Mines::Count)
+                    .unwrap_or(Mines::Density(self.density)),
+            ),
+            _ => Ok(BoardConfig::preset(self.difficulty).unwrap_or_default()),
+        };
+        config.map(|config| {
+            config
+                .with_generation(generation)
+                .with_practice(self.practice)
+                .with_topology(self.topology)
+                .with_win_rule(self.win_rule)
+        })
+    }
+
+    /// Data folder and profile given on the command line or chosen in the
+    /// settings.
+    fn data_dir(&self) -> Result<DataDir, HistoryError> {
+        let settings = match (&self.data_dir, &self.profile) {
+            (Some(_), Some(_)) => Settings::default(),
+            _ => load_settings()?,
+        };
+        let dir = match &self.data_dir {
+            Some(path) => DataDir::new(path),
+            None => settings.data_dir()?,
+        };
+        dir.with_profile(self.profile.as_deref().or(settings.profile.as_deref()))
+    }
 }
 
 /// Parse user input.
@@ -24,45 +220,325 @@ fn parse_command() -> Result<Command, InvalidCommand> {
     input.trim().parse()
 }
 
+/// Print the mine probability in percent of every closed cell. Estimates
+/// are marked with `~`.
+fn print_probabilities(board: &Board) {
+    let probabilities = probabilities(board);
+    print!("   ");
+    for c in 1..=board.total_columns().get() {
+        print!("{c:<4}");
+    }
+    for (pos, cell) in board.positions() {
+        if pos.x.get() == 1 {
+            print!("\n{:<2} ", pos.y);
+        }
+        match probabilities.get(&pos) {
+            Some(p) => {
+                let mark = if p.exact { "" } else { "~" };
+                print!("{:<4}", format!("{mark}{}", (p.value * 100.).round()))
+            }
+            None => print!("{:<4}", cell.to_string()),
+        }
+    }
+    println!("\n");
+}
+
+/// Print the board after every move of a replay and check the replay
+/// reaches its recorded outcome.
+fn print_replay(dir: &DataDir, file: Option<&Path>) {
+    let replay = load_replay(dir, file).unwrap_or_else(|err| {
+        eprintln!("Failed to load replay: {err}");
+        exit(1);
+    });
+    let mut player = Player::new(replay);
+    println!("{}", player.board());
+    while !player.is_finished() {
+        player.step();
+        let timed = player.replay().actions[player.played() - 1];
+        println!(
+            "Move {}: {} at {:.1}s",
+            player.played(),
+            timed.action,
+            timed.at as f64 / 1000.
+        );
+        println!("{}", player.board());
+    }
+    let replay = player.replay();
+    match replay.verify() {
+        Ok(()) => println!(
+            "Verified {:?} in {:.3} seconds",
+            replay.outcome,
+            replay.duration.as_secs_f64()
+        ),
+        Err(err) => println!("Verification failed: {err}"),
+    }
+}
+
+/// Print the lifetime statistics of every board configuration played.
+fn print_statistics(dir: &DataDir) {
+    let statistics = load_statistics(dir).unwrap_or_else(|err| {
+        eprintln!("Failed to load statistics: {err}");
+        exit(1);
+    });
+    if statistics.boards.is_empty() {
+        println!("No games played");
+    }
+    for (config, stats) in &statistics.boards {
+        let seconds = |time: Option<Duration>| {
+            time.map_or("-".into(), |t| format!("{:.3}s", t.as_secs_f64()))
+        };
+        println!("{config}");
+        println!(
+            "  Played {}, won {} ({:.0}%), lost {}, abandoned {}",
+            stats.played,
+            stats.wins,
+            stats.win_rate(),
+            stats.losses,
+            stats.abandoned
+        );
+        println!(
+            "  Streak {}, best streak {}, best time {}, average time {}",
+            stats.current_streak,
+            stats.best_streak,
+            seconds(stats.best_time),
+            seconds(stats.average_time())
+        );
+        if let Some(efficiency) = stats.efficiency() {
+            println!(
+                "  3BV {}, clicks {}, efficiency {efficiency:.0}%",
+                stats.bbbv, stats.clicks
+            );
+        }
+    }
+}
+
+/// Write the win history or statistics to a file.
+fn export(dir: &DataDir, records: Records, file: &Path, format: Format) {
+    let exported = match records {
+        Records::Wins => load_wins(dir)
+            .and_then(|history| export_wins(&history, file, format).map(|()| history.wins.len())),
+        Records::Stats => load_statistics(dir).and_then(|statistics| {
+            export_statistics(&statistics, file, format).map(|()| statistics.boards.len())
+        }),
+    };
+    match (exported, records) {
+        (Ok(count), Records::Wins) => println!("Exported {count} wins to {}", file.display()),
+        (Ok(count), Records::Stats) => {
+            println!(
+                "Exported statistics of {count} boards to {}",
+                file.display()
+            )
+        }
+        (Err(err), _) => {
+            eprintln!("Failed to export: {err}");
+            exit(1);
+        }
+    }
+}
+
+/// Merge wins or statistics from a file.
+fn import(dir: &DataDir, records: Records, file: &Path, format: Format) {
+    let imported = match records {
+        Records::Wins => import_wins(dir, file, format),
+        Records::Stats => import_statistics(dir, file, format),
+    };
+    match (imported, records) {
+        (Ok(count), Records::Wins) => println!("Imported {count} new wins"),
+        (Ok(count), Records::Stats) => println!("Imported statistics of {count} boards"),
+        (Err(err), _) => {
+            eprintln!("Failed to import: {err}");
+            exit(1);
+        }
+    }
+}
+
 /// Main game loop. Draws the board and takes user input
-/// until win/loss or quit.
-fn game_loop(mut board: Board) {
+/// until win/loss or quit. The clock of a resumed game carries
+/// on from its saved time. A lost practice game can still be undone.
+/// The replay of a finished game is saved and every game is counted in the
+/// lifetime statistics, as abandoned when quit without saving.
+fn game_loop(dir: &DataDir, mut board: Board) {
+    board.resume_clock();
+    let mut saved = false;
+    let prompt = if board.config().practice() {
+        "(o, f, c, h, p, s, u, r, q): "
+    } else {
+        "(o, f, c, h, p, s, q): "
+    };
     loop {
         println!("{board}");
 
         match board.state() {
             GameState::Loss => {
                 println!("You Lose!");
-                break;
+                if !board.can_undo() {
+                    break;
+                }
             }
             GameState::Win => {
                 println!("You Win!");
+                println!("Time {:.3} seconds", board.elapsed().as_secs_f64());
+                if let Some(metrics) = board.metrics() {
+                    println!("{metrics}");
+                }
                 break;
             }
-            GameState::Active | GameState::New => {
-                print!("(o, f, q): ");
-                stdout().flush().unwrap();
-                match parse_command() {
-                    Ok(Command::Quit) => break,
-                    Ok(Command::Open(p)) => {
-                        board.open_cell(p);
-                    }
-                    Ok(Command::Flag(p)) => {
-                        board.flag_cell(p);
-                    }
-                    Err(e) => {
-                        eprintln!("Invalid command: {e}");
-                    }
+            GameState::Active | GameState::New => (),
+        }
+
+        print!("{prompt}");
+        stdout().flush().unwrap();
+        match parse_command() {
+            Ok(Command::Quit) => break,
+            Ok(Command::Open(p)) => {
+                if let Err(e) = board.open_cell(p) {
+                    eprintln!("Failed to generate board: {e}");
+                    break;
+                }
+            }
+            Ok(Command::Flag(p)) => {
+                board.flag_cell(p);
+            }
+            Ok(Command::Chord(p)) => {
+                board.chord(p);
+            }
+            Ok(Command::Hint) => match board.hint() {
+                Some(hint) => println!("Hint {}: {hint}", board.hints()),
+                None => println!("No hint available"),
+            },
+            Ok(Command::Probabilities) => print_probabilities(&board),
+            Ok(Command::Save) => match save_game(dir, &board) {
+                Ok(()) if *board.state() == GameState::Active => {
+                    saved = true;
+                    println!("Game saved");
+                }
+                Ok(()) => println!("Only a started game can be saved"),
+                Err(e) => eprintln!("Failed to save game: {e}"),
+            },
+            Ok(Command::Undo) => {
+                if let Err(e) = board.undo() {
+                    eprintln!("{e}");
                 }
             }
+            Ok(Command::Redo) => {
+                if let Err(e) = board.redo() {
+                    eprintln!("{e}");
+                }
+            }
+            Err(e) => {
+                eprintln!("Invalid command: {e}");
+            }
+        }
+    }
+
+    let finished = matches!(board.state(), GameState::Win | GameState::Loss);
+    if finished {
+        if let Err(e) = save_replay(dir, &Replay::new(&board)) {
+            eprintln!("Failed to save replay: {e}");
+        }
+    }
+    if finished || !saved {
+        if let Err(e) = record_game(dir, &board) {
+            eprintln!("Failed to save statistics: {e}");
+        }
+    }
+}
+
+/// Format given on the command line or from the file extension.
+fn file_format(file: &Path, format: Option<Format>) -> Format {
+    format
+        .map_or_else(|| Format::of(file), Ok)
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            exit(1);
+        })
+}
+
+/// Play games with the JSON line protocol until the input ends or the
+/// player quits. Protocol games are kept out of the history.
+fn protocol_loop(board: Board) {
+    let mut session = Session::new(board);
+    for line in stdin().lines() {
+        let line = line.unwrap_or_else(|err| {
+            eprintln!("Failed to read command: {err}");
+            exit(1);
+        });
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, quit) = session.handle_line(&line);
+        println!(
+            "{}",
+            serde_json::to_string(&response).expect("Serializable response")
+        );
+        if quit {
+            break;
+        }
+    }
+}
+
+/// Play the board in the terminal interface, over the protocol or at the
+/// prompt.
+fn play(dir: &DataDir, board: Board, args: &ProgramArgs) {
+    if args.protocol.is_some() {
+        return protocol_loop(board);
+    }
+    #[cfg(feature = "tui")]
+    if args.tui {
+        if let Err(err) = tui::run(dir, board) {
+            eprintln!("Failed to run the terminal interface: {err}");
+            exit(1);
         }
+        return;
     }
+    game_loop(dir, board)
 }
 
 /// Parse command line arguments and start game.
 fn main() {
-    let ProgramArgs { rows, columns } = ProgramArgs::parse();
-    game_loop(Board::new(columns, rows));
+    let args = ProgramArgs::parse();
+    let dir = args.data_dir().unwrap_or_else(|err| {
+        eprintln!("Failed to find the data folder: {err}");
+        exit(1);
+    });
+    match &args.command {
+        Some(ProgramCommand::Replay { file }) => return print_replay(&dir, file.as_deref()),
+        Some(ProgramCommand::Stats) => return print_statistics(&dir),
+        Some(ProgramCommand::Export {
+            records,
+            file,
+            format,
+        }) => return export(&dir, *records, file, file_format(file, *format)),
+        Some(ProgramCommand::Import {
+            records,
+            file,
+            format,
+        }) => return import(&dir, *records, file, file_format(file, *format)),
+        None => (),
+    }
+    let config = args.board_config().unwrap_or_else(|err| {
+        eprintln!("Invalid board: {err}");
+        exit(1);
+    });
+    if args.load {
+        match load_game(&dir) {
+            Ok(Some(saved)) => {
+                if let Err(err) = remove_saved_game(&dir) {
+                    eprintln!("Failed to remove saved game: {err}");
+                }
+                play(&dir, saved.board, &args);
+            }
+            Ok(None) => eprintln!("No saved game"),
+            Err(err) => eprintln!("Failed to load game: {err}"),
+        }
+        return;
+    }
+    let mut board = match args.seed {
+        Some(seed) => Board::with_seed(config, seed),
+        None => Board::new(config),
+    };
+    board.set_question_marks(args.question_marks);
+    play(&dir, board, &args);
 }
 
 #[cfg(test)]
@@ -74,12 +550,25 @@ mod test {
         assert_eq!(open, Command::Open((1, 1).try_into().unwrap()));
         let flag = "f 1 1".parse::<Command>().unwrap();
         assert_eq!(flag, Command::Flag((1, 1).try_into().unwrap()));
+        let chord = "c 2 3".parse::<Command>().unwrap();
+        assert_eq!(chord, Command::Chord((2, 3).try_into().unwrap()));
+        let hint = "h".parse::<Command>().unwrap();
+        assert_eq!(hint, Command::Hint);
+        let probabilities = "p".parse::<Command>().unwrap();
+        assert_eq!(probabilities, Command::Probabilities);
+        let save = "s".parse::<Command>().unwrap();
+        assert_eq!(save, Command::Save);
+        let undo = "u".parse::<Command>().unwrap();
+        assert_eq!(undo, Command::Undo);
+        let redo = "r".parse::<Command>().unwrap();
+        assert_eq!(redo, Command::Redo);
         let quit = "q".parse::<Command>().unwrap();
         assert_eq!(quit, Command::Quit);
         let invalid = "abc".parse::<Command>();
         assert!(matches!(invalid, Err(InvalidCommand::Command(s)) if s == "abc"));
-        let overflow = "o 260 2".parse::<Command>();
-        dbg!(&overflow);
+        let wide = "o 260 2".parse::<Command>().unwrap();
+        assert_eq!(wide, Command::Open((260, 2).try_into().unwrap()));
+        let overflow = "o 70000 2".parse::<Command>();
         assert!(matches!(overflow, Err(InvalidCommand::Dimension(_))));
     }
 }
@@ -1,17 +1,34 @@
 //! Game types and trait implementations.
+mod cells;
+mod config;
+mod events;
 mod game;
-
+mod metrics;
+mod moves;
+mod topology;
+
+pub use config::{BoardConfig, ConfigError, Difficulty, GenerationMode, Mines, WinRule, MAX_CELLS};
+pub use events::BoardEvent;
+pub use metrics::{Clicks, Metrics};
+pub use moves::{Action, Move, UndoError};
+pub use topology::{Grid, Hex, Knight, Neighbours, Topology, TopologyMode, Torus};
+
+use self::{cells::Cells, events::Subscribers};
+use crate::clock::{GameClock, TimeSource};
 use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, HashSet},
     fmt::{Display, Formatter},
-    num::{NonZeroU8, TryFromIntError},
+    num::{NonZeroU16, TryFromIntError},
+    sync::Arc,
+    time::Duration,
 };
+use thiserror::Error;
 
 /// Board cell.
-#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Cell {
     pub state: CellState,
@@ -24,11 +41,15 @@ impl Cell {
     }
 }
 
+/// Column or row of a cell, counted from one. Saved as an integer, so
+/// positions and boards saved with narrower coordinates still load.
+pub type Coord = NonZeroU16;
+
 /// Cell position on the board.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Pos {
-    pub x: NonZeroU8,
-    pub y: NonZeroU8,
+    pub x: Coord,
+    pub y: Coord,
 }
 
 impl PartialOrd for Pos {
@@ -43,9 +64,9 @@ impl Ord for Pos {
     }
 }
 
-impl TryFrom<(u8, u8)> for Pos {
+impl TryFrom<(u16, u16)> for Pos {
     type Error = TryFromIntError;
-    fn try_from((x, y): (u8, u8)) -> Result<Self, Self::Error> {
+    fn try_from((x, y): (u16, u16)) -> Result<Self, Self::Error> {
         let pos = Self {
             x: x.try_into()?,
             y: y.try_into()?,
@@ -54,8 +75,8 @@ impl TryFrom<(u8, u8)> for Pos {
     }
 }
 
-impl From<(NonZeroU8, NonZeroU8)> for Pos {
-    fn from((x, y): (NonZeroU8, NonZeroU8)) -> Self {
+impl From<(Coord, Coord)> for Pos {
+    fn from((x, y): (Coord, Coord)) -> Self {
         Self { x, y }
     }
 }
@@ -65,7 +86,14 @@ impl From<(NonZeroU8, NonZeroU8)> for Pos {
 #[serde(tag = "type", content = "content")]
 pub enum CellState {
     Open,
-    Closed { flagged: bool, mined: bool },
+    Closed {
+        flagged: bool,
+        mined: bool,
+        /// Marked as uncertain with a question mark, which is not counted
+        /// as a flag and does not stop the cell from being opened.
+        #[serde(default)]
+        questioned: bool,
+    },
     ExposedMine,
 }
 
@@ -74,6 +102,7 @@ impl Default for CellState {
         CellState::Closed {
             flagged: false,
             mined: false,
+            questioned: false,
         }
     }
 }
@@ -91,9 +120,15 @@ impl Display for Cell {
                     } else {
                         ' '
                     },
-                CellState::Closed { flagged, .. } =>
+                CellState::Closed {
+                    flagged,
+                    questioned,
+                    ..
+                } =>
                     if flagged {
                         'F'
+                    } else if questioned {
+                        '?'
                     } else {
                         '.'
                     },
@@ -104,7 +139,7 @@ impl Display for Cell {
 }
 
 /// State of the game.
-#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
 pub enum GameState {
     New,
     Active,
@@ -112,20 +147,55 @@ pub enum GameState {
     Win,
 }
 
+impl GameState {
+    /// Is the game won or lost?
+    pub fn is_over(self) -> bool {
+        matches!(self, GameState::Win | GameState::Loss)
+    }
+}
+
+/// Failure to generate a mine layout.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GenerationError {
+    #[error("No board solvable without guessing was found in {0} attempts")]
+    NoGuessBudget(usize),
+}
+
 /// Game board.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Board {
-    cells: BTreeMap<Pos, Cell>,
-    columns: NonZeroU8,
-    rows: NonZeroU8,
+    cells: Cells,
+    config: BoardConfig,
+    seed: u64,
     state: GameState,
     opened: usize,
     flagged: usize,
     mined: usize,
+    hints: usize,
+    #[serde(default)]
+    moves: Vec<Move>,
+    /// Undone moves that can be redone, most recent last.
+    #[serde(default)]
+    undone: Vec<Move>,
+    #[serde(default)]
+    clicks: Clicks,
+    /// Time played, started by the first opened cell.
+    #[serde(default)]
+    clock: GameClock,
+    /// Flagging a flagged cell marks it with a question mark.
+    #[serde(default)]
+    question_marks: bool,
+    /// Topology used instead of the one of the configuration. It is not
+    /// saved with the board.
+    #[serde(skip)]
+    topology: Option<Arc<dyn Topology>>,
+    #[serde(skip)]
+    subscribers: Subscribers,
 }
 
 impl Board {
-    pub fn positions(&self) -> impl Iterator<Item = (&Pos, &Cell)> {
+    /// Cells of the board ordered by row, then by column.
+    pub fn positions(&self) -> impl Iterator<Item = (Pos, &Cell)> {
         self.cells.iter()
     }
 
@@ -133,12 +203,41 @@ impl Board {
         self.cells.get(pos)
     }
 
-    pub fn total_rows(&self) -> NonZeroU8 {
-        self.rows
+    pub fn total_rows(&self) -> Coord {
+        self.config.rows()
+    }
+
+    pub fn total_columns(&self) -> Coord {
+        self.config.columns()
     }
 
-    pub fn total_columns(&self) -> NonZeroU8 {
-        self.columns
+    pub fn config(&self) -> &BoardConfig {
+        &self.config
+    }
+
+    /// Seed used to generate the mine layout.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Topology of the board, the one of its configuration unless the board
+    /// was given its own.
+    pub fn topology(&self) -> &dyn Topology {
+        self.topology
+            .as_deref()
+            .unwrap_or(self.config.topology().topology())
+    }
+
+    /// Use a topology of your own again on a board loaded from a save, which
+    /// falls back to the topology of its configuration.
+    pub fn set_topology(&mut self, topology: Arc<dyn Topology>) {
+        self.topology = Some(topology);
+    }
+
+    /// Positions adjacent to the given position in the topology of the board.
+    pub fn neighbours(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        let (columns, rows) = (self.config.columns().get(), self.config.rows().get());
+        self.topology().neighbours(pos, columns, rows).into_iter()
     }
 
     pub fn opened(&self) -> usize {
@@ -152,18 +251,62 @@ impl Board {
     pub fn mined(&self) -> usize {
         self.mined
     }
+
+    /// Number of hints given during the game.
+    pub fn hints(&self) -> usize {
+        self.hints
+    }
+
+    /// Time played so far, excluding pauses.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed()
+    }
+
+    pub fn clock(&self) -> &GameClock {
+        &self.clock
+    }
+
+    /// Pause the clock, for example while a dialog covers the board.
+    pub fn pause_clock(&mut self) {
+        self.clock.pause();
+    }
+
+    /// Resume the clock of an active game.
+    pub fn resume_clock(&mut self) {
+        if self.state == GameState::Active {
+            self.clock.resume();
+        }
+    }
+
+    /// Read the time of the clock from another source.
+    pub fn set_time_source(&mut self, source: Arc<dyn TimeSource>) {
+        self.clock.set_source(source);
+    }
+
+    /// Set the time already played on a board restored without its clock.
+    pub(crate) fn set_elapsed(&mut self, elapsed: Duration) {
+        self.clock = self.clock.clone().with_elapsed(elapsed);
+    }
 }
 
 impl Display for Board {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "board: {}, mines: {}", self.board_size(), self.mined)?;
+        writeln!(
+            f,
+            "board: {}, mines: {}, seed: {}",
+            self.board_size(),
+            self.mined,
+            self.seed
+        )?;
         write!(f, "   ")?;
-        for c in 1..=self.columns.get() {
+        for c in 1..=self.total_columns().get() {
             write!(f, "{c:<3}")?;
         }
+        let topology = self.topology();
         for (pos, cell) in self.cells.iter() {
             if pos.x.get() == 1 {
-                write!(f, "\n{:<2} {cell}  ", pos.y)?;
+                let indent = topology.indent(pos.y.get());
+                write!(f, "\n{:<2} {:indent$}{cell}  ", pos.y, "")?;
             } else {
                 write!(f, "{cell:<3}  ")?;
             }
@@ -173,28 +316,56 @@ impl Display for Board {
 }
 
 struct CellExpandIter<'a> {
-    start: Option<Pos>,
-    board: &'a mut BTreeMap<Pos, Cell>,
-    visited: HashSet<Pos>,
+    board: &'a mut Cells,
     adjacent: Vec<Pos>,
-    total_rows: u8,
-    total_columns: u8,
+    topology: &'a dyn Topology,
+    total_rows: u16,
+    total_columns: u16,
 }
 
 impl<'a> CellExpandIter<'a> {
     pub fn new(
         pos: Pos,
-        board: &'a mut BTreeMap<Pos, Cell>,
-        total_rows: u8,
-        total_columns: u8,
+        board: &'a mut Cells,
+        topology: &'a dyn Topology,
+        config: &BoardConfig,
     ) -> Self {
-        CellExpandIter {
-            start: Some(pos),
-            board,
-            visited: HashSet::new(),
+        let mut iter = CellExpandIter {
             adjacent: vec![],
-            total_rows,
-            total_columns,
+            board,
+            topology,
+            total_rows: config.rows().get(),
+            total_columns: config.columns().get(),
+        };
+        iter.open_neighbours(pos);
+        iter
+    }
+
+    /// Open the closed neighbours of the position that can be opened by the
+    /// flood fill and queue them. Opening them right away queues every cell
+    /// once.
+    fn open_neighbours(&mut self, pos: Pos) {
+        for adj in self
+            .topology
+            .neighbours(pos, self.total_columns, self.total_rows)
+        {
+            let closed = self.board.get(&adj).is_some_and(|c| {
+                matches!(
+                    c.state,
+                    CellState::Closed {
+                        flagged: false,
+                        mined: false,
+                        ..
+                    }
+                )
+            });
+            if !closed {
+                continue;
+            }
+            if let Some(c) = self.board.get_mut(&adj) {
+                c.state = CellState::Open;
+                self.adjacent.push(adj);
+            }
         }
     }
 }
@@ -205,122 +376,151 @@ impl Iterator for CellExpandIter<'_> {
     type Item = (Pos, Cell);
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Start of iteration.
-        if let Some(p) = self.start.take() {
-            self.adjacent = p.adjacent(self.total_rows, self.total_columns).collect();
-        }
-
-        while !self.adjacent.is_empty() {
-            while let Some(p) = self.adjacent.pop() {
-                if self.visited.contains(&p) {
-                    continue;
-                }
-
-                self.visited.insert(p);
-                if let Some(c) = self.board.get_mut(&p).filter(|c| {
-                    matches!(
-                        c.state,
-                        CellState::Closed {
-                            flagged: false,
-                            mined: false
-                        }
-                    )
-                }) {
-                    if c.adjacent_mines == 0 {
-                        self.adjacent
-                            .extend(p.adjacent(self.total_rows, self.total_columns));
-                    }
-                    c.state = CellState::Open;
-                    return Some((p, *c));
-                }
-            }
+        let p = self.adjacent.pop()?;
+        let cell = *self.board.get(&p)?;
+        if cell.adjacent_mines == 0 {
+            self.open_neighbours(p);
         }
-        None
+        Some((p, cell))
     }
 }
 
 /// A Random Position iterator that yields unique positions within
 /// range.
 struct RandomPosIter {
-    used_positions: HashSet<Pos>,
-    columns: u8,
-    rows: u8,
-    rng: ThreadRng,
+    /// Positions excluded or already yielded, row after row.
+    used_positions: Vec<bool>,
+    used: usize,
+    columns: u16,
+    rows: u16,
+    rng: ChaCha8Rng,
 }
 
 impl RandomPosIter {
-    fn new(columns: u8, rows: u8, exclude: Vec<Pos>) -> Self {
-        Self {
-            used_positions: HashSet::from_iter(exclude),
-            rng: Default::default(),
+    fn new(columns: u16, rows: u16, exclude: Vec<Pos>, seed: u64) -> Self {
+        let mut iter = Self {
+            used_positions: vec![false; usize::from(columns) * usize::from(rows)],
+            used: 0,
+            rng: ChaCha8Rng::seed_from_u64(seed),
             rows,
             columns,
+        };
+        for pos in exclude {
+            iter.insert(pos);
         }
+        iter
+    }
+
+    /// Mark the position as used. Returns false when it was used already or
+    /// is out of range.
+    fn insert(&mut self, pos: Pos) -> bool {
+        let (x, y) = (pos.x.get(), pos.y.get());
+        if x > self.columns || y > self.rows {
+            return false;
+        }
+        let index = usize::from(y - 1) * usize::from(self.columns) + usize::from(x - 1);
+        let inserted = !self.used_positions[index];
+        self.used_positions[index] = true;
+        self.used += usize::from(inserted);
+        inserted
     }
 }
 
 impl Iterator for RandomPosIter {
     type Item = Pos;
     fn next(&mut self) -> Option<Self::Item> {
+        // Every position is either excluded or already yielded.
+        if self.used >= self.used_positions.len() {
+            None?;
+        }
+
         loop {
-            let x = self.rng.random_range(1..self.columns);
-            let y = self.rng.random_range(1..self.rows);
+            let x = self.rng.random_range(1..=self.columns);
+            let y = self.rng.random_range(1..=self.rows);
 
             let pos = Pos::try_from((x, y)).ok()?;
 
-            if self.used_positions.len() == usize::from(self.rows) * usize::from(self.columns) {
-                None?;
-            }
-
-            if !self.used_positions.contains(&pos) {
-                self.used_positions.insert(pos);
-                return Some(Pos::try_from((x, y)).expect("No zero"));
+            if self.insert(pos) {
+                return Some(pos);
             }
         }
     }
 }
 
 impl Pos {
-    /// Yields unique random positions within range and exclusion.
-    fn random_positions(columns: u8, rows: u8, exclude: Vec<Pos>) -> impl Iterator<Item = Pos> {
-        RandomPosIter::new(columns, rows, exclude)
-    }
-
-    /// Yields adjacent positions within bounds.
-    fn adjacent(&self, max_rows: u8, max_columns: u8) -> impl Iterator<Item = Pos> + '_ {
-        let &Pos { x, y } = &self;
-        let edges = |n| [n - 1, n, n + 1].into_iter();
-
-        edges(x.get())
-            .flat_map(move |x1| edges(y.get()).map(move |y1| (x1, y1)))
-            .filter(move |&(x1, y1)| x1 > 0 && y1 > 0 && (x1, y1) != (x.get(), y.get()))
-            .filter(move |&(x, y)| y <= max_rows && x <= max_columns)
-            .map(Pos::try_from)
-            .flat_map(|r| r.ok())
+    /// Yields unique random positions within range and exclusion. The same seed
+    /// always yields the same positions.
+    fn random_positions(
+        columns: u16,
+        rows: u16,
+        exclude: Vec<Pos>,
+        seed: u64,
+    ) -> impl Iterator<Item = Pos> {
+        RandomPosIter::new(columns, rows, exclude, seed)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Pos;
+    use super::{topology::Grid, Pos, Topology};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_random_positions_reach_every_cell() {
+        let exclude = Pos::try_from((2, 2)).unwrap();
+        let (columns, rows, mines, runs) = (4, 4, 3, 3000);
+        let mut counts = HashMap::new();
+
+        for seed in 0..runs {
+            for pos in Pos::random_positions(columns, rows, vec![exclude], seed).take(mines) {
+                *counts.entry(pos).or_insert(0_usize) += 1;
+            }
+        }
+
+        // Every cell except the excluded one is drawn close to its expected
+        // share, including the last row and column.
+        let expected = runs as usize * mines / 15;
+        assert!(!counts.contains_key(&exclude));
+        assert_eq!(counts.len(), 15);
+        for (pos, count) in counts {
+            assert!(
+                count.abs_diff(expected) < expected / 5,
+                "{pos:?} drawn {count} times, expected about {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_positions_exhausted() {
+        let exclude = [(1, 1), (2, 1), (3, 1), (1, 2), (2, 2)]
+            .map(|p| Pos::try_from(p).unwrap())
+            .to_vec();
+        let positions = Pos::random_positions(3, 3, exclude.clone(), 1)
+            .take(10)
+            .collect::<Vec<_>>();
+        assert_eq!(positions.len(), 4);
+        assert!(positions.iter().all(|pos| !exclude.contains(pos)));
+
+        let all = (1..=3)
+            .flat_map(|x| (1..=3).map(move |y| Pos::try_from((x, y)).unwrap()))
+            .collect();
+        assert_eq!(Pos::random_positions(3, 3, all, 1).next(), None);
+    }
     #[test]
     fn test_adjacent() {
         let adjacent = Pos::try_from((1, 1))
-            .unwrap()
-            .adjacent(10, 10)
-            .collect::<Vec<_>>();
+            .map(|pos| Grid.neighbours(pos, 10, 10))
+            .unwrap();
         dbg!(&adjacent);
         assert_eq!(adjacent.len(), 3);
         let adjacent = Pos::try_from((3, 3))
-            .unwrap()
-            .adjacent(10, 10)
-            .collect::<Vec<_>>();
+            .map(|pos| Grid.neighbours(pos, 10, 10))
+            .unwrap();
         dbg!(&adjacent);
         assert_eq!(adjacent.len(), 8);
         let adjacent = Pos::try_from((2, 1))
-            .unwrap()
-            .adjacent(10, 10)
-            .collect::<Vec<_>>();
+            .map(|pos| Grid.neighbours(pos, 10, 10))
+            .unwrap();
         dbg!(&adjacent);
     }
 }
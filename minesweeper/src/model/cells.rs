@@ -0,0 +1,237 @@
+//! Dense storage of the cells of a board.
+use super::{BoardConfig, Cell, Coord, Pos, Topology};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Cells of a board row after row, addressed by their position. Positions
+/// left out by the topology of the board hold no cell.
+///
+/// Cells are saved as a map from position to cell, as boards stored them
+/// before.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Cells {
+    columns: u16,
+    cells: Vec<Option<Cell>>,
+    len: usize,
+    /// Indices and cells as they were before being borrowed mutably while
+    /// changes are tracked. Boards of 65535 by 65535 cells still fit the
+    /// indices.
+    journal: Option<Vec<(u32, Cell)>>,
+    /// Indices in the journal, one bit per cell.
+    journaled: Vec<u64>,
+}
+
+impl Cells {
+    /// Closed cells of a board with the configuration on the topology.
+    pub(crate) fn new(config: &BoardConfig, topology: &dyn Topology) -> Self {
+        let (columns, rows) = (config.columns().get(), config.rows().get());
+        // Generate a cartesian product. Similar to my approach in Haskell.
+        let cells = (1..=rows)
+            .flat_map(|y| (1..=columns).map(move |x| Pos::from_coords(x, y)))
+            .map(|pos| topology.contains(pos, columns, rows).then(Cell::default))
+            .collect::<Vec<_>>();
+        let len = cells.iter().flatten().count();
+        Self {
+            columns,
+            cells,
+            len,
+            journal: None,
+            journaled: vec![],
+        }
+    }
+
+    fn index(&self, pos: &Pos) -> Option<usize> {
+        let (x, y) = (usize::from(pos.x.get()), usize::from(pos.y.get()));
+        let index = (y - 1) * usize::from(self.columns) + x - 1;
+        (x <= usize::from(self.columns) && index < self.cells.len()).then_some(index)
+    }
+
+    fn pos(&self, index: usize) -> Pos {
+        let columns = usize::from(self.columns);
+        Pos::from_coords((index % columns + 1) as u16, (index / columns + 1) as u16)
+    }
+
+    pub(crate) fn get(&self, pos: &Pos) -> Option<&Cell> {
+        self.index(pos).and_then(|index| self.cells[index].as_ref())
+    }
+
+    /// Borrow the cell at the position to change it. The cell is kept for
+    /// [`Cells::changes`] while changes are tracked.
+    pub(crate) fn get_mut(&mut self, pos: &Pos) -> Option<&mut Cell> {
+        let index = self.index(pos)?;
+        let cell = self.cells[index].as_mut()?;
+        if let Some(journal) = &mut self.journal {
+            // Only the first borrow has the cell before any change.
+            let (word, bit) = (index / 64, 1 << (index % 64));
+            if self.journaled[word] & bit == 0 {
+                self.journaled[word] |= bit;
+                journal.push((index as u32, *cell));
+            }
+        }
+        Some(cell)
+    }
+
+    pub(crate) fn contains_key(&self, pos: &Pos) -> bool {
+        self.get(pos).is_some()
+    }
+
+    /// Number of cells of the board.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Cells ordered by row, then by column.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Pos, &Cell)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(|(index, cell)| cell.as_ref().map(|cell| (self.pos(index), cell)))
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Cell> {
+        self.cells.iter().flatten()
+    }
+
+    /// Start tracking the cells changed through [`Cells::get_mut`].
+    pub(crate) fn track_changes(&mut self) {
+        self.journaled.resize(self.cells.len().div_ceil(64), 0);
+        self.journal = Some(vec![]);
+    }
+
+    /// Stop tracking changes. Returns the cells changed since tracking
+    /// started, with the cell before and after, in the order they were
+    /// first borrowed.
+    pub(crate) fn changes(&mut self) -> Vec<(Pos, Cell, Cell)> {
+        self.take_journal()
+            .filter_map(|(index, before)| {
+                let after = self.cells[index]?;
+                (after != before).then(|| (self.pos(index), before, after))
+            })
+            .collect()
+    }
+
+    /// Stop tracking changes. Returns whether any cell changed since
+    /// tracking started.
+    pub(crate) fn changed(&mut self) -> bool {
+        self.take_journal()
+            .any(|(index, before)| self.cells[index] != Some(before))
+    }
+
+    /// Take the journal and clear the indices kept in it.
+    fn take_journal(&mut self) -> impl Iterator<Item = (usize, Cell)> {
+        let journal = self.journal.take().unwrap_or_default();
+        for &(index, _) in &journal {
+            self.journaled[index as usize / 64] = 0;
+        }
+        journal
+            .into_iter()
+            .map(|(index, before)| (index as usize, before))
+    }
+
+    /// Replace the cells at the positions.
+    pub(crate) fn extend(&mut self, cells: impl IntoIterator<Item = (Pos, Cell)>) {
+        for (pos, cell) in cells {
+            if let Some(old) = self.get_mut(&pos) {
+                *old = cell;
+            }
+        }
+    }
+}
+
+impl Serialize for Cells {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Cells {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = BTreeMap::<Pos, Cell>::deserialize(deserializer)?;
+        let columns = map.keys().map(|pos| pos.x.get()).max().unwrap_or_default();
+        let rows = map.keys().map(|pos| pos.y.get()).max().unwrap_or_default();
+        let mut cells = Self {
+            columns,
+            cells: vec![None; usize::from(columns) * usize::from(rows)],
+            len: map.len(),
+            journal: None,
+            journaled: vec![],
+        };
+        for (pos, cell) in map {
+            if let Some(index) = cells.index(&pos) {
+                cells.cells[index] = Some(cell);
+            }
+        }
+        Ok(cells)
+    }
+}
+
+impl Pos {
+    /// Position of coordinates counted from one.
+    fn from_coords(x: u16, y: u16) -> Self {
+        Self {
+            x: Coord::new(x).expect("No zero"),
+            y: Coord::new(y).expect("No zero"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{CellState, Mines};
+
+    #[test]
+    fn test_cells() {
+        let config = BoardConfig::custom(
+            Coord::new(300).unwrap(),
+            Coord::new(2).unwrap(),
+            Mines::Count(10),
+        )
+        .unwrap();
+        let mut cells = Cells::new(&config, &config.topology());
+        assert_eq!(cells.len(), 600);
+        let pos = |x, y| Pos::try_from((x, y)).unwrap();
+        assert!(cells.contains_key(&pos(300, 2)));
+        assert!(!cells.contains_key(&pos(301, 1)));
+        assert!(!cells.contains_key(&pos(1, 3)));
+
+        cells.get_mut(&pos(260, 2)).unwrap().state = CellState::Open;
+        assert_eq!(
+            cells.iter().nth(559),
+            Some((
+                pos(260, 2),
+                &Cell {
+                    state: CellState::Open,
+                    adjacent_mines: 0
+                }
+            ))
+        );
+
+        // Saved as a map, the same as boards saved before.
+        let bytes = rmp_serde::to_vec_named(&cells).unwrap();
+        let map = rmp_serde::from_slice::<BTreeMap<Pos, Cell>>(&bytes).unwrap();
+        assert_eq!(map.len(), 600);
+        assert_eq!(map[&pos(260, 2)].state, CellState::Open);
+        let read = rmp_serde::from_slice::<Cells>(&bytes).unwrap();
+        assert!(read.iter().eq(cells.iter()));
+    }
+
+    #[test]
+    fn test_narrow_positions() {
+        // Positions saved before coordinates were widened.
+        #[derive(Serialize)]
+        struct NarrowPos {
+            x: std::num::NonZeroU8,
+            y: std::num::NonZeroU8,
+        }
+        let narrow = NarrowPos {
+            x: 9.try_into().unwrap(),
+            y: 255.try_into().unwrap(),
+        };
+        let bytes = rmp_serde::to_vec_named(&narrow).unwrap();
+        assert_eq!(
+            rmp_serde::from_slice::<Pos>(&bytes).unwrap(),
+            Pos::try_from((9, 255)).unwrap()
+        );
+    }
+}
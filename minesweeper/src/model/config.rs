@@ -0,0 +1,399 @@
+//! Board configuration and difficulty presets.
+use super::{Coord, TopologyMode};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+/// Maximum number of cells kept free of mines around the first opened cell.
+const PROTECTED_CELLS: usize = 9;
+
+/// Largest number of cells of a board, as many as a square board 2048
+/// cells wide.
+pub const MAX_CELLS: usize = 2048 * 2048;
+
+/// Difficulty level of a board.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+pub enum Difficulty {
+    /// 9 x 9 with 10 mines.
+    #[default]
+    Beginner,
+    /// 16 x 16 with 40 mines.
+    Intermediate,
+    /// 30 x 16 with 99 mines.
+    Expert,
+    /// User provided dimensions and mines.
+    #[value(skip)]
+    Custom,
+}
+
+impl Difficulty {
+    /// Difficulty levels with a predefined board.
+    pub const PRESETS: [Difficulty; 3] = [
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+        Difficulty::Expert,
+    ];
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Beginner => "Beginner",
+            Difficulty::Intermediate => "Intermediate",
+            Difficulty::Expert => "Expert",
+            Difficulty::Custom => "Custom",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// How mines are placed after the first opened cell.
+#[derive(
+    Debug, Copy, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub enum GenerationMode {
+    /// Mines are placed at random.
+    #[default]
+    Random,
+    /// Mines are placed so the board can be solved by logic alone from
+    /// the first opened cell.
+    NoGuess,
+}
+
+impl Display for GenerationMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationMode::Random => write!(f, "Random"),
+            GenerationMode::NoGuess => write!(f, "No guess"),
+        }
+    }
+}
+
+/// When a game is won.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+pub enum WinRule {
+    /// Every safe cell is opened. The mines left closed are flagged.
+    #[default]
+    Classic,
+    /// Every safe cell is opened or every mine is flagged.
+    Flags,
+}
+
+impl Display for WinRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WinRule::Classic => write!(f, "Classic"),
+            WinRule::Flags => write!(f, "Flags"),
+        }
+    }
+}
+
+/// Requested amount of mines for a custom board.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Mines {
+    /// An exact number of mines.
+    Count(usize),
+    /// A ratio of mined cells between 0 and 1.
+    Density(f64),
+}
+
+/// Validated board dimensions and mine count.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BoardConfig {
+    columns: Coord,
+    rows: Coord,
+    mines: usize,
+    difficulty: Difficulty,
+    #[serde(default)]
+    generation: GenerationMode,
+    /// Practice games allow undo and are kept off the leaderboard.
+    #[serde(default)]
+    practice: bool,
+    #[serde(default)]
+    topology: TopologyMode,
+    #[serde(default)]
+    win_rule: WinRule,
+}
+
+impl BoardConfig {
+    /// Configuration for a preset difficulty level. Returns `None` for
+    /// [`Difficulty::Custom`].
+    pub fn preset(difficulty: Difficulty) -> Option<Self> {
+        let (columns, rows, mines) = match difficulty {
+            Difficulty::Beginner => (9, 9, 10),
+            Difficulty::Intermediate => (16, 16, 40),
+            Difficulty::Expert => (30, 16, 99),
+            Difficulty::Custom => None?,
+        };
+        Some(Self {
+            columns: Coord::new(columns).expect("No zero"),
+            rows: Coord::new(rows).expect("No zero"),
+            mines,
+            difficulty,
+            generation: GenerationMode::default(),
+            practice: false,
+            topology: TopologyMode::default(),
+            win_rule: WinRule::default(),
+        })
+    }
+
+    /// Configuration for a custom board. The board can hold at most
+    /// [`MAX_CELLS`] cells and the mine count must leave room for the cells
+    /// protected around the first opened position.
+    pub fn custom(columns: Coord, rows: Coord, mines: Mines) -> Result<Self, ConfigError> {
+        let cells = usize::from(columns.get()) * usize::from(rows.get());
+        if cells > MAX_CELLS {
+            return Err(ConfigError::TooManyCells {
+                cells,
+                max: MAX_CELLS,
+            });
+        }
+        let mines = match mines {
+            Mines::Count(count) => count,
+            Mines::Density(density) if density > 0. && density < 1. => {
+                (cells as f64 * density) as usize
+            }
+            Mines::Density(density) => return Err(ConfigError::Density(density)),
+        };
+        let max = Self::max_mines(columns, rows);
+
+        if mines == 0 {
+            Err(ConfigError::NoMines)
+        } else if mines > max {
+            Err(ConfigError::TooManyMines { mines, max })
+        } else {
+            Ok(Self {
+                columns,
+                rows,
+                mines,
+                difficulty: Difficulty::Custom,
+                generation: GenerationMode::default(),
+                practice: false,
+                topology: TopologyMode::default(),
+                win_rule: WinRule::default(),
+            })
+        }
+    }
+
+    /// Check a configuration that was read without [`BoardConfig::custom`],
+    /// like one from a file. A preset must have the board of its difficulty.
+    pub fn validate(self) -> Result<Self, ConfigError> {
+        match Self::preset(self.difficulty) {
+            Some(preset)
+                if (preset.columns, preset.rows, preset.mines)
+                    != (self.columns, self.rows, self.mines) =>
+            {
+                Err(ConfigError::Preset(self.difficulty))
+            }
+            Some(_) => Ok(self),
+            None => Self::custom(self.columns, self.rows, Mines::Count(self.mines)).map(|_| self),
+        }
+    }
+
+    /// Largest number of mines a board with the given dimensions can hold.
+    pub fn max_mines(columns: Coord, rows: Coord) -> usize {
+        let cells = usize::from(columns.get()) * usize::from(rows.get());
+        cells - cells.min(PROTECTED_CELLS)
+    }
+
+    pub fn columns(&self) -> Coord {
+        self.columns
+    }
+
+    pub fn rows(&self) -> Coord {
+        self.rows
+    }
+
+    pub fn mines(&self) -> usize {
+        self.mines
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn generation(&self) -> GenerationMode {
+        self.generation
+    }
+
+    /// Use the given generation mode for placing mines.
+    pub fn with_generation(self, generation: GenerationMode) -> Self {
+        Self { generation, ..self }
+    }
+
+    pub fn practice(&self) -> bool {
+        self.practice
+    }
+
+    /// Play a practice game where moves can be undone. Practice wins are
+    /// not recorded.
+    pub fn with_practice(self, practice: bool) -> Self {
+        Self { practice, ..self }
+    }
+
+    pub fn topology(&self) -> TopologyMode {
+        self.topology
+    }
+
+    /// Use the given topology for the neighbours of the cells.
+    pub fn with_topology(self, topology: TopologyMode) -> Self {
+        Self { topology, ..self }
+    }
+
+    pub fn win_rule(&self) -> WinRule {
+        self.win_rule
+    }
+
+    /// Use the given rule for winning the game.
+    pub fn with_win_rule(self, win_rule: WinRule) -> Self {
+        Self { win_rule, ..self }
+    }
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self::preset(Difficulty::default()).expect("Preset difficulty")
+    }
+}
+
+impl Display for BoardConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}x{}, {} mines)",
+            self.difficulty, self.columns, self.rows, self.mines
+        )?;
+        if self.generation == GenerationMode::NoGuess {
+            write!(f, " no guess")?;
+        }
+        if self.topology != TopologyMode::Grid {
+            write!(f, " {}", self.topology.to_string().to_lowercase())?;
+        }
+        if self.win_rule == WinRule::Flags {
+            write!(f, " flag wins")?;
+        }
+        if self.practice {
+            write!(f, " practice")?;
+        }
+        Ok(())
+    }
+}
+
+/// Invalid board configuration.
+#[derive(Debug, Error, PartialEq)]
+pub enum ConfigError {
+    #[error("A board needs at least one mine")]
+    NoMines,
+    #[error("Too many mines: {mines}, the board can hold at most {max}")]
+    TooManyMines { mines: usize, max: usize },
+    #[error("Invalid mine density: {0}, expected a value between 0 and 1")]
+    Density(f64),
+    #[error("Too many cells: {cells}, a board can have at most {max}")]
+    TooManyCells { cells: usize, max: usize },
+    #[error("The board is not the {0} board")]
+    Preset(Difficulty),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dim(n: u16) -> Coord {
+        Coord::new(n).unwrap()
+    }
+
+    #[test]
+    fn test_presets() {
+        let expert = BoardConfig::preset(Difficulty::Expert).unwrap();
+        assert_eq!(
+            (expert.columns().get(), expert.rows().get(), expert.mines()),
+            (30, 16, 99)
+        );
+        assert!(BoardConfig::preset(Difficulty::Custom).is_none());
+    }
+
+    #[test]
+    fn test_custom() {
+        let config = BoardConfig::custom(dim(10), dim(10), Mines::Density(0.1)).unwrap();
+        assert_eq!(config.mines(), 10);
+        assert_eq!(config.difficulty(), Difficulty::Custom);
+
+        assert_eq!(
+            BoardConfig::custom(dim(5), dim(5), Mines::Count(17)),
+            Err(ConfigError::TooManyMines { mines: 17, max: 16 })
+        );
+        assert!(BoardConfig::custom(dim(5), dim(5), Mines::Count(16)).is_ok());
+        assert_eq!(
+            BoardConfig::custom(dim(3), dim(3), Mines::Count(1)),
+            Err(ConfigError::TooManyMines { mines: 1, max: 0 })
+        );
+        assert_eq!(
+            BoardConfig::custom(dim(5), dim(5), Mines::Count(0)),
+            Err(ConfigError::NoMines)
+        );
+        assert_eq!(
+            BoardConfig::custom(dim(5), dim(5), Mines::Density(1.5)),
+            Err(ConfigError::Density(1.5))
+        );
+        assert_eq!(
+            BoardConfig::custom(dim(u16::MAX), dim(u16::MAX), Mines::Density(0.1)),
+            Err(ConfigError::TooManyCells {
+                cells: 65535 * 65535,
+                max: MAX_CELLS
+            })
+        );
+        assert!(BoardConfig::custom(dim(2048), dim(2048), Mines::Count(1)).is_ok());
+        assert!(BoardConfig::custom(dim(u16::MAX), dim(2), Mines::Count(1)).is_ok());
+    }
+
+    #[test]
+    fn test_validate() {
+        let expert = BoardConfig::preset(Difficulty::Expert).unwrap();
+        assert_eq!(expert.validate(), Ok(expert));
+        let resized = BoardConfig {
+            rows: dim(30),
+            ..expert
+        };
+        assert_eq!(
+            resized.validate(),
+            Err(ConfigError::Preset(Difficulty::Expert))
+        );
+
+        let custom = BoardConfig::custom(dim(5), dim(5), Mines::Count(3)).unwrap();
+        assert_eq!(custom.validate(), Ok(custom));
+        let crowded = BoardConfig {
+            mines: 20,
+            ..custom
+        };
+        assert_eq!(
+            crowded.validate(),
+            Err(ConfigError::TooManyMines { mines: 20, max: 16 })
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Events sent to subscribers of a board as it changes.
+use super::{moves::Counters, Board, Cell, CellState, GameState, Pos};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// Change of a board. The events of an action are sent once it is played,
+/// in the order the game started, cells changed, counters changed and the
+/// game ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoardEvent {
+    /// The first cell was opened and the board was mined.
+    GameStarted,
+    CellOpened(Pos, Cell),
+    CellFlagged(Pos, Cell),
+    CellUnflagged(Pos, Cell),
+    CellQuestioned(Pos, Cell),
+    CellUnquestioned(Pos, Cell),
+    /// A mine shown when the game is lost.
+    MineExposed(Pos, Cell),
+    /// An opened cell or exposed mine closed again by undo.
+    CellClosed(Pos, Cell),
+    GameWon,
+    GameLost,
+    /// Undo took a finished game or the first move back to the state.
+    StateRestored(GameState),
+    CountersChanged {
+        opened: usize,
+        flagged: usize,
+        mined: usize,
+    },
+}
+
+impl BoardEvent {
+    /// Position and new state of the cell changed by the event.
+    pub fn cell(&self) -> Option<(Pos, Cell)> {
+        match *self {
+            BoardEvent::CellOpened(pos, cell)
+            | BoardEvent::CellFlagged(pos, cell)
+            | BoardEvent::CellUnflagged(pos, cell)
+            | BoardEvent::CellQuestioned(pos, cell)
+            | BoardEvent::CellUnquestioned(pos, cell)
+            | BoardEvent::MineExposed(pos, cell)
+            | BoardEvent::CellClosed(pos, cell) => Some((pos, cell)),
+            _ => None,
+        }
+    }
+}
+
+/// Senders of the events of a board. Clones of a board, like the trial
+/// boards of the generator, start without subscribers.
+#[derive(Debug, Default)]
+pub(super) struct Subscribers(Vec<Sender<BoardEvent>>);
+
+impl Subscribers {
+    /// Is anyone subscribed?
+    pub(super) fn any(&self) -> bool {
+        !self.0.is_empty()
+    }
+}
+
+impl Clone for Subscribers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl Board {
+    /// Receive the events of every later change of the board. Draining the
+    /// receiver with `try_iter` after an action gives the events of the
+    /// action. Dropping the receiver ends the subscription.
+    pub fn subscribe(&mut self) -> Receiver<BoardEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.0.push(sender);
+        receiver
+    }
+
+    /// Send the events of the changed cells, with their state before and
+    /// after, and of the changed counters.
+    pub(super) fn emit(
+        &mut self,
+        cells: impl IntoIterator<Item = (Pos, Cell, Cell)>,
+        before: Counters,
+        after: Counters,
+    ) {
+        if !self.subscribers.any() {
+            return;
+        }
+        let mut events = vec![];
+        if (before.state, after.state) == (GameState::New, GameState::Active) {
+            events.push(BoardEvent::GameStarted);
+        }
+        events.extend(
+            cells
+                .into_iter()
+                .flat_map(|(pos, before, after)| cell_events(pos, before, after)),
+        );
+        if (before.opened, before.flagged, before.mined)
+            != (after.opened, after.flagged, after.mined)
+        {
+            events.push(BoardEvent::CountersChanged {
+                opened: after.opened,
+                flagged: after.flagged,
+                mined: after.mined,
+            });
+        }
+        match (before.state, after.state) {
+            (GameState::Win, GameState::Win) | (GameState::Loss, GameState::Loss) => (),
+            (_, GameState::Win) => events.push(BoardEvent::GameWon),
+            (_, GameState::Loss) => events.push(BoardEvent::GameLost),
+            (GameState::Win | GameState::Loss, state)
+            | (GameState::Active, state @ GameState::New) => {
+                events.push(BoardEvent::StateRestored(state))
+            }
+            _ => (),
+        }
+        self.subscribers
+            .0
+            .retain(|sender| events.iter().all(|&event| sender.send(event).is_ok()));
+    }
+}
+
+/// Events of a cell that changed from one state to another. Flags and
+/// question marks change independently, so a flag turned into a question
+/// mark sends `CellUnflagged` then `CellQuestioned`. Mines placed under
+/// closed cells send no event.
+fn cell_events(pos: Pos, before: Cell, after: Cell) -> Vec<BoardEvent> {
+    match (before.state, after.state) {
+        (CellState::Closed { .. }, CellState::Open) => vec![BoardEvent::CellOpened(pos, after)],
+        (CellState::Closed { .. }, CellState::ExposedMine) => {
+            vec![BoardEvent::MineExposed(pos, after)]
+        }
+        (CellState::Open | CellState::ExposedMine, CellState::Closed { .. }) => {
+            vec![BoardEvent::CellClosed(pos, after)]
+        }
+        (
+            CellState::Closed {
+                flagged: was_flagged,
+                questioned: was_questioned,
+                ..
+            },
+            CellState::Closed {
+                flagged,
+                questioned,
+                ..
+            },
+        ) => {
+            let mut events = vec![];
+            // Take the old mark off before putting the new one on.
+            match (was_flagged, flagged) {
+                (true, false) => events.push(BoardEvent::CellUnflagged(pos, after)),
+                (false, true) => events.push(BoardEvent::CellFlagged(pos, after)),
+                _ => (),
+            }
+            match (was_questioned, questioned) {
+                (true, false) => events.insert(0, BoardEvent::CellUnquestioned(pos, after)),
+                (false, true) => events.push(BoardEvent::CellQuestioned(pos, after)),
+                _ => (),
+            }
+            events
+        }
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{BoardConfig, Coord, Mines};
+
+    fn pos(x: u16, y: u16) -> Pos {
+        Pos::try_from((x, y)).unwrap()
+    }
+
+    #[test]
+    fn test_events() {
+        let four = Coord::new(4).unwrap();
+        let config = BoardConfig::custom(four, four, Mines::Count(2))
+            .unwrap()
+            .with_practice(true);
+        let mut board = Board::with_mines(config, [pos(4, 4), pos(4, 1)]);
+        let events = board.subscribe();
+
+        board.flag_cell(pos(4, 4));
+        board.flag_cell(pos(4, 4));
+        let drained = events.try_iter().collect::<Vec<_>>();
+        assert!(matches!(drained[0], BoardEvent::CellFlagged(p, _) if p == pos(4, 4)));
+        assert!(matches!(drained[2], BoardEvent::CellUnflagged(p, _) if p == pos(4, 4)));
+        assert_eq!(
+            drained[3],
+            BoardEvent::CountersChanged {
+                opened: 0,
+                flagged: 0,
+                mined: 2
+            }
+        );
+
+        board.set_question_marks(true);
+        board.flag_cell(pos(3, 4));
+        board.flag_cell(pos(3, 4));
+        board.flag_cell(pos(3, 4));
+        let marks = events
+            .try_iter()
+            .filter(|e| e.cell().is_some())
+            .collect::<Vec<_>>();
+        // The flag comes off before the question mark goes on.
+        assert!(matches!(
+            marks[..],
+            [
+                BoardEvent::CellFlagged(..),
+                BoardEvent::CellUnflagged(..),
+                BoardEvent::CellQuestioned(..),
+                BoardEvent::CellUnquestioned(..)
+            ]
+        ));
+
+        board.open_cell(pos(1, 1)).unwrap();
+        let opened = events.try_iter().filter(|e| e.cell().is_some()).count();
+        assert_eq!(opened, board.opened());
+
+        // Trial boards don't send to the subscribers of the board.
+        let mut trial = board.clone();
+        trial.open_cell(pos(4, 4)).unwrap();
+        assert_eq!(events.try_iter().count(), 0);
+
+        board.open_cell(pos(4, 4)).unwrap();
+        let drained = events.try_iter().collect::<Vec<_>>();
+        assert!(drained
+            .iter()
+            .any(|e| matches!(e, BoardEvent::MineExposed(p, _) if *p == pos(4, 1))));
+        assert_eq!(drained.last(), Some(&BoardEvent::GameLost));
+
+        board.undo().unwrap();
+        let drained = events.try_iter().collect::<Vec<_>>();
+        assert_eq!(drained.iter().filter(|e| e.cell().is_some()).count(), 2);
+        assert_eq!(
+            drained.last(),
+            Some(&BoardEvent::StateRestored(GameState::Active))
+        );
+
+        drop(events);
+        board.redo().unwrap();
+        assert!(board.subscribers.0.is_empty());
+    }
+}
@@ -1,47 +1,116 @@
 //! Board implementation for handling game play.
-use super::{Board, Cell, CellExpandIter, CellState, GameState, Pos};
-use std::{collections::BTreeMap, num::NonZeroU8};
+use super::{
+    Action, Board, BoardConfig, Cell, CellExpandIter, CellState, Cells, GameState, GenerationError,
+    GenerationMode, Pos, Topology, WinRule,
+};
+use crate::solver::{self, Deduction};
+use rand::RngCore;
+use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+use std::sync::Arc;
+
+/// Number of layouts tried when generating a board that needs no guessing.
+const NO_GUESS_ATTEMPTS: usize = 500;
 
 impl Board {
-    /// Create a new board with the given columns and rows.
-    pub fn new(columns: NonZeroU8, rows: NonZeroU8) -> Self {
-        // Generate a cartesian product. Similar to my approach in Haskell.
-        let cells = (1..=rows.get())
-            .flat_map(|y| (1..=columns.get()).map(move |x| (x, y)))
-            .map(|p| (p.try_into().expect("No zero"), Cell::default()))
-            .collect::<BTreeMap<_, _>>();
+    /// Create a new board with the given configuration and a random seed.
+    pub fn new(config: BoardConfig) -> Self {
+        Self::with_seed(config, rand::random())
+    }
+
+    /// Create a new board with the given configuration. Boards with the same
+    /// seed and first opened position have the same mine layout.
+    pub fn with_seed(config: BoardConfig, seed: u64) -> Self {
+        Self::with_cells(config, seed, None)
+    }
+
+    /// Create a new board whose cells and neighbours follow a topology of your
+    /// own instead of the topology of the configuration. The topology is not
+    /// saved with the board, see [`Board::set_topology`].
+    pub fn with_topology(config: BoardConfig, seed: u64, topology: Arc<dyn Topology>) -> Self {
+        Self::with_cells(config, seed, Some(topology))
+    }
+
+    fn with_cells(config: BoardConfig, seed: u64, topology: Option<Arc<dyn Topology>>) -> Self {
+        let cells = match &topology {
+            Some(topology) => Cells::new(&config, topology.as_ref()),
+            None => Cells::new(&config, config.topology().topology()),
+        };
         Board {
             cells,
-            columns,
-            rows,
+            config,
+            seed,
             state: GameState::New,
             opened: 0,
             flagged: 0,
             mined: 0,
+            hints: 0,
+            moves: vec![],
+            undone: vec![],
+            clicks: Default::default(),
+            clock: Default::default(),
+            question_marks: false,
+            topology,
+            subscribers: Default::default(),
         }
     }
 
-    /// Randomly mine the board with a difficulty ratio. Exclude mining the provided position.
-    fn mine_board(&mut self, exclude_pos: &Pos) {
-        let mut avoid_cells = exclude_pos
-            .adjacent(self.rows.get(), self.columns.get())
-            .collect::<Vec<_>>();
-        avoid_cells.push(*exclude_pos);
+    /// Randomly mine the board with the configured number of mines. Exclude mining the
+    /// provided position.
+    fn mine_board(&mut self, exclude_pos: &Pos) -> Result<(), GenerationError> {
+        match self.config.generation() {
+            GenerationMode::Random => {
+                let mined_positions = self.random_layout(exclude_pos, self.seed);
+                self.place_mines(mined_positions);
+                Ok(())
+            }
+            GenerationMode::NoGuess => self.mine_board_no_guess(exclude_pos),
+        }
+    }
+
+    /// Try random layouts until one can be solved by logic from the excluded
+    /// position. The first attempt uses the board seed and later attempts use
+    /// seeds derived from it so the result stays reproducible.
+    fn mine_board_no_guess(&mut self, exclude_pos: &Pos) -> Result<(), GenerationError> {
+        let mut attempt_seeds = ChaCha8Rng::seed_from_u64(self.seed);
+        for attempt in 0..NO_GUESS_ATTEMPTS {
+            let seed = if attempt == 0 {
+                self.seed
+            } else {
+                attempt_seeds.next_u64()
+            };
+            let mined_positions = self.random_layout(exclude_pos, seed);
 
-        // Set the total amount of mined cells based on
-        // difficulty level.
-        let total_mined_cells =
-            (f64::from(self.rows.get()) * f64::from(self.columns.get()) * 0.10) as usize;
+            let mut trial = self.clone();
+            trial.place_mines(mined_positions.iter().copied());
+            trial.state = GameState::Active;
+            trial.reveal(*exclude_pos);
 
-        // Iterator yielding mined positions.
-        let mined_positions =
-            Pos::random_positions(self.columns.get(), self.rows.get(), avoid_cells)
-                .take(total_mined_cells);
+            if solver::solve(&mut trial) {
+                self.place_mines(mined_positions);
+                return Ok(());
+            }
+        }
+        Err(GenerationError::NoGuessBudget(NO_GUESS_ATTEMPTS))
+    }
+
+    /// Random mine positions avoiding the excluded position and its neighbours.
+    fn random_layout(&self, exclude_pos: &Pos, seed: u64) -> Vec<Pos> {
+        let (columns, rows) = (self.config.columns().get(), self.config.rows().get());
+        let mut avoid_cells = self.neighbours(*exclude_pos).collect::<Vec<_>>();
+        avoid_cells.push(*exclude_pos);
+
+        Pos::random_positions(columns, rows, avoid_cells, seed)
+            .filter(|pos| self.cells.contains_key(pos))
+            .take(self.config.mines())
+            .collect()
+    }
 
+    /// Mine the given positions and update the adjacent mine counts.
+    pub(crate) fn place_mines(&mut self, positions: impl IntoIterator<Item = Pos>) {
         let mut total_mined = 0;
 
         // Update cell status for mined positions and mined counts.
-        for pos in mined_positions {
+        for pos in positions {
             if let Some(mined) =
                 self.cells
                     .get_mut(&pos)
@@ -53,42 +122,70 @@ impl Board {
             {
                 *mined = true;
                 total_mined += 1;
-                for adj in pos.adjacent(self.rows.get(), self.columns.get()) {
-                    self.cells.entry(adj).and_modify(|c| c.adjacent_mines += 1);
+                for adj in self.neighbours(pos) {
+                    if let Some(c) = self.cells.get_mut(&adj) {
+                        c.adjacent_mines += 1;
+                    }
                 }
             }
         }
 
-        self.mined = total_mined;
+        self.mined += total_mined;
     }
 
     /// Return an iterator of all positions that are safe to open and have been opened.
     fn expand(&mut self, pos: Pos) -> impl Iterator<Item = (Pos, Cell)> + '_ {
-        CellExpandIter::new(pos, &mut self.cells, self.rows.get(), self.columns.get())
+        let topology = self
+            .topology
+            .as_deref()
+            .unwrap_or(self.config.topology().topology());
+        CellExpandIter::new(pos, &mut self.cells, topology, &self.config)
     }
 
-    /// Open a cell and adjacent cells that have no mine counts.
-    pub fn open_cell(&mut self, pos: Pos) -> Vec<(Pos, Cell)> {
-        if self.state == GameState::New {
+    /// Open a cell and adjacent cells that have no mine counts. Fails when the first
+    /// move can't generate a mine layout. Nothing is opened once the game is over.
+    pub fn open_cell(&mut self, pos: Pos) -> Result<Vec<(Pos, Cell)>, GenerationError> {
+        if self.state == GameState::New && self.mined == 0 {
             // This is the first move in the game. We will mine the
-            // board now and avoid mining the position being opened.
-            self.mine_board(&pos);
-            self.state = GameState::Active;
+            // board now and avoid mining the position being opened. The
+            // layout is not part of the move, so it stays when the move is
+            // undone.
+            self.mine_board(&pos)?;
         }
+        Ok(self.record(Action::Open(pos), |board| board.open(pos)))
+    }
 
+    fn open(&mut self, pos: Pos) -> Vec<(Pos, Cell)> {
+        if self.state.is_over() {
+            return vec![];
+        }
+        self.state = GameState::Active;
+        self.clock.start();
+
+        self.reveal(pos)
+    }
+
+    /// Open a cell on a mined board and adjacent cells that have no mine counts.
+    pub(crate) fn reveal(&mut self, pos: Pos) -> Vec<(Pos, Cell)> {
         let mut opened_positions = vec![];
+        if self.state.is_over() {
+            return opened_positions;
+        }
         if let Some(c) = self.cells.get_mut(&pos) {
             match c.state {
                 CellState::Closed {
                     mined: true,
                     flagged: false,
+                    ..
                 } => {
                     self.expose_mines();
                     self.state = GameState::Loss;
+                    self.clock.stop();
                 }
                 CellState::Closed {
                     mined: false,
                     flagged: false,
+                    ..
                 } => {
                     c.state = CellState::Open;
                     opened_positions.push((pos, *c));
@@ -102,26 +199,143 @@ impl Board {
         self.opened += opened_positions.len();
         if self.is_win() {
             self.state = GameState::Win;
+            self.clock.stop();
+            if self.config.win_rule() == WinRule::Classic {
+                self.flag_mines();
+            }
         }
         opened_positions
     }
 
     /// Expose all mined cells on the board.
     fn expose_mines(&mut self) {
-        for c in self.cells.values_mut().filter(|c| c.is_closed_and_mined()) {
-            c.state = CellState::ExposedMine
+        for pos in self.closed_mines() {
+            if let Some(c) = self.cells.get_mut(&pos) {
+                c.state = CellState::ExposedMine
+            }
         }
     }
 
-    /// Flag the cell as being potentially mined.
+    /// Positions of the mines that are still closed.
+    fn closed_mines(&self) -> Vec<Pos> {
+        self.cells
+            .iter()
+            .filter(|(_, c)| c.is_closed_and_mined())
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    /// Open all closed and unflagged neighbours of an open cell when the number of
+    /// flagged neighbours matches its adjacent mine count. Opening a mined neighbour
+    /// because of a wrong flag loses the game.
+    pub fn chord(&mut self, pos: Pos) -> Vec<(Pos, Cell)> {
+        self.record(Action::Chord(pos), |board| board.open_neighbours(pos))
+    }
+
+    fn open_neighbours(&mut self, pos: Pos) -> Vec<(Pos, Cell)> {
+        let Some(cell) = self
+            .cells
+            .get(&pos)
+            .filter(|c| c.state == CellState::Open && c.adjacent_mines > 0)
+        else {
+            return vec![];
+        };
+
+        let flagged = self
+            .neighbours(pos)
+            .filter(|p| {
+                self.cells
+                    .get(p)
+                    .is_some_and(|c| matches!(c.state, CellState::Closed { flagged: true, .. }))
+            })
+            .count();
+
+        if self.state != GameState::Active || flagged != usize::from(cell.adjacent_mines) {
+            return vec![];
+        }
+
+        let mut opened_positions = vec![];
+        for adj in self.neighbours(pos) {
+            opened_positions.extend(self.reveal(adj));
+            if self.state != GameState::Active {
+                break;
+            }
+        }
+        opened_positions
+    }
+
+    /// Find a closed cell that can be proven safe or mined from the open
+    /// numbers and flags, preferring safe cells. Every hint given is counted.
+    pub fn hint(&mut self) -> Option<Deduction> {
+        if self.state != GameState::Active {
+            return None;
+        }
+        let hint = solver::deduce(self).iter().next();
+        if hint.is_some() {
+            self.hints += 1;
+        }
+        hint
+    }
+
+    /// Flag the cell as being potentially mined. With question marks on, a
+    /// flagged cell is marked with a question mark instead of unflagged and
+    /// the question mark is removed next. Cells of a finished game keep their
+    /// marks.
     pub fn flag_cell(&mut self, pos: Pos) -> Option<(Pos, Cell)> {
+        self.record(Action::Flag(pos), |board| board.cycle_mark(pos))
+    }
+
+    /// Move a closed cell to its next mark without logging a move. A cell is
+    /// marked with a question mark when no flag is left.
+    fn cycle_mark(&mut self, pos: Pos) -> Option<(Pos, Cell)> {
+        if self.state.is_over() {
+            return None;
+        }
+        let CellState::Closed {
+            flagged,
+            questioned,
+            ..
+        } = self.cells.get(&pos)?.state
+        else {
+            return None;
+        };
+        if questioned {
+            self.mark_question(pos, false)
+        } else if flagged && self.question_marks {
+            self.toggle_flag(pos);
+            self.mark_question(pos, true)
+        } else {
+            self.toggle_flag(pos)
+                .or_else(|| self.question_marks.then(|| self.mark_question(pos, true))?)
+        }
+    }
+
+    /// Add or remove the question mark of a closed cell.
+    fn mark_question(&mut self, pos: Pos, mark: bool) -> Option<(Pos, Cell)> {
+        let cell = self.cells.get_mut(&pos)?;
+        match &mut cell.state {
+            CellState::Closed { questioned, .. } => *questioned = mark,
+            _ => return None,
+        }
+        Some((pos, *cell))
+    }
+
+    /// Flag or unflag a cell without logging a move. Flagging removes a
+    /// question mark.
+    pub(crate) fn toggle_flag(&mut self, pos: Pos) -> Option<(Pos, Cell)> {
         match self.cells.get_mut(&pos) {
             Some(Cell {
-                state: CellState::Closed { flagged, .. },
+                state:
+                    CellState::Closed {
+                        flagged,
+                        questioned,
+                        ..
+                    },
                 ..
             }) => {
                 if (self.flagged < self.mined) || *flagged {
                     *flagged = !*flagged;
+                    *questioned = false;
                     if *flagged {
                         self.flagged += 1;
                     } else {
@@ -136,6 +350,17 @@ impl Board {
         }
     }
 
+    /// Flagging a flagged cell marks it with a question mark.
+    pub fn question_marks(&self) -> bool {
+        self.question_marks
+    }
+
+    /// Cycle right clicks through no mark, a flag and a question mark
+    /// instead of toggling the flag.
+    pub fn set_question_marks(&mut self, question_marks: bool) {
+        self.question_marks = question_marks;
+    }
+
     /// Get the state of the board.
     pub fn state(&self) -> &GameState {
         &self.state
@@ -146,18 +371,58 @@ impl Board {
         self.cells.len()
     }
 
-    /// Evaluate board to see if all non mined cells have been opened.
+    /// Create an active board with mines at the given positions.
+    #[cfg(test)]
+    pub(crate) fn with_mines(config: BoardConfig, mines: impl IntoIterator<Item = Pos>) -> Self {
+        Self::with_layout(config, rand::random(), mines)
+    }
+
+    /// Create an active board for the seed with mines at the given positions.
+    pub(crate) fn with_layout(
+        config: BoardConfig,
+        seed: u64,
+        mines: impl IntoIterator<Item = Pos>,
+    ) -> Self {
+        let mut board = Self::with_seed(config, seed);
+        board.place_mines(mines);
+        board.state = GameState::Active;
+        board
+    }
+
+    /// Evaluate board to see if the game is won by its win rule.
     fn is_win(&self) -> bool {
-        let opened_cells = self
-            .cells
-            .values()
-            .filter(|&&cell| matches!(cell.state, CellState::Open))
-            .count();
-        let total_open_for_win = self.cells.len() - self.mined;
-        total_open_for_win == opened_cells || self.all_mines_flagged()
+        let all_opened = self.opened == self.cells.len() - self.mined;
+        match self.config.win_rule() {
+            WinRule::Classic => all_opened,
+            WinRule::Flags => all_opened || self.all_mines_flagged(),
+        }
+    }
+
+    /// Flag the mines left closed in a classic win.
+    fn flag_mines(&mut self) {
+        for pos in self.closed_mines() {
+            if let Some(Cell {
+                state:
+                    CellState::Closed {
+                        flagged,
+                        questioned,
+                        ..
+                    },
+                ..
+            }) = self.cells.get_mut(&pos)
+            {
+                *flagged = true;
+                *questioned = false;
+            }
+        }
+        self.flagged = self.mined;
     }
 
     fn all_mines_flagged(&self) -> bool {
+        // Fewer flags than mines can't cover them, so skip the scan.
+        if self.flagged < self.mined {
+            return false;
+        }
         let flagged_mines = self
             .cells
             .values()
@@ -166,7 +431,8 @@ impl Board {
                     cell.state,
                     CellState::Closed {
                         flagged: true,
-                        mined: true
+                        mined: true,
+                        ..
                     }
                 )
             })
@@ -178,27 +444,273 @@ impl Board {
 
 #[cfg(test)]
 mod test {
-    use std::num::NonZeroU8;
-
     use super::*;
+    use crate::{
+        model::{Coord, Mines, TopologyMode},
+        solver::{Rule, Verdict},
+    };
+
+    fn test_config(board_max: Coord) -> BoardConfig {
+        BoardConfig::custom(board_max, board_max, Mines::Density(0.1)).unwrap()
+    }
 
     #[test]
     fn test_board_new() {
-        let board_max = NonZeroU8::new(5).unwrap();
-        let test_board = Board::new(board_max, board_max);
+        let board_max = Coord::new(5).unwrap();
+        let test_board = Board::new(test_config(board_max));
         dbg!(&test_board);
         assert_eq!(
-            test_board.cells.len() as u8,
+            test_board.cells.len() as u16,
             board_max.get() * board_max.get()
         );
     }
 
     #[test]
     fn test_mined_cells() {
-        let board_max = NonZeroU8::new(5).unwrap();
-        let mut board = Board::new(board_max, board_max);
-        board.mine_board(&(1, 1).try_into().unwrap());
+        let board_max = Coord::new(5).unwrap();
+        let mut board = Board::new(test_config(board_max));
+        board.mine_board(&(1, 1).try_into().unwrap()).unwrap();
         dbg!(&board);
-        assert!(board.mined > 0);
+        assert_eq!(board.mined, 2);
+    }
+
+    #[test]
+    fn test_seeded_layout() {
+        let config = test_config(Coord::new(10).unwrap());
+        let first = (5, 5).try_into().unwrap();
+        let mined_positions = |board: &Board| {
+            board
+                .positions()
+                .filter(|(_, cell)| cell.is_closed_and_mined())
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>()
+        };
+
+        let mut board = Board::with_seed(config, 42);
+        board.open_cell(first).unwrap();
+        let mut same_seed = Board::with_seed(config, 42);
+        same_seed.open_cell(first).unwrap();
+        let mut other_seed = Board::with_seed(config, 43);
+        other_seed.open_cell(first).unwrap();
+
+        assert_eq!(board.seed(), 42);
+        assert_eq!(mined_positions(&board), mined_positions(&same_seed));
+        assert_ne!(mined_positions(&board), mined_positions(&other_seed));
+    }
+
+    /// Create an active 5 x 5 board with mines at the given positions.
+    fn board_with_mines(mines: &[(u16, u16)]) -> Board {
+        Board::with_mines(
+            test_config(Coord::new(5).unwrap()),
+            mines.iter().map(|&p| Pos::try_from(p).unwrap()),
+        )
+    }
+
+    fn pos(x: u16, y: u16) -> Pos {
+        Pos::try_from((x, y)).unwrap()
+    }
+
+    #[test]
+    fn test_chord() {
+        let mut board = board_with_mines(&[(1, 1), (5, 5)]);
+        board.open_cell(pos(2, 2)).unwrap();
+
+        // Flag count does not match the adjacent mine count yet.
+        assert!(board.chord(pos(2, 2)).is_empty());
+        // Chording a closed cell does nothing.
+        assert!(board.chord(pos(3, 3)).is_empty());
+
+        board.flag_cell(pos(1, 1));
+        let opened = board.chord(pos(2, 2));
+        assert_eq!(opened.len(), 22);
+        assert_eq!(board.opened(), 23);
+        assert_eq!(board.state(), &GameState::Win);
+    }
+
+    #[test]
+    fn test_chord_wrong_flag() {
+        let mut board = board_with_mines(&[(1, 1), (5, 5)]);
+        board.open_cell(pos(2, 2)).unwrap();
+        board.flag_cell(pos(1, 2));
+
+        board.chord(pos(2, 2));
+        assert_eq!(board.state(), &GameState::Loss);
+        assert_eq!(
+            board.get_pos(&pos(1, 1)).map(|c| c.state),
+            Some(CellState::ExposedMine)
+        );
+    }
+
+    #[test]
+    fn test_finished_game() {
+        let mut board = board_with_mines(&[(1, 1), (5, 5)]);
+        board.open_cell(pos(2, 2)).unwrap();
+        board.open_cell(pos(1, 1)).unwrap();
+        assert_eq!(board.state(), &GameState::Loss);
+
+        // Opening every safe cell left doesn't turn the loss into a win.
+        assert!(board.open_cell(pos(3, 3)).unwrap().is_empty());
+        assert!(board.flag_cell(pos(5, 5)).is_none());
+        assert!(board.chord(pos(2, 2)).is_empty());
+        assert_eq!(board.state(), &GameState::Loss);
+        assert_eq!((board.opened(), board.flagged()), (1, 0));
+        assert_eq!(board.moves().len(), 2);
+    }
+
+    #[test]
+    fn test_win_rules() {
+        // Flagging every mine doesn't win a classic game.
+        let mut board = board_with_mines(&[(1, 1), (5, 5)]);
+        board.open_cell(pos(2, 2)).unwrap();
+        board.flag_cell(pos(1, 1));
+        board.flag_cell(pos(5, 5));
+        board.open_cell(pos(1, 2)).unwrap();
+        assert_eq!(board.state(), &GameState::Active);
+
+        // Opening every safe cell does, flagging the mines left closed.
+        board.flag_cell(pos(5, 5));
+        board.open_cell(pos(3, 3)).unwrap();
+        assert_eq!(board.state(), &GameState::Win);
+        assert_eq!(board.flagged(), 2);
+        assert_eq!(
+            board.get_pos(&pos(5, 5)).map(|c| c.to_string()).as_deref(),
+            Some("F")
+        );
+
+        let config = test_config(Coord::new(5).unwrap()).with_win_rule(WinRule::Flags);
+        let mut board = Board::with_mines(config, [pos(1, 1), pos(5, 5)]);
+        board.open_cell(pos(2, 2)).unwrap();
+        board.flag_cell(pos(1, 1));
+        board.flag_cell(pos(5, 5));
+        board.open_cell(pos(1, 2)).unwrap();
+        assert_eq!(board.state(), &GameState::Win);
+        assert_eq!(board.opened(), 2);
+    }
+
+    #[test]
+    fn test_question_marks() {
+        let mut board = board_with_mines(&[(1, 1)]);
+        let state = |board: &Board| board.get_pos(&pos(1, 2)).map(|c| c.to_string());
+
+        // Without question marks the flag is toggled.
+        board.flag_cell(pos(1, 2));
+        board.flag_cell(pos(1, 2));
+        assert_eq!(state(&board).as_deref(), Some("."));
+
+        board.set_question_marks(true);
+        board.flag_cell(pos(1, 2));
+        assert_eq!(state(&board).as_deref(), Some("F"));
+        board.flag_cell(pos(1, 2));
+        assert_eq!(state(&board).as_deref(), Some("?"));
+        assert_eq!(board.flagged(), 0);
+
+        // A question mark is used when no flag is left.
+        board.flag_cell(pos(5, 5));
+        board.flag_cell(pos(3, 3));
+        assert_eq!(
+            board.get_pos(&pos(3, 3)).map(|c| c.to_string()).as_deref(),
+            Some("?")
+        );
+
+        // Questioned cells are opened and flood filled.
+        board.open_cell(pos(1, 2)).unwrap();
+        assert_eq!(state(&board).as_deref(), Some("1"));
+        board.open_cell(pos(5, 1)).unwrap();
+        assert_eq!(board.opened(), 23);
+
+        board.flag_cell(pos(5, 5));
+        board.flag_cell(pos(5, 5));
+        assert_eq!(board.flagged(), 0);
+    }
+
+    #[test]
+    fn test_hint() {
+        let mut board = board_with_mines(&[(1, 1), (3, 1)]);
+        assert!(board.hint().is_none());
+
+        board.open_cell(pos(5, 5)).unwrap();
+        assert_eq!(
+            board.hint(),
+            Some(Deduction {
+                pos: pos(2, 1),
+                verdict: Verdict::Safe,
+                rule: Rule::Subset {
+                    subset: pos(4, 1),
+                    superset: pos(3, 2),
+                },
+            })
+        );
+
+        // No hints are given once the game is over.
+        board.open_cell(pos(2, 1)).unwrap();
+        assert_eq!(board.state(), &GameState::Win);
+        assert!(board.hint().is_none());
+        assert_eq!(board.hints(), 1);
+    }
+
+    #[test]
+    fn test_no_guess_generation() {
+        let config = BoardConfig::preset(crate::model::Difficulty::Beginner)
+            .unwrap()
+            .with_generation(GenerationMode::NoGuess);
+        let first = pos(5, 5);
+
+        for seed in 0..10 {
+            let mut board = Board::with_seed(config, seed);
+            board.open_cell(first).unwrap();
+            assert_eq!(board.mined(), 10);
+            assert!(solver::solve(&mut board.clone()));
+
+            // Same seed and first move give the same layout.
+            let mut same_seed = Board::with_seed(config, seed);
+            same_seed.open_cell(first).unwrap();
+            assert!(board
+                .positions()
+                .zip(same_seed.positions())
+                .all(|((_, a), (_, b))| a.state == b.state));
+        }
+    }
+
+    #[test]
+    fn test_no_guess_budget() {
+        // Every layout leaves two indistinguishable cells in the corner.
+        let config = BoardConfig::custom(
+            Coord::new(5).unwrap(),
+            Coord::new(2).unwrap(),
+            Mines::Count(1),
+        )
+        .unwrap()
+        .with_generation(GenerationMode::NoGuess);
+        let mut board = Board::new(config);
+        assert!(matches!(
+            board.open_cell(pos(5, 1)),
+            Err(GenerationError::NoGuessBudget(NO_GUESS_ATTEMPTS))
+        ));
+        assert_eq!(board.state(), &GameState::New);
+    }
+
+    #[test]
+    fn test_topologies() {
+        let torus = test_config(Coord::new(5).unwrap()).with_topology(TopologyMode::Torus);
+        let mut board = Board::with_mines(torus, [pos(1, 1)]);
+        // The mine is counted across the wrapped edges.
+        assert_eq!(board.get_pos(&pos(5, 5)).unwrap().adjacent_mines, 1);
+        assert_eq!(board.get_pos(&pos(2, 5)).unwrap().adjacent_mines, 1);
+        let opened = board.open_cell(pos(3, 3)).unwrap();
+        assert_eq!(opened.len(), 24);
+        assert_eq!(board.state(), &GameState::Win);
+
+        let first = pos(5, 5);
+        for topology in [TopologyMode::Hex, TopologyMode::Knight] {
+            let config = BoardConfig::default().with_topology(topology);
+            let mut board = Board::with_seed(config, 7);
+            board.open_cell(first).unwrap();
+            assert_eq!(board.mined(), 10);
+            // The first opened cell and its neighbours are never mined.
+            assert!(board
+                .neighbours(first)
+                .chain([first])
+                .all(|p| !board.get_pos(&p).unwrap().is_closed_and_mined()));
+        }
     }
 }
@@ -0,0 +1,224 @@
+//! 3BV, click counts and the speed and efficiency metrics of finished games.
+use super::{Action, Board, CellState, GameState, Pos};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+/// Clicks made during a game, including clicks that changed nothing.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clicks {
+    pub left: u32,
+    pub right: u32,
+    pub chord: u32,
+}
+
+impl Clicks {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+
+    /// Count the click made for the action.
+    pub(super) fn count(&mut self, action: Action) {
+        match action {
+            Action::Open(_) => self.left += 1,
+            Action::Flag(_) => self.right += 1,
+            Action::Chord(_) => self.chord += 1,
+        }
+    }
+}
+
+/// Speed and efficiency of a finished game.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    /// Minimum number of clicks to clear the board.
+    pub bbbv: u32,
+    /// 3BV cleared, less than the total for a lost game.
+    pub solved_bbbv: u32,
+    pub clicks: Clicks,
+    /// 3BV cleared per second.
+    pub bbbv_per_second: f64,
+    /// Index of speed, log(3BV) / log(seconds). Missing for games of a second
+    /// or less.
+    pub ios: Option<f64>,
+    /// Seconds divided by 3BV/s, lower is better. Missing when no 3BV was
+    /// cleared.
+    pub rqp: Option<f64>,
+    /// Percentage of 3BV cleared per click.
+    pub efficiency: f64,
+}
+
+impl Metrics {
+    pub(crate) fn new(bbbv: u32, solved_bbbv: u32, clicks: Clicks, duration: Duration) -> Self {
+        // Games shorter than a second count as a second.
+        let seconds = duration.as_secs_f64().max(1.);
+        let bbbv_per_second = f64::from(solved_bbbv) / seconds;
+        Self {
+            bbbv,
+            solved_bbbv,
+            clicks,
+            bbbv_per_second,
+            ios: (seconds > 1. && solved_bbbv > 0)
+                .then(|| f64::from(solved_bbbv).ln() / seconds.ln()),
+            rqp: (bbbv_per_second > 0.).then(|| seconds / bbbv_per_second),
+            efficiency: match clicks.total() {
+                0 => 0.,
+                total => 100. * f64::from(solved_bbbv) / f64::from(total),
+            },
+        }
+    }
+}
+
+impl Display for Metrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.solved_bbbv == self.bbbv {
+            write!(f, "3BV {}", self.bbbv)?;
+        } else {
+            write!(f, "3BV {}/{}", self.solved_bbbv, self.bbbv)?;
+        }
+        write!(f, ", 3BV/s {:.2}", self.bbbv_per_second)?;
+        if let Some(ios) = self.ios {
+            write!(f, ", IOS {ios:.2}")?;
+        }
+        if let Some(rqp) = self.rqp {
+            write!(f, ", RQP {rqp:.2}")?;
+        }
+        write!(
+            f,
+            ", clicks {}, efficiency {:.0}%",
+            self.clicks.total(),
+            self.efficiency
+        )
+    }
+}
+
+impl Board {
+    /// 3BV of the mine layout, `None` until mines are placed.
+    pub fn bbbv(&self) -> Option<u32> {
+        (self.state != GameState::New).then(|| self.count_bbbv().0)
+    }
+
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    /// Metrics of a won or lost game over the time on its clock.
+    pub fn metrics(&self) -> Option<Metrics> {
+        matches!(self.state, GameState::Win | GameState::Loss).then(|| {
+            let (bbbv, solved_bbbv) = self.count_bbbv();
+            Metrics::new(bbbv, solved_bbbv, self.clicks, self.elapsed())
+        })
+    }
+
+    /// Count the 3BV of the layout and the 3BV cleared so far. Every opening,
+    /// a region of cells without adjacent mines with its numbered border, is
+    /// one click and so is every numbered cell outside an opening.
+    fn count_bbbv(&self) -> (u32, u32) {
+        let is_mine = |pos: &Pos| {
+            self.cells.get(pos).is_some_and(|cell| {
+                matches!(
+                    cell.state,
+                    CellState::Closed { mined: true, .. } | CellState::ExposedMine
+                )
+            })
+        };
+        let is_open = |pos: &Pos| {
+            self.cells
+                .get(pos)
+                .is_some_and(|cell| cell.state == CellState::Open)
+        };
+
+        let (mut bbbv, mut solved) = (0, 0);
+        let mut in_opening = HashSet::new();
+        for (start, cell) in self.cells.iter() {
+            if cell.adjacent_mines > 0 || is_mine(&start) || in_opening.contains(&start) {
+                continue;
+            }
+            bbbv += 1;
+            let mut opened = false;
+            let mut stack = vec![start];
+            while let Some(pos) = stack.pop() {
+                if !in_opening.insert(pos) {
+                    continue;
+                }
+                let zero = self
+                    .cells
+                    .get(&pos)
+                    .is_some_and(|cell| cell.adjacent_mines == 0);
+                opened |= zero && is_open(&pos);
+                if zero {
+                    stack.extend(self.neighbours(pos).filter(|p| !in_opening.contains(p)));
+                }
+            }
+            solved += u32::from(opened);
+        }
+        for (pos, _) in self.cells.iter() {
+            if !is_mine(&pos) && !in_opening.contains(&pos) {
+                bbbv += 1;
+                solved += u32::from(is_open(&pos));
+            }
+        }
+        (bbbv, solved)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        clock::test::ManualTime,
+        model::{BoardConfig, Coord, Mines},
+    };
+
+    fn pos(x: u16, y: u16) -> Pos {
+        Pos::try_from((x, y)).unwrap()
+    }
+
+    /// Create an active 5 x 5 board with mines at the given positions.
+    fn board(mines: &[(u16, u16)]) -> Board {
+        let five = Coord::new(5).unwrap();
+        let config = BoardConfig::custom(five, five, Mines::Count(mines.len())).unwrap();
+        Board::with_mines(config, mines.iter().map(|&(x, y)| pos(x, y)))
+    }
+
+    #[test]
+    fn test_bbbv() {
+        // One opening covering the board and a mine in each of two corners.
+        assert_eq!(board(&[(1, 1), (5, 5)]).bbbv(), Some(1));
+        // A wall of mines splits the board into an opening on the left and
+        // numbered cells on the right that are not next to an opening.
+        let wall = board(&[(4, 1), (4, 2), (4, 3), (4, 4), (4, 5)]);
+        assert_eq!(wall.bbbv(), Some(1 + 5));
+        assert_eq!(Board::with_seed(BoardConfig::default(), 1).bbbv(), None);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let time = ManualTime::new();
+        let mut board = board(&[(1, 1), (5, 5)]);
+        board.set_time_source(time.clone());
+        board.flag_cell(pos(2, 2));
+        board.open_cell(pos(3, 3)).unwrap();
+        time.advance(4_000);
+        assert_eq!(board.metrics(), None);
+        board.chord(pos(3, 3));
+        board.open_cell(pos(1, 1)).unwrap();
+        time.advance(1_000);
+        assert_eq!(board.state(), &GameState::Loss);
+
+        let clicks = board.clicks();
+        assert_eq!((clicks.left, clicks.right, clicks.chord), (2, 1, 1));
+        let metrics = board.metrics().unwrap();
+        assert_eq!((metrics.bbbv, metrics.solved_bbbv), (1, 1));
+        assert_eq!(metrics.bbbv_per_second, 0.25);
+        assert_eq!(metrics.ios, Some(0.));
+        assert_eq!(metrics.rqp, Some(16.));
+        assert_eq!(metrics.efficiency, 25.);
+
+        // Clicks after the game is over are not counted.
+        board.open_cell(pos(4, 4)).unwrap();
+        assert_eq!(board.clicks().total(), 4);
+    }
+}
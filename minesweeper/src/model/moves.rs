@@ -0,0 +1,348 @@
+//! Move log for undoing and redoing moves in practice games.
+use super::{Board, Cell, GameState, GenerationError, Pos};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+/// Player action that changed the board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "content")]
+pub enum Action {
+    Open(Pos),
+    Flag(Pos),
+    Chord(Pos),
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Open(pos) => write!(f, "open {} {}", pos.x, pos.y),
+            Action::Flag(pos) => write!(f, "flag {} {}", pos.x, pos.y),
+            Action::Chord(pos) => write!(f, "chord {} {}", pos.x, pos.y),
+        }
+    }
+}
+
+/// Board counters changed by a move.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub(super) struct Counters {
+    pub(super) state: GameState,
+    pub(super) opened: usize,
+    pub(super) flagged: usize,
+    pub(super) mined: usize,
+}
+
+impl Counters {
+    fn of(board: &Board) -> Self {
+        Self {
+            state: board.state,
+            opened: board.opened,
+            flagged: board.flagged,
+            mined: board.mined,
+        }
+    }
+
+    fn apply(self, board: &mut Board) {
+        board.state = self.state;
+        board.opened = self.opened;
+        board.flagged = self.flagged;
+        board.mined = self.mined;
+    }
+}
+
+/// A recorded move with every cell it changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Move {
+    pub action: Action,
+    /// Milliseconds on the game clock when the move was played.
+    #[serde(default)]
+    pub at: u64,
+    /// Changed cells with their state before and after the move, only kept
+    /// in practice games.
+    cells: Vec<(Pos, Cell, Cell)>,
+    before: Counters,
+    after: Counters,
+}
+
+/// Failure to undo or redo a move.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum UndoError {
+    #[error("Undo is only available in practice games")]
+    Ranked,
+    #[error("No move to undo")]
+    NothingToUndo,
+    #[error("No move to redo")]
+    NothingToRedo,
+}
+
+impl Board {
+    /// Apply a change to the board and log it as a move when any cell changed.
+    /// Only practice moves keep their changed cells for undo. A new move
+    /// clears the moves that can be redone. Every click before the game is
+    /// over is counted.
+    pub(super) fn record<T>(&mut self, action: Action, apply: impl FnOnce(&mut Self) -> T) -> T {
+        if matches!(self.state, GameState::New | GameState::Active) {
+            self.clicks.count(action);
+        }
+        self.cells.track_changes();
+        let before = Counters::of(self);
+        let result = apply(self);
+
+        // Ranked moves only need to know if anything changed, unless the
+        // changes are sent to subscribers.
+        let (changed, cells) = if self.config.practice() || self.subscribers.any() {
+            let cells = self.cells.changes();
+            (!cells.is_empty(), cells)
+        } else {
+            (self.cells.changed(), vec![])
+        };
+        let after = Counters::of(self);
+        self.emit(cells.iter().copied(), before, after);
+        if changed {
+            let at = self.elapsed().as_millis() as u64;
+            self.moves.push(Move {
+                action,
+                at,
+                cells: if self.config.practice() {
+                    cells
+                } else {
+                    vec![]
+                },
+                before,
+                after,
+            });
+            self.undone.clear();
+        }
+        result
+    }
+
+    /// Play an action. Returns the changed cells.
+    pub fn play(&mut self, action: Action) -> Result<Vec<(Pos, Cell)>, GenerationError> {
+        match action {
+            Action::Open(pos) => self.open_cell(pos),
+            Action::Flag(pos) => Ok(self.flag_cell(pos).into_iter().collect()),
+            Action::Chord(pos) => Ok(self.chord(pos)),
+        }
+    }
+
+    /// Moves played so far, oldest first.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.config.practice() && !self.moves.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.config.practice() && !self.undone.is_empty()
+    }
+
+    /// Take back the last move, including a losing one. Returns the cells
+    /// restored to their previous state. Undoing the first move keeps the
+    /// mine layout.
+    pub fn undo(&mut self) -> Result<Vec<(Pos, Cell)>, UndoError> {
+        if !self.config.practice() {
+            return Err(UndoError::Ranked);
+        }
+        let undone = self.moves.pop().ok_or(UndoError::NothingToUndo)?;
+        let cells = undone
+            .cells
+            .iter()
+            .map(|&(pos, before, _)| (pos, before))
+            .collect::<Vec<_>>();
+        self.cells.extend(cells.iter().copied());
+        undone.before.apply(self);
+        self.sync_clock();
+        self.emit(
+            undone
+                .cells
+                .iter()
+                .map(|&(pos, before, after)| (pos, after, before)),
+            undone.after,
+            undone.before,
+        );
+        self.undone.push(undone);
+        Ok(cells)
+    }
+
+    /// Play the last undone move again. Returns the changed cells.
+    pub fn redo(&mut self) -> Result<Vec<(Pos, Cell)>, UndoError> {
+        if !self.config.practice() {
+            return Err(UndoError::Ranked);
+        }
+        let redone = self.undone.pop().ok_or(UndoError::NothingToRedo)?;
+        let cells = redone
+            .cells
+            .iter()
+            .map(|&(pos, _, after)| (pos, after))
+            .collect::<Vec<_>>();
+        self.cells.extend(cells.iter().copied());
+        redone.after.apply(self);
+        self.sync_clock();
+        self.emit(redone.cells.iter().copied(), redone.before, redone.after);
+        self.moves.push(redone);
+        Ok(cells)
+    }
+
+    /// Count time again when undoing the end of the game and stop counting
+    /// when redoing it. Undoing the first move pauses the clock until the
+    /// next first move.
+    fn sync_clock(&mut self) {
+        match self.state {
+            GameState::Win | GameState::Loss => self.clock.stop(),
+            GameState::New => self.clock.pause(),
+            GameState::Active => self.clock.resume(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        clock::test::ManualTime,
+        model::{BoardConfig, CellState, Coord, Mines},
+    };
+    use std::time::Duration;
+
+    fn pos(x: u16, y: u16) -> Pos {
+        Pos::try_from((x, y)).unwrap()
+    }
+
+    /// Create an active 5 x 5 practice board with mines at the given positions.
+    fn practice_board(mines: &[(u16, u16)]) -> Board {
+        let five = Coord::new(5).unwrap();
+        let config = BoardConfig::custom(five, five, Mines::Count(mines.len()))
+            .unwrap()
+            .with_practice(true);
+        Board::with_mines(config, mines.iter().map(|&(x, y)| pos(x, y)))
+    }
+
+    fn states(board: &Board) -> Vec<CellState> {
+        board.positions().map(|(_, cell)| cell.state).collect()
+    }
+
+    #[test]
+    fn test_undo_flood_fill_and_flag() {
+        // The mines wall off the corner from the flood fill.
+        let mut board = practice_board(&[(1, 2), (2, 1)]);
+        let start = states(&board);
+
+        let opened = board.open_cell(pos(3, 3)).unwrap();
+        let after_open = states(&board);
+        board.flag_cell(pos(1, 2));
+        assert_eq!(board.moves().len(), 2);
+        assert_eq!(board.flagged(), 1);
+
+        assert_eq!(
+            board.undo().unwrap(),
+            vec![(pos(1, 2), *board.cells.get(&pos(1, 2)).unwrap())]
+        );
+        assert_eq!(board.flagged(), 0);
+        assert_eq!(states(&board), after_open);
+
+        assert_eq!(board.undo().unwrap().len(), opened.len());
+        assert_eq!(board.opened(), 0);
+        assert_eq!(states(&board), start);
+        assert_eq!(board.undo(), Err(UndoError::NothingToUndo));
+
+        board.redo().unwrap();
+        assert_eq!(board.opened(), opened.len());
+        assert_eq!(states(&board), after_open);
+    }
+
+    #[test]
+    fn test_undo_loss() {
+        let time = ManualTime::new();
+        let mut board = practice_board(&[(1, 1), (5, 5)]);
+        board.set_time_source(time.clone());
+        time.advance(100);
+        board.open_cell(pos(2, 2)).unwrap();
+        time.advance(1_500);
+        board.open_cell(pos(5, 5)).unwrap();
+        assert_eq!(board.state(), &GameState::Loss);
+        assert_eq!(board.moves()[1].at, 1_500);
+        time.advance(2_000);
+        assert_eq!(board.elapsed(), Duration::from_millis(1_500));
+
+        let restored = board.undo().unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(board.state(), &GameState::Active);
+        assert!(board
+            .positions()
+            .all(|(_, c)| c.state != CellState::ExposedMine));
+
+        time.advance(20);
+        assert_eq!(board.elapsed(), Duration::from_millis(1_520));
+
+        board.redo().unwrap();
+        assert_eq!(board.state(), &GameState::Loss);
+        time.advance(20);
+        assert_eq!(board.elapsed(), Duration::from_millis(1_520));
+    }
+
+    #[test]
+    fn test_undo_first_move() {
+        let five = Coord::new(5).unwrap();
+        let config = BoardConfig::custom(five, five, Mines::Count(10))
+            .unwrap()
+            .with_practice(true);
+        let time = ManualTime::new();
+        let mut board = Board::with_seed(config, 5);
+        board.set_time_source(time.clone());
+        let opened = board.open_cell(pos(3, 3)).unwrap();
+        time.advance(300);
+        // The move keeps the opened cells, not the mines placed for it.
+        assert_eq!(board.moves()[0].cells.len(), opened.len());
+        let mines = |board: &Board| {
+            board
+                .positions()
+                .filter(|(_, c)| c.is_closed_and_mined())
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>()
+        };
+        let layout = mines(&board);
+
+        board.undo().unwrap();
+        assert_eq!(board.state(), &GameState::New);
+        assert_eq!((board.opened(), board.mined()), (0, 10));
+        // The clock waits for the next first move.
+        assert!(!board.clock.is_running());
+        time.advance(5_000);
+        assert_eq!(board.elapsed(), Duration::from_millis(300));
+        board.open_cell(pos(3, 3)).unwrap();
+        assert!(board.clock.is_running());
+        assert_eq!(mines(&board), layout);
+        assert_eq!(board.mined(), 10);
+
+        // Ranked moves keep no cells since they can't be undone.
+        let mut ranked = Board::with_seed(config.with_practice(false), 5);
+        ranked.open_cell(pos(3, 3)).unwrap();
+        ranked.flag_cell(layout[0]);
+        assert_eq!(ranked.moves().len(), 2);
+        assert!(ranked.moves().iter().all(|m| m.cells.is_empty()));
+    }
+
+    #[test]
+    fn test_new_move_clears_redo() {
+        let mut board = practice_board(&[(1, 1), (5, 5)]);
+        board.open_cell(pos(2, 2)).unwrap();
+        board.undo().unwrap();
+        assert!(board.can_redo());
+
+        board.flag_cell(pos(1, 1));
+        assert!(!board.can_redo());
+        assert_eq!(board.redo(), Err(UndoError::NothingToRedo));
+    }
+
+    #[test]
+    fn test_undo_ranked() {
+        let mut board = practice_board(&[(1, 1)]);
+        board.config = board.config.with_practice(false);
+        board.open_cell(pos(2, 2)).unwrap();
+        assert!(!board.can_undo());
+        assert_eq!(board.undo(), Err(UndoError::Ranked));
+        assert_eq!(board.opened(), 1);
+    }
+}
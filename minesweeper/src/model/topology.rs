@@ -0,0 +1,289 @@
+//! Neighbourhoods of the cells of a board.
+use super::Pos;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::Deref;
+
+/// Shape of a board: which cells it holds and which cells count as the
+/// neighbours of a cell for the mine counts and the flood fill. Boards pick
+/// one of the shapes of [`TopologyMode`] or take their own with
+/// [`Board::with_topology`](super::Board::with_topology).
+pub trait Topology: Debug + Send + Sync {
+    /// Distinct positions adjacent to the position on a board of the size,
+    /// without the position itself. A position is expected to be a neighbour
+    /// of each of its neighbours.
+    fn neighbours(&self, pos: Pos, columns: u16, rows: u16) -> Neighbours;
+
+    /// The position is a cell of a board of the size.
+    fn contains(&self, pos: Pos, columns: u16, rows: u16) -> bool {
+        pos.x.get() <= columns && pos.y.get() <= rows
+    }
+
+    /// Columns of text a row is shifted by when the board is printed.
+    fn indent(&self, _row: u16) -> usize {
+        0
+    }
+}
+
+/// Classic square grid where the eight surrounding cells are neighbours.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Grid;
+
+impl Topology for Grid {
+    fn neighbours(&self, pos: Pos, columns: u16, rows: u16) -> Neighbours {
+        offset_neighbours(self, pos, columns, rows, &SQUARE)
+    }
+}
+
+/// Square grid whose edges wrap around, so every cell has eight neighbours
+/// on boards of at least three by three.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Torus;
+
+impl Topology for Torus {
+    fn neighbours(&self, pos: Pos, columns: u16, rows: u16) -> Neighbours {
+        let wrap = |n: u16, delta: i32, size: u16| {
+            (i32::from(n) - 1 + delta).rem_euclid(i32::from(size)) as u16 + 1
+        };
+        let mut neighbours = Neighbours::default();
+        for &(dx, dy) in &SQUARE {
+            let Ok(p) =
+                Pos::try_from((wrap(pos.x.get(), dx, columns), wrap(pos.y.get(), dy, rows)))
+            else {
+                continue;
+            };
+            if p != pos && !neighbours.contains(&p) {
+                neighbours.push(p);
+            }
+        }
+        neighbours.positions[..neighbours.len].sort();
+        neighbours
+    }
+}
+
+/// Hexagonal grid of rows where even rows are shifted right by half a cell,
+/// so every cell has six neighbours.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Hex;
+
+impl Topology for Hex {
+    fn neighbours(&self, pos: Pos, columns: u16, rows: u16) -> Neighbours {
+        let offsets = if pos.y.get().is_multiple_of(2) {
+            [(-1, 0), (1, 0), (0, -1), (1, -1), (0, 1), (1, 1)]
+        } else {
+            [(-1, 0), (1, 0), (-1, -1), (0, -1), (-1, 1), (0, 1)]
+        };
+        offset_neighbours(self, pos, columns, rows, &offsets)
+    }
+
+    fn indent(&self, row: u16) -> usize {
+        usize::from(row.is_multiple_of(2))
+    }
+}
+
+/// Square grid where the cells a knight's move away are neighbours.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Knight;
+
+impl Topology for Knight {
+    fn neighbours(&self, pos: Pos, columns: u16, rows: u16) -> Neighbours {
+        let offsets = [
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ];
+        offset_neighbours(self, pos, columns, rows, &offsets)
+    }
+}
+
+/// Offsets of the eight cells around a cell of a square grid.
+const SQUARE: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Positions at the offsets from the position that are on the board.
+fn offset_neighbours(
+    topology: &impl Topology,
+    pos: Pos,
+    columns: u16,
+    rows: u16,
+    offsets: &[(i32, i32)],
+) -> Neighbours {
+    let mut neighbours = Neighbours::default();
+    let positions = offsets.iter().filter_map(|&(dx, dy)| {
+        let x = u16::try_from(i32::from(pos.x.get()) + dx).ok()?;
+        let y = u16::try_from(i32::from(pos.y.get()) + dy).ok()?;
+        Pos::try_from((x, y)).ok()
+    });
+    for p in positions.filter(|&p| topology.contains(p, columns, rows)) {
+        neighbours.push(p);
+    }
+    neighbours
+}
+
+/// Neighbours of a cell, kept inline since every topology has at most eight.
+#[derive(Debug, Copy, Clone)]
+pub struct Neighbours {
+    positions: [Pos; 8],
+    len: usize,
+}
+
+impl Neighbours {
+    /// Add a neighbour.
+    ///
+    /// # Panics
+    ///
+    /// Panics when there are eight neighbours already.
+    pub fn push(&mut self, pos: Pos) {
+        self.positions[self.len] = pos;
+        self.len += 1;
+    }
+}
+
+impl Default for Neighbours {
+    fn default() -> Self {
+        let origin = Pos::try_from((1, 1)).expect("No zero");
+        Self {
+            positions: [origin; 8],
+            len: 0,
+        }
+    }
+}
+
+impl Deref for Neighbours {
+    type Target = [Pos];
+
+    fn deref(&self) -> &[Pos] {
+        &self.positions[..self.len]
+    }
+}
+
+impl IntoIterator for Neighbours {
+    type Item = Pos;
+    type IntoIter = std::iter::Take<std::array::IntoIter<Pos, 8>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.positions.into_iter().take(self.len)
+    }
+}
+
+/// Topology of a board configuration.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    clap::ValueEnum,
+)]
+pub enum TopologyMode {
+    /// The classic square grid.
+    #[default]
+    Grid,
+    /// A square grid wrapping around its edges.
+    Torus,
+    /// A hexagonal grid.
+    Hex,
+    /// A square grid of knight's move neighbours.
+    Knight,
+}
+
+impl TopologyMode {
+    pub(super) fn topology(self) -> &'static dyn Topology {
+        match self {
+            TopologyMode::Grid => &Grid,
+            TopologyMode::Torus => &Torus,
+            TopologyMode::Hex => &Hex,
+            TopologyMode::Knight => &Knight,
+        }
+    }
+}
+
+impl Topology for TopologyMode {
+    fn neighbours(&self, pos: Pos, columns: u16, rows: u16) -> Neighbours {
+        self.topology().neighbours(pos, columns, rows)
+    }
+
+    fn contains(&self, pos: Pos, columns: u16, rows: u16) -> bool {
+        self.topology().contains(pos, columns, rows)
+    }
+
+    fn indent(&self, row: u16) -> usize {
+        self.topology().indent(row)
+    }
+}
+
+impl Display for TopologyMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopologyMode::Grid => write!(f, "Grid"),
+            TopologyMode::Torus => write!(f, "Torus"),
+            TopologyMode::Hex => write!(f, "Hexagonal"),
+            TopologyMode::Knight => write!(f, "Knight"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pos(x: u16, y: u16) -> Pos {
+        (x, y).try_into().unwrap()
+    }
+
+    #[test]
+    fn test_neighbours() {
+        assert_eq!(Grid.neighbours(pos(2, 1), 10, 10).len(), 5);
+        assert_eq!(Grid.neighbours(pos(10, 10), 10, 10).len(), 3);
+
+        let corner = Torus.neighbours(pos(1, 1), 10, 10);
+        assert_eq!(corner.len(), 8);
+        assert!(corner.contains(&pos(10, 10)) && corner.contains(&pos(2, 10)));
+        // Both sides wrap to the same cell on a board two cells wide.
+        assert_eq!(Torus.neighbours(pos(1, 1), 2, 10).len(), 5);
+
+        assert_eq!(Hex.neighbours(pos(3, 3), 10, 10).len(), 6);
+        assert!(Hex.neighbours(pos(3, 3), 10, 10).contains(&pos(2, 2)));
+        assert!(Hex.neighbours(pos(3, 2), 10, 10).contains(&pos(4, 3)));
+        assert_eq!(Hex.neighbours(pos(1, 1), 10, 10).len(), 2);
+
+        assert_eq!(Knight.neighbours(pos(5, 5), 10, 10).len(), 8);
+        assert_eq!(
+            *Knight.neighbours(pos(1, 1), 10, 10),
+            [pos(2, 3), pos(3, 2)]
+        );
+
+        // Neighbourhoods are symmetric.
+        for mode in [
+            TopologyMode::Grid,
+            TopologyMode::Torus,
+            TopologyMode::Hex,
+            TopologyMode::Knight,
+        ] {
+            for a in (1..=5).flat_map(|x| (1..=4).map(move |y| pos(x, y))) {
+                for b in mode.neighbours(a, 5, 4) {
+                    assert!(mode.neighbours(b, 5, 4).contains(&a), "{mode} {a:?} {b:?}");
+                }
+            }
+        }
+    }
+}
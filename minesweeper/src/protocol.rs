@@ -0,0 +1,339 @@
+//! Line based JSON protocol for scripts and bots. Each request is a JSON
+//! object on one line and gets a JSON response on one line. Cells are sent as
+//! a player sees them, so mines and the counts of closed cells stay hidden
+//! until the game ends.
+use crate::model::{
+    Board, BoardConfig, Cell, CellState, ConfigError, Coord, Difficulty, GameState,
+    GenerationError, GenerationMode, Mines, Pos, TopologyMode, WinRule,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Mine density of a custom board when no mine count is given.
+const DEFAULT_DENSITY: f64 = 0.1;
+
+/// Request read from a line, tagged by its `command` field.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "command", rename_all = "camelCase")]
+pub enum Request {
+    /// Start a game, on the board of the current game unless another is given.
+    New(NewGame),
+    Open(Pos),
+    Flag(Pos),
+    Chord(Pos),
+    /// Send every cell of the board.
+    State,
+    Quit,
+}
+
+/// Board of a new game. A preset difficulty or custom dimensions replace the
+/// current board, the mine count defaults to a tenth of the cells.
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NewGame {
+    pub difficulty: Option<Difficulty>,
+    pub columns: Option<Coord>,
+    pub rows: Option<Coord>,
+    pub mines: Option<usize>,
+    pub seed: Option<u64>,
+    pub no_guess: Option<bool>,
+    pub topology: Option<TopologyMode>,
+    pub win_rule: Option<WinRule>,
+    /// Flag a flagged cell again to mark it with a question mark.
+    pub question_marks: Option<bool>,
+}
+
+impl NewGame {
+    /// Configuration of the new game, starting from the current one.
+    fn config(&self, current: &BoardConfig) -> Result<BoardConfig, ConfigError> {
+        let config = match (self.columns, self.rows, self.difficulty) {
+            (Some(columns), Some(rows), _) => BoardConfig::custom(
+                columns,
+                rows,
+                self.mines
+                    .map(Mines::Count)
+                    .unwrap_or(Mines::Density(DEFAULT_DENSITY)),
+            )?,
+            (_, _, Some(difficulty)) => BoardConfig::preset(difficulty).unwrap_or(*current),
+            _ => *current,
+        };
+        let generation = match self.no_guess {
+            Some(true) => GenerationMode::NoGuess,
+            Some(false) => GenerationMode::Random,
+            None => current.generation(),
+        };
+        Ok(config
+            .with_generation(generation)
+            .with_practice(current.practice())
+            .with_topology(self.topology.unwrap_or(current.topology()))
+            .with_win_rule(self.win_rule.unwrap_or(current.win_rule())))
+    }
+}
+
+/// Cell sent in a response.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct CellUpdate {
+    #[serde(flatten)]
+    pub pos: Pos,
+    #[serde(flatten)]
+    pub cell: Cell,
+}
+
+/// Counters of the game.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Counters {
+    pub columns: Coord,
+    pub rows: Coord,
+    pub mines: usize,
+    pub opened: usize,
+    pub flagged: usize,
+    pub elapsed_ms: u128,
+    /// Seed of the mine layout, only sent once the game ends.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// Response written for every request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    /// Cells changed by the request, every cell for `new` and `state`, and
+    /// every mine once the game ends.
+    pub cells: Vec<CellUpdate>,
+    pub state: GameState,
+    pub counters: Counters,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Request that could not be carried out.
+#[derive(Debug, Error)]
+pub enum ProtocolError {
+    #[error("Invalid request: {0}")]
+    Request(#[from] serde_json::Error),
+    #[error("Invalid board: {0}")]
+    Config(#[from] ConfigError),
+    #[error("Failed to generate board: {0}")]
+    Generation(#[from] GenerationError),
+    #[error("The game is over, start a new game")]
+    GameOver,
+}
+
+/// Game played through the protocol.
+#[derive(Debug)]
+pub struct Session {
+    board: Board,
+}
+
+impl Session {
+    pub fn new(board: Board) -> Self {
+        Self { board }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Parse a line and carry out its request. Returns the response and
+    /// whether the player quit.
+    pub fn handle_line(&mut self, line: &str) -> (Response, bool) {
+        match serde_json::from_str::<Request>(line) {
+            Ok(request) => (self.handle(&request), request == Request::Quit),
+            Err(err) => (self.respond(vec![], Some(err.into())), false),
+        }
+    }
+
+    /// Carry out the request.
+    pub fn handle(&mut self, request: &Request) -> Response {
+        let before = *self.board.state();
+        if before.is_over()
+            && matches!(
+                request,
+                Request::Open(_) | Request::Flag(_) | Request::Chord(_)
+            )
+        {
+            return self.respond(vec![], Some(ProtocolError::GameOver));
+        }
+        let changed = match request {
+            Request::New(new) => match new.config(self.board.config()) {
+                Ok(config) => {
+                    let question_marks = new.question_marks.unwrap_or(self.board.question_marks());
+                    self.board = match new.seed {
+                        Some(seed) => Board::with_seed(config, seed),
+                        None => Board::new(config),
+                    };
+                    self.board.set_question_marks(question_marks);
+                    self.all_cells()
+                }
+                Err(err) => return self.respond(vec![], Some(err.into())),
+            },
+            Request::Open(pos) => match self.board.open_cell(*pos) {
+                Ok(changed) => changed,
+                Err(err) => return self.respond(vec![], Some(err.into())),
+            },
+            Request::Flag(pos) => self.board.flag_cell(*pos).into_iter().collect(),
+            Request::Chord(pos) => self.board.chord(*pos),
+            Request::State | Request::Quit => self.all_cells(),
+        };
+        let mut cells = changed
+            .into_iter()
+            .map(|(pos, cell)| CellUpdate { pos, cell })
+            .collect::<Vec<_>>();
+        if self.board.state().is_over() && !before.is_over() {
+            cells.extend(self.all_cells().into_iter().filter_map(|(pos, cell)| {
+                let mined = matches!(
+                    cell.state,
+                    CellState::ExposedMine | CellState::Closed { mined: true, .. }
+                );
+                mined.then_some(CellUpdate { pos, cell })
+            }));
+        }
+        self.respond(cells, None)
+    }
+
+    /// Response with the cells as the player sees them.
+    fn respond(&self, cells: Vec<CellUpdate>, error: Option<ProtocolError>) -> Response {
+        let state = *self.board.state();
+        let over = state.is_over();
+        Response {
+            cells: cells
+                .into_iter()
+                .map(|update| CellUpdate {
+                    cell: if over {
+                        update.cell
+                    } else {
+                        hidden(update.cell)
+                    },
+                    ..update
+                })
+                .collect(),
+            state,
+            counters: Counters {
+                columns: self.board.total_columns(),
+                rows: self.board.total_rows(),
+                mines: self.board.config().mines(),
+                opened: self.board.opened(),
+                flagged: self.board.flagged(),
+                elapsed_ms: self.board.elapsed().as_millis(),
+                seed: over.then(|| self.board.seed()),
+            },
+            error: error.map(|err| err.to_string()),
+        }
+    }
+
+    fn all_cells(&self) -> Vec<(Pos, Cell)> {
+        self.board
+            .positions()
+            .map(|(pos, &cell)| (pos, cell))
+            .collect()
+    }
+}
+
+/// Cell with its mine and mine count cleared while it is closed.
+fn hidden(cell: Cell) -> Cell {
+    match cell.state {
+        CellState::Closed {
+            flagged,
+            questioned,
+            ..
+        } => Cell {
+            state: CellState::Closed {
+                flagged,
+                mined: false,
+                questioned,
+            },
+            adjacent_mines: 0,
+        },
+        _ => cell,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn pos(x: u16, y: u16) -> Pos {
+        (x, y).try_into().unwrap()
+    }
+
+    fn send(session: &mut Session, line: &str) -> serde_json::Value {
+        serde_json::to_value(session.handle_line(line).0).unwrap()
+    }
+
+    #[test]
+    fn test_protocol() {
+        let config = BoardConfig::custom(
+            Coord::new(4).unwrap(),
+            Coord::new(4).unwrap(),
+            Mines::Count(1),
+        )
+        .unwrap();
+        let mut session = Session::new(Board::with_mines(config, [pos(4, 4)]));
+
+        let response = send(&mut session, r#"{"command":"flag","x":4,"y":4}"#);
+        assert_eq!(
+            response["cells"],
+            json!([{"x": 4, "y": 4, "state": {"type": "Closed", "content": {"flagged": true, "mined": false, "questioned": false}}, "adjacentMines": 0}])
+        );
+        assert_eq!(response["state"], "Active");
+        assert_eq!(response["counters"]["flagged"], 1);
+        assert!(response["counters"].get("seed").is_none());
+
+        let response = send(&mut session, r#"{"command":"state"}"#);
+        assert_eq!(response["cells"].as_array().unwrap().len(), 16);
+        assert!(!response.to_string().contains(r#""mined":true"#));
+
+        let response = send(&mut session, r#"{"command":"open","x":1,"y":1}"#);
+        assert_eq!(response["state"], "Win");
+        assert_eq!(response["counters"]["opened"], 15);
+        assert!(response["counters"]["seed"].is_u64());
+        let cells = response["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), 16);
+        assert_eq!(cells[15]["state"]["content"]["mined"], true);
+
+        let response = send(&mut session, r#"{"command":"open","x":0,"y":1}"#);
+        assert!(response["error"].is_string());
+        assert_eq!(response["state"], "Win");
+
+        let response = send(&mut session, r#"{"command":"new","difficulty":"Expert"}"#);
+        assert_eq!(response["state"], "New");
+        assert_eq!(response["counters"]["mines"], 99);
+        assert_eq!(response["cells"].as_array().unwrap().len(), 480);
+
+        let response = send(
+            &mut session,
+            r#"{"command":"new","columns":9,"rows":9,"mines":90}"#,
+        );
+        assert!(response["error"].is_string());
+        assert_eq!(session.board().config().mines(), 99);
+
+        assert!(session.handle_line(r#"{"command":"quit"}"#).1);
+    }
+
+    #[test]
+    fn test_moves_after_loss() {
+        let config = BoardConfig::custom(
+            Coord::new(4).unwrap(),
+            Coord::new(4).unwrap(),
+            Mines::Count(1),
+        )
+        .unwrap();
+        let mut session = Session::new(Board::with_mines(config, [pos(4, 4)]));
+
+        let response = send(&mut session, r#"{"command":"open","x":4,"y":4}"#);
+        assert_eq!(response["state"], "Loss");
+
+        for command in ["open", "flag", "chord"] {
+            let response = send(
+                &mut session,
+                &format!(r#"{{"command":"{command}","x":1,"y":1}}"#),
+            );
+            assert_eq!(response["error"], "The game is over, start a new game");
+            assert_eq!(response["state"], "Loss");
+            assert_eq!(response["cells"], json!([]));
+        }
+        assert_eq!(session.board().opened(), 0);
+    }
+}
@@ -0,0 +1,314 @@
+//! Game replays recorded from the move log, playback and verification.
+use crate::{
+    clock::deserialize_time,
+    model::{
+        Action, Board, BoardConfig, BoardEvent, Cell, CellState, GameState, GenerationError, Pos,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::{sync::mpsc::Receiver, time::Duration};
+use thiserror::Error;
+
+/// An action with the milliseconds on the game clock when it was played.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimedAction {
+    pub at: u64,
+    pub action: Action,
+}
+
+/// Everything needed to play a game again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub config: BoardConfig,
+    pub seed: u64,
+    /// Mined positions, empty when no cell was opened.
+    pub mines: Vec<Pos>,
+    pub actions: Vec<TimedAction>,
+    pub outcome: GameState,
+    /// Time of play recorded for the game.
+    #[serde(deserialize_with = "deserialize_time")]
+    pub duration: Duration,
+    /// Flags were cycled through question marks.
+    #[serde(default)]
+    pub question_marks: bool,
+}
+
+/// Failure to verify a replay.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ReplayError {
+    #[error(transparent)]
+    Generation(#[from] GenerationError),
+    #[error("Mine layout does not match the seed")]
+    Layout,
+    #[error("Move {0} is timed before the move preceding it")]
+    Unordered(usize),
+    #[error("Move {0} was played after the game ended")]
+    GameOver(usize),
+    #[error("Move {0} did not change the board")]
+    NoEffect(usize),
+    #[error("Recorded outcome {recorded:?} but the replay ends in {replayed:?}")]
+    Outcome {
+        recorded: GameState,
+        replayed: GameState,
+    },
+    #[error("Recorded duration of {claimed:?} is shorter than the {played:?} taken by the moves")]
+    Duration { claimed: Duration, played: Duration },
+}
+
+impl Replay {
+    /// Record the game played on the board so far.
+    pub fn new(board: &Board) -> Self {
+        Self {
+            config: *board.config(),
+            seed: board.seed(),
+            mines: board
+                .positions()
+                .filter(|(_, cell)| {
+                    matches!(
+                        cell.state,
+                        CellState::Closed { mined: true, .. } | CellState::ExposedMine
+                    )
+                })
+                .map(|(pos, _)| pos)
+                .collect(),
+            actions: board
+                .moves()
+                .iter()
+                .map(|m| TimedAction {
+                    at: m.at,
+                    action: m.action,
+                })
+                .collect(),
+            outcome: *board.state(),
+            duration: board.elapsed(),
+            question_marks: board.question_marks(),
+        }
+    }
+
+    /// Board with the recorded mine layout before the first move.
+    pub fn board(&self) -> Board {
+        let mut board = if self.mines.is_empty() {
+            Board::with_seed(self.config, self.seed)
+        } else {
+            Board::with_layout(self.config, self.seed, self.mines.iter().copied())
+        };
+        board.set_question_marks(self.question_marks);
+        board
+    }
+
+    /// Play the replay on a fresh board and confirm the mine layout comes
+    /// from the seed, every move changes the board, the recorded outcome is
+    /// reached and the recorded duration covers the time between moves.
+    pub fn verify(&self) -> Result<(), ReplayError> {
+        if let Some(&TimedAction {
+            action: Action::Open(first),
+            ..
+        }) = self.actions.first()
+        {
+            let mut generated = Board::with_seed(self.config, self.seed);
+            generated.open_cell(first)?;
+            if Self::new(&generated).mines != self.mines {
+                return Err(ReplayError::Layout);
+            }
+        }
+
+        let mut board = self.board();
+        let mut last = 0;
+        for (index, timed) in self.actions.iter().enumerate() {
+            let number = index + 1;
+            if timed.at < last {
+                return Err(ReplayError::Unordered(number));
+            }
+            if !matches!(board.state(), GameState::New | GameState::Active) {
+                return Err(ReplayError::GameOver(number));
+            }
+            let moves = board.moves().len();
+            board.play(timed.action)?;
+            if board.moves().len() == moves {
+                return Err(ReplayError::NoEffect(number));
+            }
+            last = timed.at;
+        }
+
+        if *board.state() != self.outcome {
+            return Err(ReplayError::Outcome {
+                recorded: self.outcome,
+                replayed: *board.state(),
+            });
+        }
+        let played = Duration::from_millis(last);
+        if self.duration < played {
+            return Err(ReplayError::Duration {
+                claimed: self.duration,
+                played,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Plays the actions of a replay in order on a board.
+#[derive(Debug, Clone)]
+pub struct Player {
+    replay: Replay,
+    board: Board,
+    next: usize,
+    position: u64,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            board: replay.board(),
+            replay,
+            next: 0,
+            position: 0,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Receive the events of the board as the replay is played.
+    pub fn subscribe(&mut self) -> Receiver<BoardEvent> {
+        self.board.subscribe()
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Milliseconds played from the first move.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Number of actions played.
+    pub fn played(&self) -> usize {
+        self.next
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.actions.len()
+    }
+
+    /// Play the next action and move the position to its time. Returns the
+    /// changed cells.
+    pub fn step(&mut self) -> Vec<(Pos, Cell)> {
+        let Some(timed) = self.replay.actions.get(self.next).copied() else {
+            return vec![];
+        };
+        self.next += 1;
+        self.position = self.position.max(timed.at);
+        self.board.play(timed.action).unwrap_or_default()
+    }
+
+    /// Move the position forward by the given milliseconds and play every
+    /// action reached. Returns the changed cells.
+    pub fn advance(&mut self, millis: u64) -> Vec<(Pos, Cell)> {
+        self.position += millis;
+        let mut changed = vec![];
+        while self
+            .replay
+            .actions
+            .get(self.next)
+            .is_some_and(|timed| timed.at <= self.position)
+        {
+            changed.extend(self.step());
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::Difficulty;
+
+    fn pos(x: u16, y: u16) -> Pos {
+        Pos::try_from((x, y)).unwrap()
+    }
+
+    /// Play the board until it is won by opening every safe cell.
+    fn won_replay() -> Replay {
+        let config = BoardConfig::preset(Difficulty::Beginner).unwrap();
+        let mut board = Board::with_seed(config, 11);
+        board.open_cell(pos(5, 5)).unwrap();
+        let mined = board
+            .positions()
+            .find(|(_, cell)| {
+                cell.state
+                    == CellState::Closed {
+                        flagged: false,
+                        mined: true,
+                        questioned: false,
+                    }
+            })
+            .map(|(pos, _)| pos)
+            .unwrap();
+        board.flag_cell(mined);
+        let safe = board
+            .positions()
+            .filter(|(_, cell)| cell.state == CellState::default())
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        for pos in safe {
+            board.open_cell(pos).unwrap();
+        }
+        assert_eq!(board.state(), &GameState::Win);
+        Replay::new(&board)
+    }
+
+    #[test]
+    fn test_verify() {
+        let replay = won_replay();
+        assert_eq!(replay.mines.len(), 10);
+        assert_eq!(replay.verify(), Ok(()));
+
+        let mut lost = replay.clone();
+        lost.outcome = GameState::Loss;
+        assert!(matches!(lost.verify(), Err(ReplayError::Outcome { .. })));
+
+        let mut moved = replay.clone();
+        moved.mines[0] = pos(5, 5);
+        assert_eq!(moved.verify(), Err(ReplayError::Layout));
+
+        let mut repeated = replay.clone();
+        let first = repeated.actions[0];
+        repeated.actions.insert(1, first);
+        assert_eq!(repeated.verify(), Err(ReplayError::NoEffect(2)));
+
+        let mut slow = replay;
+        slow.duration = Duration::from_secs(30);
+        slow.actions.last_mut().unwrap().at = 60_500;
+        assert_eq!(
+            slow.verify(),
+            Err(ReplayError::Duration {
+                claimed: Duration::from_secs(30),
+                played: Duration::from_millis(60_500)
+            })
+        );
+    }
+
+    #[test]
+    fn test_player() {
+        let mut replay = won_replay();
+        for (at, timed) in (0..).step_by(100).zip(&mut replay.actions) {
+            timed.at = at;
+        }
+        let total = replay.actions.len();
+        let mut player = Player::new(replay);
+
+        assert!(!player.step().is_empty());
+        assert_eq!(player.board().state(), &GameState::Active);
+        player.advance(150);
+        assert_eq!((player.played(), player.position()), (2, 150));
+
+        player.advance(u64::MAX / 2);
+        assert!(player.is_finished());
+        assert_eq!(player.played(), total);
+        assert_eq!(player.board().state(), &GameState::Win);
+        assert!(player.step().is_empty());
+    }
+}
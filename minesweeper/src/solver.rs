@@ -0,0 +1,279 @@
+//! Logical solver working only from the player visible state of a board:
+//! open numbers, flags and closed cells.
+mod probability;
+
+pub use probability::{probabilities, Probability};
+
+use crate::model::{Board, CellState, GameState, Pos};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{Display, Formatter},
+};
+
+/// Whether a deduced cell is safe to open or mined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub enum Verdict {
+    Safe,
+    Mine,
+}
+
+/// Rule proving a deduction, referring to the open numbers it was made from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "content")]
+pub enum Rule {
+    /// The number at `source` has all its mines flagged, or exactly as many
+    /// closed neighbours as missing mines.
+    SingleCell { source: Pos },
+    /// The closed neighbours of the number at `subset` are all neighbours of
+    /// the number at `superset`, so the remaining neighbours of `superset`
+    /// hold the difference of their missing mines.
+    Subset { subset: Pos, superset: Pos },
+}
+
+/// A closed cell proven safe or mined and the rule proving it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct Deduction {
+    pub pos: Pos,
+    pub verdict: Verdict,
+    pub rule: Rule,
+}
+
+impl Display for Deduction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let verdict = match self.verdict {
+            Verdict::Safe => "safe",
+            Verdict::Mine => "a mine",
+        };
+        write!(f, "{} {} is {verdict}", self.pos.x, self.pos.y)?;
+        match self.rule {
+            Rule::SingleCell { source } => {
+                write!(f, ", see the number at {} {}", source.x, source.y)
+            }
+            Rule::Subset { subset, superset } => write!(
+                f,
+                ", compare the numbers at {} {} and {} {}",
+                subset.x, subset.y, superset.x, superset.y
+            ),
+        }
+    }
+}
+
+/// Closed cells proven safe or mined, each with the first rule found to
+/// prove it. Flags are trusted, so a wrong flag can lead to wrong deductions.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Deductions {
+    pub safe: BTreeMap<Pos, Rule>,
+    pub mines: BTreeMap<Pos, Rule>,
+}
+
+impl Deductions {
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+
+    /// All deductions, safe cells first.
+    pub fn iter(&self) -> impl Iterator<Item = Deduction> + '_ {
+        let safe = self.safe.iter().map(|(&pos, &rule)| Deduction {
+            pos,
+            verdict: Verdict::Safe,
+            rule,
+        });
+        let mines = self.mines.iter().map(|(&pos, &rule)| Deduction {
+            pos,
+            verdict: Verdict::Mine,
+            rule,
+        });
+        safe.chain(mines)
+    }
+
+    fn insert(&mut self, cells: impl IntoIterator<Item = Pos>, verdict: Verdict, rule: Rule) {
+        let deduced = match verdict {
+            Verdict::Safe => &mut self.safe,
+            Verdict::Mine => &mut self.mines,
+        };
+        for pos in cells {
+            deduced.entry(pos).or_insert(rule);
+        }
+    }
+}
+
+/// Closed and unflagged neighbours of the open number at `source` and how
+/// many of them must be mined.
+#[derive(Debug)]
+struct Constraint {
+    source: Pos,
+    cells: BTreeSet<Pos>,
+    mines: usize,
+}
+
+/// Collect a constraint for every open number that still has closed
+/// neighbours.
+fn constraints(board: &Board) -> Vec<Constraint> {
+    board
+        .positions()
+        .filter(|(_, cell)| cell.state == CellState::Open && cell.adjacent_mines > 0)
+        .filter_map(|(pos, cell)| {
+            let mut flagged = 0;
+            let mut cells = BTreeSet::new();
+            for adj in board.neighbours(pos) {
+                match board.get_pos(&adj).map(|c| c.state) {
+                    Some(CellState::Closed { flagged: true, .. }) => flagged += 1,
+                    Some(CellState::Closed { .. }) => {
+                        cells.insert(adj);
+                    }
+                    _ => (),
+                }
+            }
+            (!cells.is_empty()).then(|| Constraint {
+                source: pos,
+                cells,
+                mines: usize::from(cell.adjacent_mines).saturating_sub(flagged),
+            })
+        })
+        .collect()
+}
+
+/// Deduce safe and mined cells from single numbers and from pairs of
+/// numbers where the closed neighbours of one are a subset of the other.
+pub fn deduce(board: &Board) -> Deductions {
+    let constraints = constraints(board);
+    let mut deductions = Deductions::default();
+
+    // A number with all its mines flagged or with as many closed
+    // neighbours as missing mines.
+    for constraint in &constraints {
+        let rule = Rule::SingleCell {
+            source: constraint.source,
+        };
+        if constraint.mines == 0 {
+            deductions.insert(constraint.cells.iter().copied(), Verdict::Safe, rule);
+        } else if constraint.mines == constraint.cells.len() {
+            deductions.insert(constraint.cells.iter().copied(), Verdict::Mine, rule);
+        }
+    }
+
+    // Index constraints by cell so only overlapping pairs are compared.
+    let mut by_cell = HashMap::<Pos, Vec<usize>>::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        for &pos in &constraint.cells {
+            by_cell.entry(pos).or_default().push(index);
+        }
+    }
+
+    for (index, smaller) in constraints.iter().enumerate() {
+        let Some(first) = smaller.cells.first() else {
+            continue;
+        };
+        for &other in by_cell.get(first).into_iter().flatten() {
+            let larger = &constraints[other];
+            if other == index
+                || larger.cells.len() <= smaller.cells.len()
+                || !smaller.cells.is_subset(&larger.cells)
+            {
+                continue;
+            }
+            let rest = larger.cells.difference(&smaller.cells).copied();
+            let rule = Rule::Subset {
+                subset: smaller.source,
+                superset: larger.source,
+            };
+            match larger.mines.checked_sub(smaller.mines) {
+                Some(0) => deductions.insert(rest, Verdict::Safe, rule),
+                Some(mines) if mines == larger.cells.len() - smaller.cells.len() => {
+                    deductions.insert(rest, Verdict::Mine, rule)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    deductions
+}
+
+/// Play the board using only logical deductions. Returns `true` when the
+/// game is won without guessing.
+pub(crate) fn solve(board: &mut Board) -> bool {
+    while *board.state() == GameState::Active {
+        let deductions = deduce(board);
+        if deductions.is_empty() {
+            break;
+        }
+        for &pos in deductions.mines.keys() {
+            board.toggle_flag(pos);
+        }
+        for &pos in deductions.safe.keys() {
+            board.reveal(pos);
+        }
+    }
+    *board.state() == GameState::Win
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{BoardConfig, Coord, Mines, WinRule};
+
+    fn pos(x: u16, y: u16) -> Pos {
+        Pos::try_from((x, y)).unwrap()
+    }
+
+    fn board(columns: u16, rows: u16, mines: &[(u16, u16)]) -> Board {
+        let config = BoardConfig::custom(
+            Coord::new(columns).unwrap(),
+            Coord::new(rows).unwrap(),
+            Mines::Count(1),
+        )
+        .unwrap()
+        // Boards opened up to a win keep their mines unflagged.
+        .with_win_rule(WinRule::Flags);
+        Board::with_mines(config, mines.iter().map(|&(x, y)| pos(x, y)))
+    }
+
+    #[test]
+    fn test_single_cell_deductions() {
+        // The mine is the only closed neighbour of the numbers around it.
+        let mut board = board(4, 4, &[(1, 4), (2, 4)]);
+        board.reveal(pos(4, 1));
+        let deductions = deduce(&board);
+        assert_eq!(
+            deductions.mines.keys().copied().collect::<Vec<_>>(),
+            [pos(1, 4), pos(2, 4)]
+        );
+        assert_eq!(
+            deductions.mines[&pos(2, 4)],
+            Rule::SingleCell { source: pos(1, 3) }
+        );
+        assert!(deductions.safe.is_empty());
+    }
+
+    #[test]
+    fn test_subset_deduction() {
+        // The closed neighbours of the numbers at each end are a subset of
+        // their inner neighbour's, which leaves the middle cell safe.
+        let mut board = board(5, 3, &[(2, 3), (4, 3)]);
+        board.reveal(pos(1, 1));
+        let deductions = deduce(&board);
+        assert_eq!(
+            deductions.iter().collect::<Vec<_>>(),
+            [Deduction {
+                pos: pos(3, 3),
+                verdict: Verdict::Safe,
+                rule: Rule::Subset {
+                    subset: pos(1, 2),
+                    superset: pos(2, 2),
+                },
+            }]
+        );
+        assert!(solve(&mut board));
+    }
+
+    #[test]
+    fn test_guess_required() {
+        // Two closed cells sharing a single mine can't be told apart.
+        let mut board = board(5, 2, &[(1, 2)]);
+        board.reveal(pos(5, 1));
+        assert!(!solve(&mut board));
+        assert_eq!(board.state(), &GameState::Active);
+    }
+}
@@ -0,0 +1,466 @@
+//! Mine probabilities for closed cells, exact unless a frontier group has
+//! too many layouts to enumerate.
+use super::{constraints, Constraint};
+use crate::model::{Board, CellState, GameState, Pos};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// Most steps spent enumerating the layouts of a frontier group before it
+/// is estimated instead, which keeps hints quick on any board.
+const SEARCH_STEPS: usize = 1 << 20;
+
+/// Mine probability of a closed cell.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+pub struct Probability {
+    /// Chance between 0 and 1 that the cell is mined.
+    pub value: f64,
+    /// The value counts every layout of the numbers next to the cell. Cells
+    /// of groups with too many layouts, and the cells away from the numbers
+    /// that share their estimate, are not exact.
+    pub exact: bool,
+}
+
+/// Mine probability for every closed and unflagged cell.
+///
+/// The frontier of closed cells next to open numbers is split into groups
+/// that share no numbers. Every mine layout of a group is enumerated and
+/// the groups are weighted by the ways the remaining mines fit in the other
+/// closed cells. Groups with too many layouts to enumerate get the same
+/// estimate as the cells away from the numbers. Flags are trusted, and an
+/// empty map is returned when they leave no consistent layout or the game is
+/// over.
+pub fn probabilities(board: &Board) -> HashMap<Pos, Probability> {
+    probabilities_within(board, SEARCH_STEPS)
+}
+
+/// Probabilities enumerating each group in at most `steps` steps.
+fn probabilities_within(board: &Board, steps: usize) -> HashMap<Pos, Probability> {
+    if matches!(board.state(), GameState::Win | GameState::Loss) {
+        return HashMap::new();
+    }
+
+    let closed = board
+        .positions()
+        .filter(|(_, cell)| matches!(cell.state, CellState::Closed { flagged: false, .. }))
+        .map(|(pos, _)| pos)
+        .collect::<Vec<_>>();
+    let Some(remaining) = board.config().mines().checked_sub(board.flagged()) else {
+        return HashMap::new();
+    };
+
+    let constraints = constraints(board);
+    let all_groups = groups(&constraints);
+    let groups = all_groups
+        .iter()
+        .filter_map(|group| Group::enumerate(&constraints, group, steps))
+        .collect::<Vec<_>>();
+    // Cells of groups left out count as interior cells.
+    let estimated = groups.len() < all_groups.len();
+    let frontier = groups.iter().map(|g| g.cells.len()).sum::<usize>();
+    let interior = closed.len() - frontier;
+
+    // Ways to place the mines left for the interior cells, by the number of
+    // mines in the frontier. Kept as logarithms relative to the largest so
+    // big boards don't overflow.
+    let ln_factorials = ln_factorials(interior);
+    let ln_ways = (0..=frontier)
+        .map(|mines| {
+            remaining
+                .checked_sub(mines)
+                .filter(|&left| left <= interior)
+                .map(|left| ln_binomial(&ln_factorials, interior, left))
+        })
+        .collect::<Vec<_>>();
+    let Some(ln_max) = ln_ways.iter().flatten().copied().reduce(f64::max) else {
+        return HashMap::new();
+    };
+    let interior_ways = ln_ways
+        .iter()
+        .map(|ln| ln.map_or(0., |ln| (ln - ln_max).exp()))
+        .collect::<Vec<_>>();
+
+    let mut probabilities = HashMap::new();
+
+    for (index, group) in groups.iter().enumerate() {
+        let others = groups
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| other != index)
+            .fold(vec![1.], |weights, (_, g)| convolve(&weights, &g.weights));
+
+        // Weight of each mine count of this group over every layout of the
+        // other groups and the interior.
+        let weights = (0..group.weights.len())
+            .map(|mines| {
+                others
+                    .iter()
+                    .enumerate()
+                    .map(|(other_mines, w)| w * interior_ways[mines + other_mines])
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>();
+        let total = group
+            .weights
+            .iter()
+            .zip(&weights)
+            .map(|(w, v)| w * v)
+            .sum::<f64>();
+        if total == 0. {
+            return HashMap::new();
+        }
+
+        for (cell_index, &pos) in group.cells.iter().enumerate() {
+            let mined = group
+                .mined
+                .iter()
+                .zip(&weights)
+                .map(|(mined, v)| mined[cell_index] * v)
+                .sum::<f64>();
+            let value = mined / total;
+            probabilities.insert(pos, Probability { value, exact: true });
+        }
+    }
+
+    if interior > 0 {
+        let all = groups
+            .iter()
+            .fold(vec![1.], |weights, g| convolve(&weights, &g.weights));
+        let (mined, total) =
+            all.iter()
+                .enumerate()
+                .fold((0., 0.), |(mined, total), (frontier_mines, w)| {
+                    let weight = w * interior_ways[frontier_mines];
+                    let left = remaining.saturating_sub(frontier_mines) as f64;
+                    (mined + weight * left / interior as f64, total + weight)
+                });
+        if total == 0. {
+            return HashMap::new();
+        }
+        let frontier = groups
+            .iter()
+            .flat_map(|g| g.cells.iter())
+            .collect::<BTreeSet<_>>();
+        let probability = Probability {
+            value: mined / total,
+            exact: !estimated,
+        };
+        for pos in closed.iter().filter(|pos| !frontier.contains(pos)) {
+            probabilities.insert(*pos, probability);
+        }
+    }
+
+    probabilities
+}
+
+/// Mine layouts of a group of frontier cells.
+#[derive(Debug)]
+struct Group {
+    cells: Vec<Pos>,
+    /// Number of layouts by mine count, scaled so the largest is 1.
+    weights: Vec<f64>,
+    /// Number of layouts mining each cell by mine count, on the same scale.
+    mined: Vec<Vec<f64>>,
+}
+
+impl Group {
+    /// Enumerate every layout of the cells of the given constraints that
+    /// satisfies all of them. Returns `None` when that takes more than
+    /// `steps` steps.
+    fn enumerate(constraints: &[Constraint], group: &[usize], steps: usize) -> Option<Self> {
+        // Cells ordered by the constraints they appear in so constraints
+        // complete early and prune the search.
+        let mut cells = Vec::<Pos>::new();
+        for &index in group {
+            for &pos in &constraints[index].cells {
+                if !cells.contains(&pos) {
+                    cells.push(pos);
+                }
+            }
+        }
+        let cell_constraints = cells
+            .iter()
+            .map(|pos| {
+                group
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &index)| constraints[index].cells.contains(pos))
+                    .map(|(local, _)| local)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut search = Search {
+            cell_constraints: &cell_constraints,
+            missing: group.iter().map(|&i| constraints[i].mines).collect(),
+            unassigned: group.iter().map(|&i| constraints[i].cells.len()).collect(),
+            layout: vec![false; cells.len()],
+            weights: vec![0.; cells.len() + 1],
+            mined: vec![vec![0.; cells.len()]; cells.len() + 1],
+            steps,
+        };
+        search.assign(0, 0)?;
+
+        let scale = search.weights.iter().copied().fold(0., f64::max);
+        if scale > 0. {
+            search.weights.iter_mut().for_each(|w| *w /= scale);
+            search.mined.iter_mut().flatten().for_each(|m| *m /= scale);
+        }
+
+        Some(Self {
+            cells,
+            weights: search.weights,
+            mined: search.mined,
+        })
+    }
+}
+
+/// Backtracking state for enumerating the layouts of a group.
+struct Search<'a> {
+    /// Constraints of the group each cell appears in.
+    cell_constraints: &'a [Vec<usize>],
+    /// Mines still needed by each constraint.
+    missing: Vec<usize>,
+    /// Cells without a value in each constraint.
+    unassigned: Vec<usize>,
+    layout: Vec<bool>,
+    weights: Vec<f64>,
+    mined: Vec<Vec<f64>>,
+    /// Steps left before the search gives up.
+    steps: usize,
+}
+
+impl Search<'_> {
+    /// Try both values of the cell and the cells after it. Returns `None`
+    /// when the search runs out of steps.
+    fn assign(&mut self, cell: usize, mines: usize) -> Option<()> {
+        self.steps = self.steps.checked_sub(1)?;
+        if cell == self.layout.len() {
+            self.weights[mines] += 1.;
+            for (count, _) in self.mined[mines]
+                .iter_mut()
+                .zip(&self.layout)
+                .filter(|(_, &mined)| mined)
+            {
+                *count += 1.;
+            }
+            return Some(());
+        }
+
+        for mined in [false, true] {
+            let constraints = &self.cell_constraints[cell];
+            let fits = constraints.iter().all(|&c| {
+                let missing = self.missing[c];
+                if mined {
+                    missing > 0
+                } else {
+                    missing < self.unassigned[c]
+                }
+            });
+            if !fits {
+                continue;
+            }
+            for &c in constraints {
+                self.unassigned[c] -= 1;
+                if mined {
+                    self.missing[c] -= 1;
+                }
+            }
+            self.layout[cell] = mined;
+            self.assign(cell + 1, mines + usize::from(mined))?;
+            for &c in constraints {
+                self.unassigned[c] += 1;
+                if mined {
+                    self.missing[c] += 1;
+                }
+            }
+        }
+        self.layout[cell] = false;
+        Some(())
+    }
+}
+
+/// Split constraints into groups connected by shared cells.
+fn groups(constraints: &[Constraint]) -> Vec<Vec<usize>> {
+    let mut by_cell = HashMap::<Pos, Vec<usize>>::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        for &pos in &constraint.cells {
+            by_cell.entry(pos).or_default().push(index);
+        }
+    }
+
+    let mut visited = vec![false; constraints.len()];
+    let mut groups = Vec::new();
+    for start in 0..constraints.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut group = vec![start];
+        let mut next = 0;
+        while let Some(&index) = group.get(next) {
+            next += 1;
+            for pos in &constraints[index].cells {
+                for &other in &by_cell[pos] {
+                    if !visited[other] {
+                        visited[other] = true;
+                        group.push(other);
+                    }
+                }
+            }
+        }
+        groups.push(group);
+    }
+    groups
+}
+
+/// Combine two distributions of layouts by mine count.
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+/// Natural logarithms of `0!` to `n!`.
+fn ln_factorials(n: usize) -> Vec<f64> {
+    let mut ln = Vec::with_capacity(n + 1);
+    ln.push(0.);
+    for i in 1..=n {
+        ln.push(ln[i - 1] + (i as f64).ln());
+    }
+    ln
+}
+
+fn ln_binomial(ln_factorials: &[f64], n: usize, k: usize) -> f64 {
+    ln_factorials[n] - ln_factorials[k] - ln_factorials[n - k]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{BoardConfig, Coord, Mines};
+
+    fn pos(x: u16, y: u16) -> Pos {
+        Pos::try_from((x, y)).unwrap()
+    }
+
+    fn board(columns: u16, rows: u16, mines: &[(u16, u16)]) -> Board {
+        let config = BoardConfig::custom(
+            Coord::new(columns).unwrap(),
+            Coord::new(rows).unwrap(),
+            Mines::Count(mines.len()),
+        )
+        .unwrap();
+        Board::with_mines(config, mines.iter().map(|&(x, y)| pos(x, y)))
+    }
+
+    /// Probabilities from every placement of the remaining mines over the
+    /// closed cells that agrees with the open numbers.
+    fn brute_force(board: &Board) -> HashMap<Pos, f64> {
+        let closed = board
+            .positions()
+            .filter(|(_, c)| matches!(c.state, CellState::Closed { flagged: false, .. }))
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        let remaining = board.config().mines() - board.flagged();
+        let constraints = constraints(board);
+        let mut counts = vec![0_u64; closed.len()];
+        let mut total = 0_u64;
+        for layout in 0_u32..1 << closed.len() {
+            if layout.count_ones() as usize != remaining {
+                continue;
+            }
+            let mined = |pos: &Pos| {
+                let index = closed.iter().position(|p| p == pos).unwrap();
+                layout & 1 << index != 0
+            };
+            if constraints
+                .iter()
+                .all(|c| c.cells.iter().filter(|p| mined(p)).count() == c.mines)
+            {
+                total += 1;
+                for (index, count) in counts.iter_mut().enumerate() {
+                    *count += u64::from(layout & 1 << index != 0);
+                }
+            }
+        }
+        closed
+            .into_iter()
+            .zip(counts)
+            .map(|(pos, count)| (pos, count as f64 / total as f64))
+            .collect()
+    }
+
+    #[test]
+    fn test_fifty_fifty() {
+        let mut board = board(5, 2, &[(1, 2)]);
+        board.reveal(pos(5, 1));
+        let probabilities = probabilities(&board);
+        assert_eq!(probabilities.len(), 2);
+        assert_eq!(probabilities[&pos(1, 1)].value, 0.5);
+        assert_eq!(probabilities[&pos(1, 2)].value, 0.5);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let config = BoardConfig::custom(
+            Coord::new(5).unwrap(),
+            Coord::new(4).unwrap(),
+            Mines::Count(5),
+        )
+        .unwrap();
+        for seed in 0..20 {
+            let mut board = Board::with_seed(config, seed);
+            board.open_cell(pos(3, 2)).unwrap();
+            if *board.state() != GameState::Active {
+                continue;
+            }
+            let expected = brute_force(&board);
+            let probabilities = probabilities(&board);
+            assert_eq!(probabilities.len(), expected.len());
+            for (pos, p) in expected {
+                assert!(
+                    (probabilities[&pos].value - p).abs() < 1e-9,
+                    "{pos:?} seed {seed}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_steps() {
+        let config = BoardConfig::custom(
+            Coord::new(5).unwrap(),
+            Coord::new(4).unwrap(),
+            Mines::Count(5),
+        )
+        .unwrap();
+        let mut board = Board::with_seed(config, 3);
+        board.open_cell(pos(3, 2)).unwrap();
+        let exact = probabilities(&board);
+        assert!(exact.values().all(|p| p.exact));
+        assert!(exact.values().any(|p| p.value == 0. || p.value == 1.));
+
+        // Without steps to enumerate the groups every closed cell gets the
+        // density of the remaining mines and is marked as estimated.
+        let estimated = probabilities_within(&board, 1);
+        assert_eq!(estimated.len(), exact.len());
+        let density = 5. / estimated.len() as f64;
+        assert!(estimated
+            .values()
+            .all(|p| !p.exact && (p.value - density).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_new_board() {
+        let board = Board::new(BoardConfig::default());
+        let probabilities = probabilities(&board);
+        assert_eq!(probabilities.len(), 81);
+        assert!(probabilities
+            .values()
+            .all(|p| p.exact && (p.value - 10. / 81.).abs() < 1e-9));
+    }
+}
@@ -0,0 +1,539 @@
+//! Full screen terminal interface played with the keyboard or the mouse.
+use minesweeper::{
+    history::{load_wins, record_game, save_game, save_replay, save_win, DataDir, WinHistory},
+    model::{Board, Cell, CellState, Coord, GameState, Pos},
+    replay::Replay,
+    solver::{Deduction, Verdict},
+};
+use ratatui::{
+    crossterm::{
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+            MouseButton, MouseEvent, MouseEventKind,
+        },
+        execute,
+    },
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, Row, Table},
+    DefaultTerminal, Frame,
+};
+use std::{io, time::Duration};
+
+/// Interval between redraws of the timer.
+const TICK: Duration = Duration::from_millis(200);
+
+/// Terminal columns taken by a cell.
+const CELL_WIDTH: u16 = 3;
+
+/// Keys shown under the board.
+const KEYS: &str =
+    "arrows/hjkl move, space open, f flag, c chord, ? hint, n new, w wins, s save, q quit";
+
+/// Play the game on the board full screen until the player quits.
+pub fn run(dir: &DataDir, board: Board) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = execute!(io::stdout(), EnableMouseCapture)
+        .and_then(|()| Tui::new(dir, board).run(&mut terminal));
+    let restored = execute!(io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+    result.and(restored)
+}
+
+/// Window shown over the board.
+enum Overlay {
+    None,
+    /// Win or loss of the finished game.
+    Outcome,
+    /// Wins of the leaderboard at the index.
+    Scoreboard(WinHistory, usize),
+}
+
+/// Game state of the terminal interface.
+struct Tui<'a> {
+    dir: &'a DataDir,
+    board: Board,
+    /// Cell selected with the keyboard.
+    cursor: Pos,
+    overlay: Overlay,
+    /// Last hint given, cleared by the next move.
+    hint: Option<Deduction>,
+    /// Message shown under the board.
+    status: String,
+    /// Cells of the board in the last frame, to find the clicked cell.
+    board_area: Rect,
+    /// The game was saved to be resumed.
+    saved: bool,
+}
+
+impl<'a> Tui<'a> {
+    fn new(dir: &'a DataDir, mut board: Board) -> Self {
+        board.resume_clock();
+        let one = Coord::MIN;
+        Self {
+            dir,
+            board,
+            cursor: Pos { x: one, y: one },
+            overlay: Overlay::None,
+            hint: None,
+            status: String::new(),
+            board_area: Rect::default(),
+            saved: false,
+        }
+    }
+
+    /// Handle input and redraw until the player quits.
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let quit = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key(key.code),
+                Event::Mouse(mouse) => {
+                    self.on_mouse(mouse);
+                    false
+                }
+                _ => false,
+            };
+            if quit {
+                self.end_game();
+                return Ok(());
+            }
+        }
+    }
+
+    /// Handle a key press. Returns true when the player quits.
+    fn on_key(&mut self, key: KeyCode) -> bool {
+        match &mut self.overlay {
+            Overlay::Scoreboard(history, selected) => {
+                let count = history.leaderboards().len().max(1);
+                match key {
+                    KeyCode::Left | KeyCode::Char('h') => {
+                        *selected = (*selected + count - 1) % count
+                    }
+                    KeyCode::Right | KeyCode::Char('l') => *selected = (*selected + 1) % count,
+                    KeyCode::Char('q') => return true,
+                    KeyCode::Esc | KeyCode::Char('w') => self.overlay = Overlay::None,
+                    _ => (),
+                }
+                return false;
+            }
+            Overlay::Outcome => match key {
+                KeyCode::Esc => {
+                    self.overlay = Overlay::None;
+                    return false;
+                }
+                KeyCode::Char('n' | 'w' | 'q') => (),
+                _ => return false,
+            },
+            Overlay::None => (),
+        }
+        match key {
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(1, 0),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(0, -1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(0, 1),
+            KeyCode::Char(' ' | 'f' | 'c' | '?') | KeyCode::Enter
+                if self.board.state().is_over() =>
+            {
+                self.status = "The game is over, press n for a new game".into()
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => self.open(self.cursor),
+            KeyCode::Char('f') => self.flag(self.cursor),
+            KeyCode::Char('c') => self.chord(self.cursor),
+            KeyCode::Char('?') => {
+                self.hint = self.board.hint();
+                self.status = match self.hint {
+                    Some(hint) => format!("Hint {}: {hint}", self.board.hints()),
+                    None => "No hint available".into(),
+                };
+            }
+            KeyCode::Char('u') => self.take_back(Board::undo),
+            KeyCode::Char('r') => self.take_back(Board::redo),
+            KeyCode::Char('n') => self.new_game(),
+            KeyCode::Char('w') => self.show_scoreboard(),
+            KeyCode::Char('s') => match save_game(self.dir, &self.board) {
+                Ok(()) if *self.board.state() == GameState::Active => {
+                    self.saved = true;
+                    return true;
+                }
+                Ok(()) => self.status = "Only a started game can be saved".into(),
+                Err(err) => self.status = format!("Failed to save game: {err}"),
+            },
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            _ => (),
+        }
+        false
+    }
+
+    /// Open, flag or chord the clicked cell.
+    fn on_mouse(&mut self, mouse: MouseEvent) {
+        let MouseEventKind::Down(button) = mouse.kind else {
+            return;
+        };
+        if !matches!(self.overlay, Overlay::None) || self.board.state().is_over() {
+            return;
+        }
+        let Some(pos) = self.cell_at(mouse.column, mouse.row) else {
+            return;
+        };
+        self.cursor = pos;
+        match button {
+            MouseButton::Left => self.open(pos),
+            MouseButton::Right => self.flag(pos),
+            MouseButton::Middle => self.chord(pos),
+        }
+    }
+
+    /// Cell drawn at the terminal column and row.
+    fn cell_at(&self, column: u16, row: u16) -> Option<Pos> {
+        let area = self.board_area;
+        if column < area.x || row < area.y {
+            return None;
+        }
+        let y = row - area.y + 1;
+        let indent = self.board.topology().indent(y) as u16;
+        let x = (column - area.x).checked_sub(indent)? / CELL_WIDTH + 1;
+        let pos = Pos::try_from((x, y)).ok()?;
+        self.board.get_pos(&pos).map(|_| pos)
+    }
+
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let step = |value: Coord, delta: i32, max: Coord| {
+            let moved = (i32::from(value.get()) + delta).clamp(1, i32::from(max.get()));
+            Coord::new(moved as u16).unwrap_or(value)
+        };
+        self.cursor = Pos {
+            x: step(self.cursor.x, dx, self.board.total_columns()),
+            y: step(self.cursor.y, dy, self.board.total_rows()),
+        };
+    }
+
+    fn open(&mut self, pos: Pos) {
+        self.moved();
+        let before = *self.board.state();
+        if let Err(err) = self.board.open_cell(pos) {
+            self.status = format!("Failed to generate board: {err}");
+        }
+        self.check_finished(before);
+    }
+
+    fn flag(&mut self, pos: Pos) {
+        self.moved();
+        self.board.flag_cell(pos);
+    }
+
+    fn chord(&mut self, pos: Pos) {
+        self.moved();
+        let before = *self.board.state();
+        self.board.chord(pos);
+        self.check_finished(before);
+    }
+
+    /// Undo or redo a move of a practice game.
+    fn take_back<T, E: std::fmt::Display>(&mut self, action: fn(&mut Board) -> Result<T, E>) {
+        self.moved();
+        if let Err(err) = action(&mut self.board) {
+            self.status = err.to_string();
+        }
+    }
+
+    /// Clear the hint and message of the previous move.
+    fn moved(&mut self) {
+        self.hint = None;
+        self.status.clear();
+    }
+
+    /// Save the replay and the win of a game that ended with the last move
+    /// and show its outcome. A game that was already over is not saved again.
+    fn check_finished(&mut self, before: GameState) {
+        let state = *self.board.state();
+        if before.is_over() || !state.is_over() {
+            return;
+        }
+        if let Err(err) = save_replay(self.dir, &Replay::new(&self.board)) {
+            self.status = format!("Failed to save replay: {err}");
+        }
+        if state == GameState::Win {
+            if let Err(err) = save_win(self.dir, &self.board) {
+                self.status = format!("Failed to save win: {err}");
+            }
+        }
+        self.overlay = Overlay::Outcome;
+    }
+
+    /// Count the game in the statistics unless it was saved to be resumed.
+    fn end_game(&mut self) {
+        if !self.saved {
+            if let Err(err) = record_game(self.dir, &self.board) {
+                self.status = format!("Failed to save statistics: {err}");
+            }
+        }
+    }
+
+    /// Start another game on the same board configuration.
+    fn new_game(&mut self) {
+        self.end_game();
+        let question_marks = self.board.question_marks();
+        self.board = Board::new(*self.board.config());
+        self.board.set_question_marks(question_marks);
+        self.board.resume_clock();
+        self.overlay = Overlay::None;
+        self.moved();
+    }
+
+    /// Show the wins of the leaderboard of the board.
+    fn show_scoreboard(&mut self) {
+        match load_wins(self.dir) {
+            Ok(history) => {
+                let selected = history
+                    .default_leaderboard(self.board.config())
+                    .and_then(|current| history.leaderboards().iter().position(|&l| l == current))
+                    .unwrap_or_default();
+                self.overlay = Overlay::Scoreboard(history, selected);
+            }
+            Err(err) => self.status = format!("Failed to load wins: {err}"),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let topology = self.board.topology();
+        let indent = (1..=self.board.total_rows().get())
+            .map(|y| topology.indent(y) as u16)
+            .max()
+            .unwrap_or_default();
+        let width = self
+            .board
+            .total_columns()
+            .get()
+            .saturating_mul(CELL_WIDTH)
+            .saturating_add(indent + 2);
+        let height = self.board.total_rows().get().saturating_add(2);
+        let [header, board, status, keys] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(height),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{}   Mines {:>3}   Time {:>4}   Hints {}",
+                self.board.config(),
+                self.board
+                    .config()
+                    .mines()
+                    .saturating_sub(self.board.flagged()),
+                self.board.elapsed().as_secs(),
+                self.board.hints()
+            ))
+            .alignment(Alignment::Center),
+            header,
+        );
+
+        let board = centered(board, width, height);
+        let block = Block::bordered();
+        self.board_area = block.inner(board);
+        let lines = (1..=self.board.total_rows().get())
+            .map(|y| {
+                let indent = Span::raw(" ".repeat(topology.indent(y)));
+                Line::from(
+                    [indent]
+                        .into_iter()
+                        .chain((1..=self.board.total_columns().get()).filter_map(|x| {
+                            let pos = Pos::try_from((x, y)).ok()?;
+                            let cell = self.board.get_pos(&pos)?;
+                            Some(self.cell_span(pos, cell))
+                        }))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        frame.render_widget(Paragraph::new(lines).block(block), board);
+
+        frame.render_widget(
+            Paragraph::new(self.status.as_str()).alignment(Alignment::Center),
+            status,
+        );
+        frame.render_widget(
+            Paragraph::new(KEYS)
+                .style(Style::new().fg(Color::DarkGray))
+                .alignment(Alignment::Center),
+            keys,
+        );
+
+        match &self.overlay {
+            Overlay::None => (),
+            Overlay::Outcome => self.draw_outcome(frame),
+            Overlay::Scoreboard(history, selected) => draw_scoreboard(frame, history, *selected),
+        }
+    }
+
+    /// Cell with a coloured number, mines shown once the game is lost.
+    fn cell_span(&self, pos: Pos, cell: &Cell) -> Span<'static> {
+        let lost = *self.board.state() == GameState::Loss;
+        let (text, style) = match cell.state {
+            CellState::Open if cell.adjacent_mines == 0 => ("   ".into(), Style::new()),
+            CellState::Open => (
+                format!(" {} ", cell.adjacent_mines),
+                Style::new()
+                    .fg(number_color(cell.adjacent_mines))
+                    .add_modifier(Modifier::BOLD),
+            ),
+            CellState::Closed { flagged: true, .. } => (
+                " F ".into(),
+                Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            CellState::Closed { mined: true, .. } if lost => {
+                (" * ".into(), Style::new().fg(Color::White))
+            }
+            CellState::Closed {
+                questioned: true, ..
+            } => (" ? ".into(), Style::new().fg(Color::Yellow)),
+            CellState::Closed { .. } => (" . ".into(), Style::new().fg(Color::DarkGray)),
+            CellState::ExposedMine => (" * ".into(), Style::new().fg(Color::White).bg(Color::Red)),
+        };
+        let style = match self.hint {
+            Some(hint) if hint.pos == pos => style.bg(match hint.verdict {
+                Verdict::Safe => Color::Green,
+                Verdict::Mine => Color::Red,
+            }),
+            _ => style,
+        };
+        let style = if pos == self.cursor {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        };
+        Span::styled(text, style)
+    }
+
+    fn draw_outcome(&self, frame: &mut Frame) {
+        let won = *self.board.state() == GameState::Win;
+        let mut lines = vec![
+            Line::styled(
+                if won { "You win!" } else { "You lose!" },
+                Style::new()
+                    .fg(if won { Color::Green } else { Color::Red })
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Line::from(format!(
+                "Time {:.3} seconds",
+                self.board.elapsed().as_secs_f64()
+            )),
+        ];
+        if let Some(metrics) = self.board.metrics() {
+            lines.push(Line::from(metrics.to_string()));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::styled(
+            "n new game, w wins, esc board, q quit",
+            Style::new().fg(Color::DarkGray),
+        ));
+        let area = centered(frame.area(), 70, lines.len() as u16 + 2);
+        frame.render_widget(Clear, area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .block(Block::bordered()),
+            area,
+        );
+    }
+}
+
+/// Wins of the leaderboard at the index, with the leaderboards to switch to.
+fn draw_scoreboard(frame: &mut Frame, history: &WinHistory, selected: usize) {
+    let leaderboards = history.leaderboards();
+    let area = centered(frame.area(), 76, 16);
+    let block = Block::bordered().title(Line::from(" Scoreboard ").centered());
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    let [picker, table, keys] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(inner);
+
+    let Some(&leaderboard) = leaderboards.get(selected) else {
+        frame.render_widget(
+            Paragraph::new("No wins yet").alignment(Alignment::Center),
+            table,
+        );
+        return;
+    };
+    frame.render_widget(
+        Paragraph::new(format!("< {leaderboard} >")).alignment(Alignment::Center),
+        picker,
+    );
+    let rows = history
+        .wins_on(leaderboard)
+        .into_iter()
+        .zip(1..)
+        .map(|(win, rank)| {
+            Row::new([
+                format!("{rank}."),
+                format!("{:.3}s", win.score().as_secs_f64()),
+                win.date.format("%b %d %Y %H:%M").to_string(),
+                win.player.clone().unwrap_or_default(),
+                match win.hints {
+                    0 => String::new(),
+                    hints => format!("{hints} hints"),
+                },
+            ])
+        });
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(4),
+                Constraint::Length(10),
+                Constraint::Length(18),
+                Constraint::Min(10),
+                Constraint::Length(9),
+            ],
+        )
+        .header(
+            Row::new(["", "Time", "Date", "Player", ""])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        ),
+        table,
+    );
+    frame.render_widget(
+        Paragraph::new("left/right leaderboard, esc close")
+            .style(Style::new().fg(Color::DarkGray))
+            .alignment(Alignment::Center),
+        keys,
+    );
+}
+
+/// Colour of the number of adjacent mines, as in the classic game.
+fn number_color(mines: u8) -> Color {
+    match mines {
+        1 => Color::LightBlue,
+        2 => Color::Green,
+        3 => Color::LightRed,
+        4 => Color::Blue,
+        5 => Color::Red,
+        6 => Color::Cyan,
+        7 => Color::Magenta,
+        _ => Color::Gray,
+    }
+}
+
+/// Area of at most the size in the middle of the area.
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,60 @@
+//! Boards on a topology defined outside the crate.
+use minesweeper::model::{Board, BoardConfig, CellState, Mines, Neighbours, Pos, Topology};
+use std::sync::Arc;
+
+/// Square grid without its corners where only the four cells sharing a side
+/// are neighbours.
+#[derive(Debug)]
+struct Plus;
+
+impl Topology for Plus {
+    fn neighbours(&self, pos: Pos, columns: u16, rows: u16) -> Neighbours {
+        let (x, y) = (pos.x.get(), pos.y.get());
+        let mut neighbours = Neighbours::default();
+        let sides = [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+        for side in sides
+            .into_iter()
+            .filter_map(|side| Pos::try_from(side).ok())
+        {
+            if self.contains(side, columns, rows) {
+                neighbours.push(side);
+            }
+        }
+        neighbours
+    }
+
+    fn contains(&self, pos: Pos, columns: u16, rows: u16) -> bool {
+        let (x, y) = (pos.x.get(), pos.y.get());
+        let corner = (x == 1 || x == columns) && (y == 1 || y == rows);
+        x <= columns && y <= rows && !corner
+    }
+}
+
+#[test]
+fn test_custom_topology() {
+    let size = 8.try_into().unwrap();
+    let config = BoardConfig::custom(size, size, Mines::Count(10)).unwrap();
+    let mut board = Board::with_topology(config, 3, Arc::new(Plus));
+    assert_eq!(board.board_size(), 60);
+
+    let first = Pos::try_from((4, 4)).unwrap();
+    board.open_cell(first).unwrap();
+    assert_eq!(board.mined(), 10);
+    assert_eq!(board.neighbours(first).count(), 4);
+
+    let mined = |pos: &Pos| {
+        board
+            .get_pos(pos)
+            .is_some_and(|cell| matches!(cell.state, CellState::Closed { mined: true, .. }))
+    };
+    for (pos, cell) in board.positions() {
+        let count = board.neighbours(pos).filter(mined).count();
+        assert_eq!(usize::from(cell.adjacent_mines), count, "{pos:?}");
+        // The flood fill opens the side neighbours of opened empty cells.
+        if cell.state == CellState::Open && cell.adjacent_mines == 0 {
+            assert!(board
+                .neighbours(pos)
+                .all(|adj| board.get_pos(&adj).unwrap().state == CellState::Open));
+        }
+    }
+}